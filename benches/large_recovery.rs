@@ -0,0 +1,44 @@
+//! Times the recovery of a large secret from many shares, which is dominated by checking that
+//! every share holds the same encrypted data
+//!
+//! Run with `cargo bench --bench large_recovery`
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+use distributed_encrypt::wrapper;
+
+const SECRET_SIZE: usize = 50 * 1024 * 1024;
+const SHARES: u8 = 10;
+
+fn large_recovery(c: &mut Criterion) {
+    let secret: Vec<u8> = (0..SECRET_SIZE).map(|i| (i % 251) as u8).collect();
+    let shares = wrapper::to_shares(secret, SHARES, SHARES).unwrap();
+
+    // Nearly all of a share is encrypted data, so a byte in the middle of the last share is one of
+    // it, which makes the check fail as late as it can
+    let mut mismatched = shares.clone();
+    let last = &mut mismatched[SHARES as usize - 1];
+    let middle = last.len() / 2;
+    last[middle] ^= 1;
+
+    let mut group = c.benchmark_group("10 shares of a 50 MB secret");
+    group.sample_size(10);
+    group.bench_function("recover", |b| {
+        b.iter_batched(
+            || shares.clone(),
+            |shares| wrapper::from_shares(shares).unwrap(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("reject mismatched data", |b| {
+        b.iter_batched(
+            || mismatched.clone(),
+            |shares| wrapper::from_shares(shares).unwrap_err(),
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, large_recovery);
+criterion_main!(benches);
@@ -0,0 +1,54 @@
+//! Typed errors as JSON responses, run with `cargo run --example axum_error_handler --features serde`
+//!
+//! ```sh
+//! curl -i --data-binary @two-shares.bin localhost:3000/combine
+//! # HTTP/1.1 422 Unprocessable Entity
+//! # {"error":"NotEnoughShares","have":2,"need":3}
+//! ```
+
+use axum::{
+    body::Bytes,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+
+use distributed_encrypt::error::ShamirError;
+use distributed_encrypt::wrapper::from_shares;
+
+/// The service only combines the shares of its 3 of n splits
+const THRESHOLD: usize = 3;
+
+async fn combine(body: Bytes) -> Result<Vec<u8>, Response> {
+    let shares: Vec<Vec<u8>> = bincode::deserialize(&body)
+        .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()).into_response())?;
+    if shares.len() < THRESHOLD {
+        let error = ShamirError::NotEnoughShares {
+            have: shares.len(),
+            need: Some(THRESHOLD),
+        };
+        return Err((StatusCode::UNPROCESSABLE_ENTITY, Json(error)).into_response());
+    }
+
+    from_shares(shares).map_err(error_response)
+}
+
+/// Typed errors are sent as JSON, anything else as plain text
+fn error_response(error: anyhow::Error) -> Response {
+    match error.downcast_ref::<ShamirError>() {
+        Some(typed) => (StatusCode::UNPROCESSABLE_ENTITY, Json(*typed)).into_response(),
+        None => (StatusCode::BAD_REQUEST, error.to_string()).into_response(),
+    }
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let app = Router::new().route("/combine", post(combine));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+    println!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,51 @@
+//! Splitting and combining over HTTP, run with `cargo run --example axum_handler --features bytes`
+//!
+//! ```sh
+//! curl --data-binary @secret.txt "localhost:3000/split?threshold=2&count=3" > shares.bin
+//! curl --data-binary @shares.bin localhost:3000/combine
+//! ```
+
+use axum::{body::Bytes, extract::Query, http::StatusCode, routing::post, Router};
+use serde::Deserialize;
+
+use distributed_encrypt::wrapper::{from_shares_bytes, to_shares_bytes};
+
+#[derive(Deserialize)]
+struct SplitParams {
+    threshold: u8,
+    count: u8,
+}
+
+async fn split(
+    Query(params): Query<SplitParams>,
+    body: Bytes,
+) -> Result<Vec<u8>, (StatusCode, String)> {
+    // The request body is handed over as is, no copy into a Vec is needed
+    let shares = to_shares_bytes(body, params.threshold, params.count).map_err(bad_request)?;
+
+    // All shares are sent back in one response, as a bincode encoded list
+    bincode::serialize(&shares.iter().map(|s| s.as_ref()).collect::<Vec<&[u8]>>())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+async fn combine(body: Bytes) -> Result<Bytes, (StatusCode, String)> {
+    let shares: Vec<Vec<u8>> = bincode::deserialize(&body).map_err(bad_request)?;
+    from_shares_bytes(shares.into_iter().map(Bytes::from).collect()).map_err(bad_request)
+}
+
+fn bad_request(error: impl ToString) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, error.to_string())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/split", post(split))
+        .route("/combine", post(combine));
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:3000").await?;
+    println!("Listening on {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+//! Prints every share of a secret as a QR code PNG image, then scans them back to recover it
+//!
+//! Run with `cargo run --example qr_share`, the images are written to the temporary folder
+
+use anyhow::Result;
+use image::Luma;
+use qrcode::{EcLevel, QrCode};
+use std::fs;
+
+use distributed_encrypt::{qr, wrapper};
+
+const SECRET: &[u8] = b"correct horse battery staple";
+
+fn main() -> Result<()> {
+    let shares = wrapper::to_shares(SECRET.to_vec(), 2, 3)?;
+    let folder = std::env::temp_dir().join("qr_share");
+    fs::create_dir_all(&folder)?;
+
+    let mut paths = Vec::new();
+    for (i, share) in shares.iter().enumerate() {
+        let data = qr::to_qr_data(share)?;
+        let code = QrCode::with_error_correction_level(&data, EcLevel::M)?;
+        let image = code.render::<Luma<u8>>().module_dimensions(4, 4).build();
+
+        let path = folder.join(format!("share{}.png", i));
+        image.save(&path)?;
+        println!(
+            "Share {} is {} bytes, {} in its QR code, written to \"{}\"",
+            i,
+            share.len(),
+            data.len(),
+            path.display()
+        );
+        paths.push(path);
+    }
+
+    // Scan the first and the last image back in
+    let mut scanned = Vec::new();
+    for path in [&paths[0], &paths[2]] {
+        let image = image::open(path)?.to_luma8();
+        let mut prepared = rqrr::PreparedImage::prepare(image);
+        for grid in prepared.detect_grids() {
+            let mut data = Vec::new();
+            grid.decode_to(&mut data)?;
+            scanned.push(qr::from_qr_data(&data)?);
+        }
+    }
+
+    let recovered = wrapper::from_shares(scanned)?;
+    assert_eq!(recovered, SECRET);
+    println!("Recovered \"{}\"", String::from_utf8(recovered)?);
+
+    Ok(())
+}
@@ -0,0 +1,46 @@
+#![no_main]
+
+//! Feeds arbitrary bytes to the share parsing and recovery, run with `cargo +nightly fuzz run
+//! from_shares`
+//!
+//! The input is split into shares, each prefixed by its length as a big endian u16. Nothing may
+//! panic, a share `validate_share` rejects must fail recovery with a `ShamirError`, and so must
+//! any share `unframe_share` rejects.
+
+use distributed_encrypt::error::ShamirError;
+use distributed_encrypt::wrapper;
+use libfuzzer_sys::fuzz_target;
+
+fn split_shares(mut data: &[u8]) -> Vec<Vec<u8>> {
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    while data.len() >= 2 {
+        let length = (u16::from_be_bytes([data[0], data[1]]) as usize).min(data.len() - 2);
+        shares.push(data[2..2 + length].to_vec());
+        data = &data[2 + length..];
+    }
+    shares
+}
+
+fuzz_target!(|data: &[u8]| {
+    let shares = split_shares(data);
+
+    let malformed = shares.iter().any(|s| wrapper::validate_share(s).is_err());
+    for share in shares.iter() {
+        wrapper::share_header(share);
+        if let Err(e) = wrapper::unframe_share(share) {
+            assert!(e.downcast_ref::<ShamirError>().is_some(), "{:#}", e);
+        }
+    }
+    wrapper::exclude_damaged_shares(shares.clone());
+    let _ = wrapper::analyze_shares(&shares);
+
+    if let Err(e) = wrapper::from_shares(shares) {
+        assert!(
+            !malformed || e.downcast_ref::<ShamirError>().is_some(),
+            "{:#}",
+            e
+        );
+    } else {
+        assert!(!malformed);
+    }
+});
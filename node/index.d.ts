@@ -0,0 +1,19 @@
+/* tslint:disable */
+/* eslint-disable */
+
+/* auto-generated by NAPI-RS */
+
+export interface SplitOptions {
+  /** Number of shares needed to recover the data */
+  threshold: number
+  /** Number of shares to create */
+  shares: number
+}
+/** Splits `data` into shares, any `threshold` of which recover it */
+export function split(data: Buffer, options: SplitOptions): Array<Buffer>
+/** Recovers the data from its shares */
+export function combine(shares: Array<Buffer>): Buffer
+/** Same as `split`, but runs on the libuv thread pool instead of blocking the event loop */
+export function splitAsync(data: Buffer, options: SplitOptions): Promise<Array<Buffer>>
+/** Same as `combine`, but runs on the libuv thread pool instead of blocking the event loop */
+export function combineAsync(shares: Array<Buffer>): Promise<Buffer>
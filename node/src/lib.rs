@@ -0,0 +1,142 @@
+use distributed_encrypt::error::ShamirError;
+use distributed_encrypt::shamir;
+use distributed_encrypt::wrapper;
+use napi::bindgen_prelude::*;
+use napi::{Env, JsUnknown, Task};
+use napi_derive::napi;
+
+#[napi(object)]
+pub struct SplitOptions {
+    /// Number of shares needed to recover the data
+    pub threshold: u32,
+    /// Number of shares to create
+    pub shares: u32,
+}
+
+/// The `code` set on thrown errors, so callers can tell the typed errors apart
+fn error_code(e: &anyhow::Error) -> &'static str {
+    match e.downcast_ref::<ShamirError>() {
+        Some(ShamirError::InconsistentShares) => "ERR_INCONSISTENT_SHARES",
+        Some(ShamirError::NotEnoughShares { .. }) => "ERR_NOT_ENOUGH_SHARES",
+        Some(ShamirError::EmptySecret) => "ERR_EMPTY_SECRET",
+        Some(ShamirError::RngUnavailable) => "ERR_RNG_UNAVAILABLE",
+        Some(ShamirError::UnsupportedParameters) => "ERR_UNSUPPORTED_PARAMETERS",
+        Some(ShamirError::ShareTampered) => "ERR_SHARE_TAMPERED",
+        Some(ShamirError::SecretTooLarge { .. }) => "ERR_SECRET_TOO_LARGE",
+        Some(ShamirError::ShareIndexOutOfRange { .. }) => "ERR_SHARE_INDEX_OUT_OF_RANGE",
+        Some(ShamirError::TooManyShares { .. }) => "ERR_TOO_MANY_SHARES",
+        Some(ShamirError::TruncatedHeader) => "ERR_TRUNCATED_HEADER",
+        Some(ShamirError::LengthOutOfBounds) => "ERR_LENGTH_OUT_OF_BOUNDS",
+        Some(ShamirError::UnsupportedVersion) => "ERR_UNSUPPORTED_VERSION",
+        Some(ShamirError::BadMagic) => "ERR_BAD_MAGIC",
+        Some(ShamirError::ChecksumMismatch) => "ERR_CHECKSUM_MISMATCH",
+        Some(ShamirError::BufferTooSmall { .. }) => "ERR_BUFFER_TOO_SMALL",
+        Some(ShamirError::ShareExpired { .. }) => "ERR_SHARE_EXPIRED",
+        Some(ShamirError::TruncatedCiphertext { .. }) => "ERR_TRUNCATED_CIPHERTEXT",
+        Some(ShamirError::QuorumNotMet { .. }) => "ERR_QUORUM_NOT_MET",
+        None => "ERR_SHAMIR",
+    }
+}
+
+/// Creates a JS `Error` with the message and `code` of the given error
+fn to_js_error(env: Env, e: anyhow::Error) -> Error {
+    let code = error_code(&e);
+    let create = || -> Result<JsUnknown> {
+        let mut error = env.create_error(Error::from_reason(e.to_string()))?;
+        error.set_named_property("code", env.create_string(code)?)?;
+        Ok(error.into_unknown())
+    };
+
+    match create() {
+        Ok(error) => Error::from(error),
+        Err(e) => e,
+    }
+}
+
+fn split_parameters(env: Env, options: &SplitOptions) -> Result<(u8, u8)> {
+    let threshold = u8::try_from(options.threshold)
+        .map_err(|_| Error::from_reason("Threshold must be at most 255"))?;
+    let count =
+        shamir::checked_share_count(options.shares as usize).map_err(|e| to_js_error(env, e))?;
+    Ok((threshold, count))
+}
+
+/// Splits `data` into shares, any `threshold` of which recover it
+#[napi]
+pub fn split(env: Env, data: Buffer, options: SplitOptions) -> Result<Vec<Buffer>> {
+    let (threshold, count) = split_parameters(env, &options)?;
+    let shares = wrapper::to_shares(data.to_vec(), threshold, count)
+        .map_err(|e| to_js_error(env, e))?;
+
+    Ok(shares.into_iter().map(Buffer::from).collect())
+}
+
+/// Recovers the data from its shares
+#[napi]
+pub fn combine(env: Env, shares: Vec<Buffer>) -> Result<Buffer> {
+    let shares = shares.iter().map(|s| s.to_vec()).collect();
+    let data = wrapper::from_shares(shares).map_err(|e| to_js_error(env, e))?;
+
+    Ok(data.into())
+}
+
+pub struct SplitTask {
+    data: Vec<u8>,
+    threshold: u8,
+    count: u8,
+}
+
+impl Task for SplitTask {
+    type Output = anyhow::Result<Vec<Vec<u8>>>;
+    type JsValue = Vec<Buffer>;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(wrapper::to_shares(
+            std::mem::take(&mut self.data),
+            self.threshold,
+            self.count,
+        ))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let shares = output.map_err(|e| to_js_error(env, e))?;
+        Ok(shares.into_iter().map(Buffer::from).collect())
+    }
+}
+
+/// Same as `split`, but runs on the libuv thread pool instead of blocking the event loop
+#[napi(ts_return_type = "Promise<Array<Buffer>>")]
+pub fn split_async(env: Env, data: Buffer, options: SplitOptions) -> Result<AsyncTask<SplitTask>> {
+    let (threshold, count) = split_parameters(env, &options)?;
+    Ok(AsyncTask::new(SplitTask {
+        data: data.to_vec(),
+        threshold,
+        count,
+    }))
+}
+
+pub struct CombineTask {
+    shares: Vec<Vec<u8>>,
+}
+
+impl Task for CombineTask {
+    type Output = anyhow::Result<Vec<u8>>;
+    type JsValue = Buffer;
+
+    fn compute(&mut self) -> Result<Self::Output> {
+        Ok(wrapper::from_shares(std::mem::take(&mut self.shares)))
+    }
+
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let data = output.map_err(|e| to_js_error(env, e))?;
+        Ok(data.into())
+    }
+}
+
+/// Same as `combine`, but runs on the libuv thread pool instead of blocking the event loop
+#[napi(ts_return_type = "Promise<Buffer>")]
+pub fn combine_async(shares: Vec<Buffer>) -> AsyncTask<CombineTask> {
+    AsyncTask::new(CombineTask {
+        shares: shares.iter().map(|s| s.to_vec()).collect(),
+    })
+}
@@ -0,0 +1,39 @@
+// Tests of the built addon, run with `npm run build && npm test`
+
+const assert = require('node:assert/strict')
+const { randomBytes } = require('node:crypto')
+const { test } = require('node:test')
+
+const shamir = require('..')
+
+test('shares round trip', () => {
+  const secret = Buffer.from('secret')
+  const shares = shamir.split(secret, { threshold: 2, shares: 3 })
+  assert.equal(shares.length, 3)
+  assert.deepEqual(shamir.combine([shares[0], shares[2]]), secret)
+  assert.deepEqual(shamir.combine(shares.slice(1)), secret)
+})
+
+test('shares of a large input round trip on the thread pool', async () => {
+  const secret = randomBytes(10 * 1024 * 1024)
+  const shares = await shamir.splitAsync(secret, { threshold: 3, shares: 5 })
+  assert.deepEqual(await shamir.combineAsync(shares.slice(2)), secret)
+})
+
+test('no shares fail with ERR_NOT_ENOUGH_SHARES', () => {
+  assert.throws(() => shamir.combine([]), { code: 'ERR_NOT_ENOUGH_SHARES' })
+})
+
+test('too few shares fail with ERR_INCONSISTENT_SHARES', async () => {
+  const shares = shamir.split(Buffer.from('secret'), { threshold: 3, shares: 5 })
+  const inconsistent = { code: 'ERR_INCONSISTENT_SHARES' }
+  assert.throws(() => shamir.combine(shares.slice(0, 2)), inconsistent)
+  await assert.rejects(shamir.combineAsync(shares.slice(0, 2)), inconsistent)
+})
+
+test('a cut short share fails with ERR_TRUNCATED_HEADER', () => {
+  const shares = shamir.split(Buffer.from('secret'), { threshold: 2, shares: 3 })
+  assert.throws(() => shamir.combine([shares[0], shares[1].subarray(0, -3)]), {
+    code: 'ERR_TRUNCATED_HEADER',
+  })
+})
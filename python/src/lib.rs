@@ -0,0 +1,135 @@
+use distributed_encrypt::error::ShamirError;
+use distributed_encrypt::wrapper;
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+create_exception!(shamir, Error, PyValueError, "Base class of all errors raised by shamir");
+create_exception!(shamir, InconsistentSharesError, Error, "The shares do not belong together");
+create_exception!(shamir, NotEnoughSharesError, Error, "Too few shares to recover the secret");
+create_exception!(shamir, EmptySecretError, Error, "There is nothing to split");
+create_exception!(shamir, RngUnavailableError, Error, "The system has no randomness to give");
+create_exception!(shamir, UnsupportedParametersError, Error, "The share format can't do this");
+create_exception!(shamir, ShareTamperedError, Error, "A share was changed after it was created");
+create_exception!(shamir, SecretTooLargeError, Error, "The shares claim a too large secret");
+create_exception!(shamir, ShareIndexOutOfRangeError, Error, "The secret has no share of that id");
+create_exception!(shamir, TooManySharesError, Error, "More shares than a secret can be split into");
+create_exception!(shamir, TruncatedHeaderError, Error, "The share is cut short");
+create_exception!(shamir, LengthOutOfBoundsError, Error, "A length in the share does not fit it");
+create_exception!(shamir, UnsupportedVersionError, Error, "The share is of an unknown version");
+create_exception!(shamir, BadMagicError, Error, "The bytes are not a framed share");
+create_exception!(shamir, ChecksumMismatchError, Error, "The framed share was damaged");
+create_exception!(shamir, BufferTooSmallError, Error, "The buffer to write into is too small");
+create_exception!(shamir, ShareExpiredError, Error, "The shares are past their expiry");
+create_exception!(shamir, TruncatedCiphertextError, Error, "The encrypted data is cut short");
+create_exception!(shamir, QuorumNotMetError, Error, "Fewer shares than the quorum requires");
+
+/// Maps the typed errors to their own exception, everything else becomes a plain `shamir.Error`
+fn to_py_err(e: anyhow::Error) -> PyErr {
+    let message = e.to_string();
+    match e.downcast_ref::<ShamirError>() {
+        Some(ShamirError::InconsistentShares) => InconsistentSharesError::new_err(message),
+        Some(ShamirError::NotEnoughShares { .. }) => NotEnoughSharesError::new_err(message),
+        Some(ShamirError::EmptySecret) => EmptySecretError::new_err(message),
+        Some(ShamirError::RngUnavailable) => RngUnavailableError::new_err(message),
+        Some(ShamirError::UnsupportedParameters) => UnsupportedParametersError::new_err(message),
+        Some(ShamirError::ShareTampered) => ShareTamperedError::new_err(message),
+        Some(ShamirError::SecretTooLarge { .. }) => SecretTooLargeError::new_err(message),
+        Some(ShamirError::ShareIndexOutOfRange { .. }) => {
+            ShareIndexOutOfRangeError::new_err(message)
+        }
+        Some(ShamirError::TooManyShares { .. }) => TooManySharesError::new_err(message),
+        Some(ShamirError::TruncatedHeader) => TruncatedHeaderError::new_err(message),
+        Some(ShamirError::LengthOutOfBounds) => LengthOutOfBoundsError::new_err(message),
+        Some(ShamirError::UnsupportedVersion) => UnsupportedVersionError::new_err(message),
+        Some(ShamirError::BadMagic) => BadMagicError::new_err(message),
+        Some(ShamirError::ChecksumMismatch) => ChecksumMismatchError::new_err(message),
+        Some(ShamirError::BufferTooSmall { .. }) => BufferTooSmallError::new_err(message),
+        Some(ShamirError::ShareExpired { .. }) => ShareExpiredError::new_err(message),
+        Some(ShamirError::TruncatedCiphertext { .. }) => TruncatedCiphertextError::new_err(message),
+        Some(ShamirError::QuorumNotMet { .. }) => QuorumNotMetError::new_err(message),
+        None => Error::new_err(message),
+    }
+}
+
+/// Splits `data` into `count` shares, any `threshold` of which recover it
+#[pyfunction]
+fn split<'py>(
+    py: Python<'py>,
+    data: &[u8],
+    threshold: u8,
+    count: u8,
+) -> PyResult<Vec<Bound<'py, PyBytes>>> {
+    let data = data.to_vec();
+    let shares = py
+        .detach(move || wrapper::to_shares(data, threshold, count))
+        .map_err(to_py_err)?;
+
+    Ok(shares.iter().map(|s| PyBytes::new(py, s)).collect())
+}
+
+/// Recovers the data from a list of shares
+#[pyfunction]
+fn combine<'py>(
+    py: Python<'py>,
+    shares: Vec<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let shares = shares
+        .iter()
+        .map(|s| s.as_bytes().to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let data = py
+        .detach(move || wrapper::from_shares(shares))
+        .map_err(to_py_err)?;
+
+    Ok(PyBytes::new(py, &data))
+}
+
+#[pymodule]
+fn shamir(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add_function(wrap_pyfunction!(split, m)?)?;
+    m.add_function(wrap_pyfunction!(combine, m)?)?;
+    m.add("Error", py.get_type::<Error>())?;
+    m.add(
+        "InconsistentSharesError",
+        py.get_type::<InconsistentSharesError>(),
+    )?;
+    m.add("NotEnoughSharesError", py.get_type::<NotEnoughSharesError>())?;
+    m.add("EmptySecretError", py.get_type::<EmptySecretError>())?;
+    m.add("RngUnavailableError", py.get_type::<RngUnavailableError>())?;
+    m.add(
+        "UnsupportedParametersError",
+        py.get_type::<UnsupportedParametersError>(),
+    )?;
+    m.add("ShareTamperedError", py.get_type::<ShareTamperedError>())?;
+    m.add("SecretTooLargeError", py.get_type::<SecretTooLargeError>())?;
+    m.add(
+        "ShareIndexOutOfRangeError",
+        py.get_type::<ShareIndexOutOfRangeError>(),
+    )?;
+    m.add("TooManySharesError", py.get_type::<TooManySharesError>())?;
+    m.add("TruncatedHeaderError", py.get_type::<TruncatedHeaderError>())?;
+    m.add(
+        "LengthOutOfBoundsError",
+        py.get_type::<LengthOutOfBoundsError>(),
+    )?;
+    m.add(
+        "UnsupportedVersionError",
+        py.get_type::<UnsupportedVersionError>(),
+    )?;
+    m.add("BadMagicError", py.get_type::<BadMagicError>())?;
+    m.add(
+        "ChecksumMismatchError",
+        py.get_type::<ChecksumMismatchError>(),
+    )?;
+    m.add("BufferTooSmallError", py.get_type::<BufferTooSmallError>())?;
+    m.add("ShareExpiredError", py.get_type::<ShareExpiredError>())?;
+    m.add(
+        "TruncatedCiphertextError",
+        py.get_type::<TruncatedCiphertextError>(),
+    )?;
+    m.add("QuorumNotMetError", py.get_type::<QuorumNotMetError>())?;
+    Ok(())
+}
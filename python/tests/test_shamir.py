@@ -0,0 +1,65 @@
+"""Tests of the shamir module, run with `maturin develop && pytest` from the python folder"""
+
+import itertools
+import os
+
+import pytest
+
+import shamir
+
+
+def test_round_trip():
+    shares = shamir.split(b"secret", 2, 3)
+    assert len(shares) == 3
+    for pair in itertools.combinations(shares, 2):
+        assert shamir.combine(list(pair)) == b"secret"
+
+
+def test_round_trip_of_all_shares():
+    shares = shamir.split(b"secret", 3, 5)
+    assert shamir.combine(shares) == b"secret"
+
+
+def test_round_trip_of_large_input():
+    data = os.urandom(10 * 1024 * 1024)
+    shares = shamir.split(data, 3, 5)
+    assert shamir.combine(shares[2:]) == data
+
+
+def test_errors_are_typed():
+    for error in [
+        shamir.NotEnoughSharesError,
+        shamir.InconsistentSharesError,
+        shamir.TruncatedHeaderError,
+    ]:
+        assert issubclass(error, shamir.Error)
+    assert issubclass(shamir.Error, ValueError)
+
+
+def test_no_shares():
+    with pytest.raises(shamir.NotEnoughSharesError):
+        shamir.combine([])
+
+
+def test_too_few_shares():
+    shares = shamir.split(b"secret", 3, 5)
+    with pytest.raises(shamir.InconsistentSharesError):
+        shamir.combine(shares[:2])
+
+
+def test_shares_of_different_secrets():
+    first = shamir.split(b"secret", 2, 3)
+    second = shamir.split(b"secret", 2, 3)
+    with pytest.raises(shamir.Error):
+        shamir.combine([first[0], second[1]])
+
+
+def test_cut_short_share():
+    shares = shamir.split(b"secret", 2, 3)
+    with pytest.raises(shamir.TruncatedHeaderError):
+        shamir.combine([shares[0], shares[1][:-3]])
+
+
+def test_threshold_above_share_count():
+    with pytest.raises(shamir.Error):
+        shamir.split(b"secret", 4, 3)
@@ -0,0 +1,118 @@
+use anyhow::Result;
+use rand::RngCore;
+
+use crate::error::ShamirError;
+use crate::shamir::secure_rng;
+
+/// Splits a secret into `count` shares that are all needed to recover it
+///
+/// All but one share are random pads, and the last one is the secret XOR-ed with all of them. Every
+/// share starts with its index and the number of shares, so missing shares can be detected.
+pub fn split_secret(secret: &[u8], count: u8) -> Result<Vec<Vec<u8>>> {
+    if count < 2 {
+        return Err(anyhow::anyhow!("Additive sharing needs at least 2 shares"));
+    }
+
+    let mut rng = secure_rng()?;
+    let mut last: Vec<u8> = secret.to_vec();
+    let mut shares: Vec<Vec<u8>> = vec![];
+    for i in 1..count {
+        let mut pad = vec![0u8; secret.len()];
+        rng.fill_bytes(&mut pad);
+        for (l, p) in last.iter_mut().zip(pad.iter()) {
+            *l ^= p;
+        }
+
+        let mut share = vec![i, count];
+        share.append(&mut pad);
+        shares.push(share);
+    }
+
+    let mut share = vec![count, count];
+    share.append(&mut last);
+    shares.push(share);
+
+    Ok(shares)
+}
+
+/// Recovers a secret split with `split_secret`, which needs every single share
+pub fn recover_secret(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let count = match shares.first() {
+        Some(share) if share.len() >= 2 => share[1],
+        _ => return Err(ShamirError::InconsistentShares.into()),
+    };
+
+    let mut indices: Vec<u8> = vec![];
+    for share in shares.iter() {
+        if share.len() != shares[0].len() || share[1] != count {
+            return Err(ShamirError::InconsistentShares.into());
+        }
+
+        if share[0] == 0 || share[0] > count || indices.contains(&share[0]) {
+            return Err(anyhow::anyhow!("Multiple shares with the same first byte"));
+        }
+
+        indices.push(share[0]);
+    }
+
+    if shares.len() < count as usize {
+        return Err(ShamirError::NotEnoughShares {
+            have: shares.len(),
+            need: Some(count as usize),
+        }
+        .into());
+    }
+
+    let mut secret = vec![0u8; shares[0].len() - 2];
+    for share in shares.iter() {
+        for (s, b) in secret.iter_mut().zip(share[2..].iter()) {
+            *s ^= b;
+        }
+    }
+
+    Ok(secret)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper::{self, Scheme};
+
+    #[test]
+    fn every_share_recovers_the_secret() {
+        let shares = split_secret(b"secret", 3).unwrap();
+        assert_eq!(shares.len(), 3);
+        assert_eq!(recover_secret(shares.clone()).unwrap(), b"secret");
+
+        let error = recover_secret(shares[..2].to_vec()).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::NotEnoughShares {
+                have: 2,
+                need: Some(3)
+            })
+        );
+    }
+
+    #[test]
+    fn all_required_splits_are_additive() {
+        assert_eq!(Scheme::for_threshold(3, 3), Scheme::Additive);
+        assert_eq!(Scheme::for_threshold(2, 3), Scheme::Shamir);
+
+        let shares = wrapper::to_shares(b"secret".to_vec(), 3, 3).unwrap();
+        assert_eq!(wrapper::from_shares(shares.clone()).unwrap(), b"secret");
+
+        for left_out in 0..3 {
+            let mut fewer = shares.clone();
+            fewer.remove(left_out);
+            let error = wrapper::from_shares(fewer).unwrap_err();
+            assert_eq!(
+                error.downcast_ref::<ShamirError>(),
+                Some(&ShamirError::NotEnoughShares {
+                    have: 2,
+                    need: Some(3)
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,111 @@
+use anyhow::Result;
+use bech32::{Bech32, Hrp};
+use serde::{Deserialize, Serialize};
+use x25519_dalek::{PublicKey, StaticSecret};
+
+const IDENTITY_PREFIX: &str = "AGE-SECRET-KEY-1";
+
+/// An age X25519 identity, along with the recipient it had at split time
+#[derive(Debug, Serialize, Deserialize)]
+struct AgeIdentity {
+    key: [u8; 32],
+    recipient: String,
+}
+
+/// Lists the recipients of all identities in an age identity file, in order
+pub fn recipients(identity_file: &str) -> Result<Vec<String>> {
+    let mut recipients: Vec<String> = vec![];
+    for line in identity_lines(identity_file) {
+        recipients.push(recipient(&parse_identity(line)?)?);
+    }
+    Ok(recipients)
+}
+
+/// Picks one identity from an age identity file, and returns the payload to share for it
+///
+/// `select` is either the 1-based position of the identity in the file, or its recipient, and can
+/// be left out for files with a single identity
+pub fn to_payload(identity_file: &str, select: Option<&str>) -> Result<Vec<u8>> {
+    let lines = identity_lines(identity_file);
+    if lines.is_empty() {
+        return Err(anyhow::anyhow!("File does not contain an age identity"));
+    }
+
+    let line = match select {
+        None if lines.len() == 1 => lines[0],
+        None => {
+            return Err(anyhow::anyhow!(
+                "File contains {} identities, pick one with --select: {}",
+                lines.len(),
+                recipients(identity_file)?.join(", ")
+            ))
+        }
+        Some(select) => match select.parse::<usize>() {
+            Ok(position) => *position
+                .checked_sub(1)
+                .and_then(|p| lines.get(p))
+                .ok_or_else(|| anyhow::anyhow!("There is no identity number {}", position))?,
+            Err(_) => {
+                let position = recipients(identity_file)?
+                    .iter()
+                    .position(|r| r == select)
+                    .ok_or_else(|| anyhow::anyhow!("No identity has recipient \"{}\"", select))?;
+                lines[position]
+            }
+        },
+    };
+
+    let key = parse_identity(line)?;
+    let identity = AgeIdentity {
+        key,
+        recipient: recipient(&key)?,
+    };
+
+    Ok(bincode::serialize(&identity)?)
+}
+
+/// Recreates the identity file from a recovered payload, checking it still has the same recipient
+pub fn from_payload(payload: &[u8]) -> Result<String> {
+    let identity: AgeIdentity = bincode::deserialize(payload)
+        .map_err(|_| anyhow::anyhow!("Recovered data is not an age identity"))?;
+
+    let recipient = recipient(&identity.key)?;
+    if recipient != identity.recipient {
+        return Err(anyhow::anyhow!(
+            "Recovered identity has recipient \"{}\", but \"{}\" was recorded when splitting",
+            recipient,
+            identity.recipient
+        ));
+    }
+
+    let hrp = Hrp::parse("age-secret-key-")?;
+    let line = bech32::encode::<Bech32>(hrp, &identity.key)?.to_ascii_uppercase();
+
+    Ok(format!("# public key: {}\n{}\n", recipient, line))
+}
+
+fn identity_lines(identity_file: &str) -> Vec<&str> {
+    identity_file
+        .lines()
+        .map(|l| l.trim())
+        .filter(|l| l.starts_with(IDENTITY_PREFIX))
+        .collect()
+}
+
+fn parse_identity(line: &str) -> Result<[u8; 32]> {
+    let (hrp, data) = bech32::decode(line)
+        .map_err(|e| anyhow::anyhow!("Invalid age identity: {}", e))?;
+
+    if hrp.to_lowercase() != "age-secret-key-" {
+        return Err(anyhow::anyhow!("Invalid age identity: unexpected prefix"));
+    }
+
+    data.try_into()
+        .map_err(|_| anyhow::anyhow!("Invalid age identity: key is not 32 bytes"))
+}
+
+fn recipient(key: &[u8; 32]) -> Result<String> {
+    let public = PublicKey::from(&StaticSecret::from(*key));
+    let hrp = Hrp::parse("age")?;
+    Ok(bech32::encode::<Bech32>(hrp, public.as_bytes())?)
+}
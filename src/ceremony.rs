@@ -0,0 +1,325 @@
+use anyhow::Result;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use sha3::{Digest, Sha3_256};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
+
+use crate::error::ShamirError;
+use crate::shamir;
+use crate::wrapper::{
+    self, share_expiry, share_header, share_index, share_set_id, Scheme, FORMAT_VERSION,
+};
+
+/// A description of the shares of a split ceremony, to archive for audits without keeping the
+/// shares or the secret
+///
+/// The hash of the secret lets anyone holding the description test guesses of the secret offline,
+/// like a `RecoveryProof`, so only archive it for secrets that can't be guessed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareSetDescription {
+    /// The first 16 bytes of the set id of the shares, see `wrapper::share_set_id`
+    #[serde(with = "hex")]
+    pub set_id: [u8; 16],
+    /// When the description was made, in seconds since the Unix epoch
+    pub created_at: u64,
+    pub threshold: u8,
+    pub count: u8,
+    /// Who was given each share, in the order of the shares
+    pub trustee_labels: Vec<String>,
+    /// The sharing scheme, cipher, hash and share format version the shares were made with
+    pub algorithm_suite: String,
+    /// SHA3-256 hash of the secret
+    #[serde(with = "hex")]
+    pub secret_hash: [u8; 32],
+}
+
+impl ShareSetDescription {
+    /// Describes all shares of a split made by `to_shares`, with the label of the trustee of every
+    /// share in the same order
+    ///
+    /// The secret is recovered from the shares to hash it, which also checks that they are all of
+    /// one intact split.
+    pub fn new(
+        shares: &[Vec<u8>],
+        threshold: u8,
+        trustee_labels: Vec<String>,
+    ) -> Result<ShareSetDescription> {
+        if trustee_labels.len() != shares.len() {
+            return Err(anyhow::anyhow!(
+                "There are {} trustee labels for {} shares",
+                trustee_labels.len(),
+                shares.len()
+            ));
+        }
+
+        let count = shamir::checked_share_count(shares.len())?;
+        if threshold == 0 || threshold > count {
+            return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+                .context("Threshold must be between 1 and the number of shares"));
+        }
+
+        let set_id = shares.first().and_then(|share| share_set_id(share)).ok_or(
+            ShamirError::NotEnoughShares {
+                have: 0,
+                need: None,
+            },
+        )?;
+        let scheme = shares
+            .first()
+            .and_then(|share| share_header(share).scheme)
+            .ok_or(ShamirError::InconsistentShares)?;
+
+        let mut secret = wrapper::from_shares(shares.to_vec())?;
+        let secret_hash = Sha3_256::digest(&secret).into();
+        secret.zeroize();
+
+        Ok(ShareSetDescription {
+            set_id: set_id[..16].try_into().unwrap(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            threshold,
+            count,
+            trustee_labels,
+            algorithm_suite: algorithm_suite(scheme),
+            secret_hash,
+        })
+    }
+
+    /// The description as a JSON record
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A description always serializes")
+    }
+
+    /// Reads a description from the JSON record of `to_json`
+    pub fn from_json(json: &str) -> Result<ShareSetDescription> {
+        serde_json::from_str(json)
+            .map_err(|e| anyhow::anyhow!("Invalid share set description: {}", e))
+    }
+
+    /// Whether the share is one of the shares of the described ceremony
+    ///
+    /// This checks the share is of the same split and has one of its indices, not that the share
+    /// is intact, which only recovering the secret does.
+    pub fn matches_share(&self, share: &[u8]) -> bool {
+        let set_id = match share_set_id(share) {
+            Some(set_id) => set_id,
+            None => return false,
+        };
+
+        wrapper::is_share(share)
+            && set_id[..16] == self.set_id
+            && share_index(share).is_some_and(|index| (1..=self.count).contains(&index))
+    }
+}
+
+/// Everything about a split that is not secret, written alongside the shares to check later that
+/// the right shares, and the right secret, are at hand
+///
+/// Nothing in it helps recover the secret: the shares are only recorded by their hash. The
+/// plaintext fingerprint does let anyone holding the manifest test guesses of the secret offline,
+/// like `ShareSetDescription`, so only keep a manifest with secrets that can't be guessed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SplitManifest {
+    /// Version of this layout, 1
+    pub manifest_version: u32,
+    /// The set id of the shares, which is their full `wrapper::split_fingerprint`
+    #[serde(with = "hex")]
+    pub split_id: [u8; 32],
+    pub scheme: Scheme,
+    /// When the split was made, in seconds since the Unix epoch
+    pub created_at: u64,
+    /// A note on what was split, chosen by whoever split it
+    pub label: Option<String>,
+    pub threshold: u8,
+    pub count: u8,
+    /// See `wrapper::share_expiry`
+    pub expires_at: Option<u64>,
+    pub format_version: u8,
+    /// How the shares were written to their files, like "binary" or "hex"
+    pub encoding: String,
+    pub cipher: String,
+    pub hash: String,
+    pub plaintext_length: u64,
+    /// See `plaintext_fingerprint`
+    #[serde(with = "hex")]
+    pub plaintext_fingerprint: [u8; 32],
+    /// Every share of the split, ordered by index
+    pub shares: Vec<ManifestShare>,
+}
+
+/// A share in a `SplitManifest`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ManifestShare {
+    pub index: u8,
+    /// The file the share was written to, `None` when it was stored elsewhere
+    pub file: Option<String>,
+    /// SHA3-256 hash of the share bytes, whatever encoding they were written in
+    #[serde(with = "hex")]
+    pub fingerprint: [u8; 32],
+    /// Length of the share bytes
+    pub size: u64,
+}
+
+impl SplitManifest {
+    /// Describes all shares of a split made by `to_shares`, in index order, of the given plaintext
+    ///
+    /// The shares are recorded without a file and as binary, the caller fills those in along with
+    /// the label.
+    pub fn new(shares: &[Vec<u8>], threshold: u8, plaintext: &[u8]) -> Result<SplitManifest> {
+        let count = shamir::checked_share_count(shares.len())?;
+        let first = shares.first().ok_or(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })?;
+        let split_id = share_set_id(first).ok_or(ShamirError::InconsistentShares)?;
+        let scheme = share_header(first)
+            .scheme
+            .ok_or(ShamirError::InconsistentShares)?;
+
+        let mut entries: Vec<ManifestShare> = Vec::new();
+        for share in shares.iter() {
+            if share_set_id(share) != Some(split_id) {
+                return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+                    .context("Shares belong to different splits"));
+            }
+            entries.push(ManifestShare {
+                index: share_index(share).ok_or(ShamirError::InconsistentShares)?,
+                file: None,
+                fingerprint: Sha3_256::digest(share).into(),
+                size: share.len() as u64,
+            });
+        }
+
+        Ok(SplitManifest {
+            manifest_version: 1,
+            split_id,
+            scheme,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+            label: None,
+            threshold,
+            count,
+            expires_at: share_expiry(first),
+            format_version: FORMAT_VERSION,
+            encoding: "binary".to_string(),
+            cipher: "aes-256-gcm".to_string(),
+            hash: "sha3-256".to_string(),
+            plaintext_length: plaintext.len() as u64,
+            plaintext_fingerprint: plaintext_fingerprint(&split_id, plaintext),
+            shares: entries,
+        })
+    }
+
+    /// The manifest as a JSON record
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("A manifest always serializes")
+    }
+
+    /// Reads a manifest from the JSON record of `to_json`
+    pub fn from_json(json: &str) -> Result<SplitManifest> {
+        serde_json::from_str(json).map_err(|e| anyhow::anyhow!("Invalid split manifest: {}", e))
+    }
+
+    /// The entry of the share with the given index
+    pub fn share(&self, index: u8) -> Option<&ManifestShare> {
+        self.shares.iter().find(|entry| entry.index == index)
+    }
+
+    /// Checks that the share is the one the manifest records under its index, byte for byte
+    pub fn check_share(&self, share: &[u8]) -> Result<()> {
+        if share_set_id(share) != Some(self.split_id) {
+            return Err(anyhow::anyhow!("Share is not of the split of the manifest"));
+        }
+
+        let index = share_index(share).ok_or(ShamirError::InconsistentShares)?;
+        let entry = match self.share(index) {
+            Some(entry) => entry,
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Share {} is not one of the shares of the manifest",
+                    index
+                ))
+            }
+        };
+
+        let fingerprint: [u8; 32] = Sha3_256::digest(share).into();
+        if entry.size != share.len() as u64 || entry.fingerprint != fingerprint {
+            return Err(
+                anyhow::Error::new(ShamirError::ShareTampered).context(format!(
+                    "Share {} differs from the one the manifest records",
+                    index
+                )),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Checks that the recovered plaintext is the one the manifest was made for
+    pub fn check_plaintext(&self, plaintext: &[u8]) -> Result<()> {
+        if plaintext.len() as u64 != self.plaintext_length
+            || plaintext_fingerprint(&self.split_id, plaintext) != self.plaintext_fingerprint
+        {
+            return Err(anyhow::anyhow!(
+                "Recovered file is not the one the manifest was made for"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// SHA3-256 hash of the plaintext, bound to the split so manifests of the same secret split twice
+/// can't be linked
+fn plaintext_fingerprint(split_id: &[u8; 32], plaintext: &[u8]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update(b"shamir split manifest plaintext")
+        .chain_update(split_id)
+        .chain_update(plaintext)
+        .finalize()
+        .into()
+}
+
+fn algorithm_suite(scheme: Scheme) -> String {
+    let sharing = match scheme {
+        Scheme::Shamir => "shamir-gf256",
+        Scheme::Additive => "additive-xor",
+        Scheme::IndexedShamir => "indexed-shamir-gf256",
+    };
+    format!("{}+aes-256-gcm+sha3-256/v{}", sharing, FORMAT_VERSION)
+}
+
+/// Writes byte arrays as hex, which is easier to compare by eye in an archived record
+mod hex {
+    use super::*;
+
+    pub fn serialize<S: Serializer, const N: usize>(
+        bytes: &[u8; N],
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize>(
+        deserializer: D,
+    ) -> std::result::Result<[u8; N], D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; N];
+        if hex.len() != N * 2 || !hex.is_ascii() {
+            return Err(serde::de::Error::custom(format!(
+                "expected {} hex characters",
+                N * 2
+            )));
+        }
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16)
+                .map_err(|_| serde::de::Error::custom("expected hex characters"))?;
+        }
+        Ok(bytes)
+    }
+}
@@ -0,0 +1,216 @@
+use aes_gcm::{
+    aead::{consts::U12, Aead, KeyInit, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::Result;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::error::ShamirError;
+use crate::shamir::{secure_rng, SecretData};
+
+/// Bytes the AES-GCM tag adds to every chunk
+const TAG_SIZE: usize = 16;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkedInfo {
+    length: usize,
+    chunk_size: usize,
+    data_hash: [u8; 32],
+    key: [u8; 32],
+    nonce: [u8; 12],
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkedShare {
+    info: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// Splits the input like `wrapper::to_shares`, but encrypts it in separately authenticated chunks
+/// of `chunk_size` bytes, so a part of it can be recovered without decrypting all of it
+pub fn to_shares(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    chunk_size: usize,
+) -> Result<Vec<Vec<u8>>> {
+    if chunk_size == 0 {
+        return Err(anyhow::anyhow!("Chunk size must be at least 1"));
+    }
+
+    let chunks = input.len().div_ceil(chunk_size);
+    if u32::try_from(chunks).is_err() {
+        return Err(anyhow::anyhow!("Too many chunks, use a larger chunk size"));
+    }
+
+    let mut rng = secure_rng()?;
+    let mut key = [0u8; 32];
+    let mut nonce = [0u8; 12];
+    rng.fill_bytes(&mut key);
+    rng.fill_bytes(&mut nonce);
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    let mut ciphertext: Vec<u8> = Vec::with_capacity(input.len() + chunks * TAG_SIZE);
+    for (i, chunk) in input.chunks(chunk_size).enumerate() {
+        let payload = Payload {
+            msg: chunk,
+            aad: &chunk_aad(i, chunks),
+        };
+        let mut encrypted = cipher
+            .encrypt(&chunk_nonce(&nonce, i), payload)
+            .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+        ciphertext.append(&mut encrypted);
+    }
+
+    let info = ChunkedInfo {
+        length: input.len(),
+        chunk_size,
+        data_hash: Sha3_256::digest(&ciphertext).into(),
+        key,
+        nonce,
+    };
+
+    let secret_data = SecretData::with_secret(bincode::serialize(&info)?, threshold)?;
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for i in 1..=count {
+        let share = ChunkedShare {
+            info: secret_data.get_share(i)?,
+            data: ciphertext.to_vec(),
+        };
+        shares.push(bincode::serialize(&share)?);
+    }
+
+    Ok(shares)
+}
+
+/// Recovers all of the input from chunked shares
+pub fn from_shares(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let (info, data) = open(input)?;
+    decrypt_range(&info, &data, 0, info.length)
+}
+
+/// Recovers `len` bytes of the input starting at `start`, only decrypting the chunks they are in
+pub fn recover_range(input: Vec<Vec<u8>>, start: usize, len: usize) -> Result<Vec<u8>> {
+    let (info, data) = open(input)?;
+    decrypt_range(&info, &data, start, len)
+}
+
+/// Recovers the share info and checks that the encrypted data belongs to it
+fn open(input: Vec<Vec<u8>>) -> Result<(ChunkedInfo, Vec<u8>)> {
+    if input.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let mut shares: Vec<ChunkedShare> = Vec::new();
+    for share in input {
+        shares.push(bincode::deserialize(&share)?);
+    }
+
+    // Check if all shares have the same encrypted data
+    let data = std::mem::take(&mut shares[0].data);
+    if shares[1..].iter().any(|s| s.data != data) {
+        return Err(anyhow::anyhow!("Shares do not match"));
+    }
+
+    let info_length = shares[0].info.len();
+    if info_length == 0 || shares.iter().any(|s| s.info.len() != info_length) {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    let raw_shares = shares.into_iter().map(|s| s.info).collect::<Vec<Vec<u8>>>();
+    let info: ChunkedInfo = bincode::deserialize(&SecretData::recover_secret(raw_shares)?)?;
+
+    // Check if the encrypted data belongs to these shares
+    let data_hash: [u8; 32] = Sha3_256::digest(&data).into();
+    if info.data_hash != data_hash {
+        return Err(anyhow::anyhow!(
+            "Encrypted data does not belong to these shares"
+        ));
+    }
+
+    if info.chunk_size == 0 {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    let chunks = info.length.div_ceil(info.chunk_size);
+    if data.len() != info.length + chunks * TAG_SIZE {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    Ok((info, data))
+}
+
+fn decrypt_range(info: &ChunkedInfo, data: &[u8], start: usize, len: usize) -> Result<Vec<u8>> {
+    let end = start
+        .checked_add(len)
+        .filter(|end| *end <= info.length)
+        .ok_or_else(|| anyhow::anyhow!("Range is outside of the secret"))?;
+    if len == 0 {
+        return Ok(vec![]);
+    }
+
+    let chunks = info.length.div_ceil(info.chunk_size);
+    let first = start / info.chunk_size;
+    let last = (end - 1) / info.chunk_size;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&info.key));
+    let mut plaintext: Vec<u8> = Vec::with_capacity((last - first + 1) * info.chunk_size);
+    for i in first..=last {
+        let offset = i * (info.chunk_size + TAG_SIZE);
+        let chunk_end = (offset + info.chunk_size + TAG_SIZE).min(data.len());
+        let payload = Payload {
+            msg: &data[offset..chunk_end],
+            aad: &chunk_aad(i, chunks),
+        };
+        let mut decrypted = cipher
+            .decrypt(&chunk_nonce(&info.nonce, i), payload)
+            .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
+        plaintext.append(&mut decrypted);
+    }
+
+    let skip = start - first * info.chunk_size;
+    Ok(plaintext[skip..skip + len].to_vec())
+}
+
+/// Every chunk gets its own nonce, the base nonce with the chunk number mixed into its last bytes
+fn chunk_nonce(nonce: &[u8; 12], index: usize) -> Nonce<U12> {
+    let mut chunk_nonce = *nonce;
+    for (n, c) in chunk_nonce[8..]
+        .iter_mut()
+        .zip((index as u32).to_be_bytes())
+    {
+        *n ^= c;
+    }
+    chunk_nonce.into()
+}
+
+/// Binds every chunk to its position and the number of chunks, so they can't be reordered or cut off
+fn chunk_aad(index: usize, chunks: usize) -> [u8; 16] {
+    let mut aad = [0u8; 16];
+    aad[..8].copy_from_slice(&(index as u64).to_le_bytes());
+    aad[8..].copy_from_slice(&(chunks as u64).to_le_bytes());
+    aad
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_middle_range_matches_the_full_recovery() {
+        let input: Vec<u8> = (0..10_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let shares = to_shares(&input, 2, 3, 1024).unwrap();
+
+        let full = from_shares(shares[..2].to_vec()).unwrap();
+        assert_eq!(full, input);
+
+        let range = recover_range(shares[1..].to_vec(), 3000, 2500).unwrap();
+        assert_eq!(range, &full[3000..5500]);
+    }
+}
@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+use std::ops::Index;
+
+use anyhow::Result;
+use zeroize::Zeroizing;
+
+use crate::error::ShamirError;
+use crate::wrapper::{from_shares, is_share, share_index, share_set_id};
+
+/// A set of serialized shares, looked up by their share index rather than their position
+#[derive(Debug, Default, Clone)]
+pub struct ShareCollection {
+    inner: HashMap<u8, Vec<u8>>,
+}
+
+impl ShareCollection {
+    pub fn new() -> ShareCollection {
+        ShareCollection::default()
+    }
+
+    /// Adds a share under the given index, replacing any share already stored there
+    pub fn insert(&mut self, index: u8, share: Vec<u8>) {
+        self.inner.insert(index, share);
+    }
+
+    pub fn contains(&self, index: u8) -> bool {
+        self.inner.contains_key(&index)
+    }
+
+    pub fn get(&self, index: u8) -> Option<&Vec<u8>> {
+        self.inner.get(&index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The shares ordered by index, ready to be passed to `from_shares`
+    pub fn into_shares(self) -> Vec<Vec<u8>> {
+        let mut shares: Vec<(u8, Vec<u8>)> = self.inner.into_iter().collect();
+        shares.sort_by_key(|(index, _)| *index);
+        shares.into_iter().map(|(_, share)| share).collect()
+    }
+}
+
+impl Index<u8> for ShareCollection {
+    type Output = Vec<u8>;
+
+    /// Panics if there is no share with the given index, use `get` to avoid this
+    fn index(&self, index: u8) -> &Vec<u8> {
+        match self.inner.get(&index) {
+            Some(share) => share,
+            None => panic!("No share with index {}", index),
+        }
+    }
+}
+
+impl From<Vec<Vec<u8>>> for ShareCollection {
+    /// Reads the index of every share from the share itself, shares too damaged to have an index
+    /// are left out
+    fn from(shares: Vec<Vec<u8>>) -> ShareCollection {
+        let mut collection = ShareCollection::new();
+        for share in shares {
+            if let Some(index) = share_index(&share) {
+                collection.insert(index, share);
+            }
+        }
+        collection
+    }
+}
+
+/// Shares of one secret, which all belong to the same split and all have a different index
+///
+/// Unlike `ShareCollection`, every share is checked as it is added, so the set can be handed to
+/// `from_shares` as `set.into()` knowing those mistakes were already ruled out. A set made with
+/// `with_threshold` also knows when it holds enough shares, and recovers the secret itself.
+#[derive(Debug, Default, Clone)]
+pub struct ShareSet {
+    set_id: Option<[u8; 32]>,
+    /// The threshold of the split, the shares don't record it
+    threshold: Option<u8>,
+    /// Ordered by index
+    shares: Vec<(u8, Vec<u8>)>,
+}
+
+/// Whether a `ShareSet` holds enough shares to recover the secret
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareSetStatus {
+    /// This many more shares are needed
+    NotReady(u8),
+    Ready,
+}
+
+impl ShareSet {
+    pub fn new() -> ShareSet {
+        ShareSet::default()
+    }
+
+    /// An empty set for a split with the given threshold, which is ready once it holds that many
+    /// shares
+    pub fn with_threshold(threshold: u8) -> ShareSet {
+        ShareSet {
+            threshold: Some(threshold),
+            ..ShareSet::default()
+        }
+    }
+
+    /// Builds a set from the shares, failing on the first share that does not fit in it
+    pub fn try_from_iter<I: IntoIterator<Item = Vec<u8>>>(shares: I) -> Result<ShareSet> {
+        let mut set = ShareSet::new();
+        for share in shares {
+            set.insert(share)?;
+        }
+        Ok(set)
+    }
+
+    /// Adds a share, failing if it belongs to another split or its index is already taken
+    pub fn insert(&mut self, share: Vec<u8>) -> Result<()> {
+        let (index, set_id) = match (share_index(&share), share_set_id(&share)) {
+            (Some(index), Some(set_id)) if is_share(&share) => (index, set_id),
+            _ => return Err(anyhow::anyhow!("Not a share")),
+        };
+
+        if self.set_id.is_some_and(|s| s != set_id) {
+            return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+                .context(format!("Share {} belongs to another split", index)));
+        }
+
+        let position = match self.shares.binary_search_by_key(&index, |(i, _)| *i) {
+            Ok(_) => {
+                return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+                    .context(format!("Share {} is already in the set", index)))
+            }
+            Err(position) => position,
+        };
+
+        self.set_id = Some(set_id);
+        self.shares.insert(position, (index, share));
+        Ok(())
+    }
+
+    /// Adds a share like `insert`, and tells whether the set is now ready to recover the secret
+    pub fn add(&mut self, share: Vec<u8>) -> Result<ShareSetStatus> {
+        self.insert(share)?;
+        Ok(self.status())
+    }
+
+    /// Whether the set holds enough shares to recover the secret
+    ///
+    /// Without a threshold, a set is ready as soon as it holds a share, and only recovering tells
+    /// whether it holds enough of them.
+    pub fn status(&self) -> ShareSetStatus {
+        let threshold = self.threshold.unwrap_or(1) as usize;
+        match threshold.checked_sub(self.shares.len()) {
+            Some(remaining) if remaining > 0 => ShareSetStatus::NotReady(remaining as u8),
+            _ => ShareSetStatus::Ready,
+        }
+    }
+
+    /// Recovers the secret from the shares of the set, failing with `ShamirError::NotEnoughShares`
+    /// while the set is not ready
+    pub fn recover(self) -> Result<Zeroizing<Vec<u8>>> {
+        if let ShareSetStatus::NotReady(remaining) = self.status() {
+            return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+                have: self.len(),
+                need: Some(self.len() + remaining as usize),
+            })
+            .context(format!("{} more shares are needed", remaining)));
+        }
+        Ok(Zeroizing::new(from_shares(self.into_shares())?))
+    }
+
+    /// The threshold the set was made with, see `with_threshold`
+    pub fn threshold(&self) -> Option<u8> {
+        self.threshold
+    }
+
+    /// Identifies the split of the shares, `None` while the set is empty
+    pub fn set_id(&self) -> Option<[u8; 32]> {
+        self.set_id
+    }
+
+    pub fn contains(&self, index: u8) -> bool {
+        self.shares.iter().any(|(i, _)| *i == index)
+    }
+
+    /// The indices of the shares, in order
+    pub fn indices(&self) -> Vec<u8> {
+        self.shares.iter().map(|(i, _)| *i).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shares.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shares.is_empty()
+    }
+
+    /// The shares ordered by index, ready to be passed to `from_shares`
+    pub fn into_shares(self) -> Vec<Vec<u8>> {
+        self.shares.into_iter().map(|(_, share)| share).collect()
+    }
+}
+
+impl FromIterator<Vec<u8>> for ShareSet {
+    /// Shares that don't fit in the set are left out, use `try_from_iter` to be told about them
+    fn from_iter<I: IntoIterator<Item = Vec<u8>>>(shares: I) -> ShareSet {
+        let mut set = ShareSet::new();
+        set.extend(shares);
+        set
+    }
+}
+
+impl Extend<Vec<u8>> for ShareSet {
+    /// Shares that don't fit in the set are left out, use `insert` to be told about them
+    fn extend<I: IntoIterator<Item = Vec<u8>>>(&mut self, shares: I) {
+        for share in shares {
+            let _ = self.insert(share);
+        }
+    }
+}
+
+impl std::ops::Add<Vec<u8>> for ShareSet {
+    type Output = Result<ShareSet>;
+
+    /// Adds a share like `insert`, so shares can be added as `((set + first)? + second)?`
+    fn add(mut self, share: Vec<u8>) -> Result<ShareSet> {
+        self.insert(share)?;
+        Ok(self)
+    }
+}
+
+impl IntoIterator for ShareSet {
+    type Item = Vec<u8>;
+    type IntoIter = std::vec::IntoIter<Vec<u8>>;
+
+    /// Iterates over the shares ordered by index
+    fn into_iter(self) -> Self::IntoIter {
+        self.into_shares().into_iter()
+    }
+}
+
+impl From<ShareSet> for Vec<Vec<u8>> {
+    fn from(set: ShareSet) -> Vec<Vec<u8>> {
+        set.into_shares()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper::to_shares;
+
+    #[test]
+    fn share_sets_recover_the_secret() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let set = ShareSet::try_from_iter(vec![shares[2].clone(), shares[0].clone()]).unwrap();
+        assert_eq!(set.indices(), vec![1, 3]);
+        assert_eq!(from_shares(set.into()).unwrap(), b"secret");
+
+        let mut set = ShareSet::with_threshold(2);
+        assert_eq!(
+            set.add(shares[1].clone()).unwrap(),
+            ShareSetStatus::NotReady(1)
+        );
+        set.extend(vec![shares[2].clone()]);
+        assert_eq!(set.status(), ShareSetStatus::Ready);
+        assert_eq!(*set.recover().unwrap(), b"secret");
+    }
+
+    #[test]
+    fn share_sets_reject_duplicate_indices_and_foreign_shares() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let others = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+
+        let error =
+            ShareSet::try_from_iter(vec![shares[0].clone(), shares[0].clone()]).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::InconsistentShares)
+        );
+
+        let error =
+            ShareSet::try_from_iter(vec![shares[0].clone(), others[1].clone()]).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::InconsistentShares)
+        );
+        assert!(ShareSet::try_from_iter(vec![b"not a share".to_vec()]).is_err());
+
+        // Collecting leaves out the shares that don't fit
+        let set = vec![shares[0].clone(), others[1].clone(), shares[1].clone()]
+            .into_iter()
+            .collect::<ShareSet>();
+        assert_eq!(set.indices(), vec![1, 2]);
+        assert_eq!(from_shares(set.into()).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn share_sets_chain_shares_and_refuse_to_recover_early() {
+        let shares = to_shares(b"secret".to_vec(), 3, 5).unwrap();
+
+        let set = ((ShareSet::with_threshold(3) + shares[4].clone()).unwrap() + shares[1].clone())
+            .unwrap();
+        assert_eq!(set.status(), ShareSetStatus::NotReady(1));
+        let error = set.clone().recover().unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::NotEnoughShares {
+                have: 2,
+                need: Some(3)
+            })
+        );
+        assert!((set.clone() + shares[1].clone()).is_err());
+
+        let set = (set + shares[3].clone()).unwrap();
+        assert_eq!(set.status(), ShareSetStatus::Ready);
+        let ordered = set.clone().into_iter().collect::<Vec<Vec<u8>>>();
+        assert_eq!(
+            ordered,
+            vec![shares[1].clone(), shares[3].clone(), shares[4].clone()]
+        );
+        assert_eq!(*set.recover().unwrap(), b"secret");
+    }
+}
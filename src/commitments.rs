@@ -0,0 +1,365 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::wrapper::{share_index, to_shares};
+
+/// Public commitments to every share of a split, which can be published separately from the shares
+///
+/// The shares are points on polynomials over GF(2^8), which has no group structure to build
+/// Feldman style commitments on, so these are hash commitments to each share instead. They let a
+/// custodian check that their share is one the dealer handed out and has not changed since, but do
+/// not prove that the shares are consistent with each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commitments {
+    pub threshold: u8,
+    pub shares: Vec<(u8, [u8; 32])>,
+}
+
+impl Commitments {
+    /// Checks that the share matches the commitment published for its index
+    pub fn verify(&self, share: &[u8]) -> bool {
+        let index = match share_index(share) {
+            Some(index) => index,
+            None => return false,
+        };
+
+        let hash: [u8; 32] = Sha3_256::digest(share).into();
+        self.shares.iter().any(|(i, h)| *i == index && *h == hash)
+    }
+}
+
+/// Like `to_shares`, but also returns commitments to the shares that can be published
+pub fn split_with_commitments(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+) -> Result<(Vec<Vec<u8>>, Commitments)> {
+    let shares = to_shares(input, threshold, count)?;
+
+    let mut commitments = Commitments {
+        threshold,
+        shares: vec![],
+    };
+    for share in shares.iter() {
+        let index = share_index(share).ok_or_else(|| anyhow::anyhow!("Invalid share"))?;
+        commitments
+            .shares
+            .push((index, Sha3_256::digest(share).into()));
+    }
+
+    Ok((shares, commitments))
+}
+
+/// A share of `split_committed`, along with the proof that it is part of the published commitment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommittedShare {
+    pub share: Vec<u8>,
+    /// The sibling hashes from the share up to the root, and whether each one is on the right
+    pub proof: Vec<([u8; 32], bool)>,
+}
+
+/// Like `to_shares`, but also returns a single commitment to all shares that can be published
+///
+/// The commitment is the root of a Merkle tree over the shares, and every share carries its path
+/// to the root. A custodian can check their share against the published commitment alone, which
+/// catches a dealer handing out shares of different splits to different custodians. Like
+/// `Commitments`, it does not prove that the shares are consistent with each other.
+pub fn split_committed(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+) -> Result<(Vec<Vec<u8>>, [u8; 32])> {
+    let shares = to_shares(input, threshold, count)?;
+
+    let leaves = shares
+        .iter()
+        .map(|s| leaf_hash(s))
+        .collect::<Vec<[u8; 32]>>();
+    let mut committed: Vec<Vec<u8>> = Vec::new();
+    for (position, share) in shares.into_iter().enumerate() {
+        let committed_share = CommittedShare {
+            share,
+            proof: merkle_proof(&leaves, position),
+        };
+        committed.push(bincode::serialize(&committed_share)?);
+    }
+
+    Ok((committed, merkle_root(&leaves)))
+}
+
+/// The root of the Merkle tree of `to_shares_with_merkle` and the number of shares, to be published
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleRoot(pub [u8; 32]);
+
+/// The path of a share of `to_shares_with_merkle` up to the root, kept apart from the share
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// The sibling hashes from the leaf of the share up to the root, levels where the share has no
+    /// sibling are skipped
+    pub siblings: Vec<[u8; 32]>,
+}
+
+impl MerkleProof {
+    /// Checks that the share is the one at `index` of the `total` shares under the root
+    ///
+    /// Which side every sibling is on follows from the index and the number of shares, so a proof
+    /// only holds for the position it was made for, and the share has to carry that index.
+    pub fn verify(&self, share: &[u8], root: &MerkleRoot, index: u8, total: u8) -> bool {
+        if index == 0 || index > total || share_index(share) != Some(index) {
+            return false;
+        }
+
+        let mut siblings = self.siblings.iter();
+        let mut hash = leaf_hash(share);
+        let mut position = (index - 1) as usize;
+        let mut width = total as usize;
+        while width > 1 {
+            if position ^ 1 < width {
+                let sibling = match siblings.next() {
+                    Some(sibling) => sibling,
+                    None => return false,
+                };
+                hash = if position.is_multiple_of(2) {
+                    node_hash(&hash, sibling)
+                } else {
+                    node_hash(sibling, &hash)
+                };
+            }
+            position /= 2;
+            width = width.div_ceil(2);
+        }
+
+        siblings.next().is_none() && sized_root(&hash, total) == root.0
+    }
+}
+
+/// Like `split_committed`, but leaves the shares as they are and returns the proofs separately
+///
+/// The shares are ordered by index, the proof of every share is at the same position. Any subset of
+/// the shares can be checked against the root with `MerkleProof::verify`, without the others.
+pub fn to_shares_with_merkle(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+) -> Result<(Vec<Vec<u8>>, MerkleRoot, Vec<MerkleProof>)> {
+    let shares = to_shares(input.to_vec(), threshold, count)?;
+
+    let leaves = shares
+        .iter()
+        .map(|s| leaf_hash(s))
+        .collect::<Vec<[u8; 32]>>();
+    let proofs = (0..leaves.len())
+        .map(|position| MerkleProof {
+            siblings: merkle_proof(&leaves, position)
+                .into_iter()
+                .map(|(sibling, _)| sibling)
+                .collect(),
+        })
+        .collect();
+
+    let root = sized_root(&merkle_root(&leaves), count);
+    Ok((shares, MerkleRoot(root), proofs))
+}
+
+/// Checks that a share of `split_committed` is part of the given commitment
+pub fn verify_against_commitment(share: &[u8], commitment: &[u8; 32]) -> bool {
+    let committed: CommittedShare = match bincode::deserialize(share) {
+        Ok(committed) => committed,
+        Err(_) => return false,
+    };
+
+    let mut hash = leaf_hash(&committed.share);
+    for (sibling, on_right) in committed.proof.iter() {
+        hash = if *on_right {
+            node_hash(&hash, sibling)
+        } else {
+            node_hash(sibling, &hash)
+        };
+    }
+
+    hash == *commitment
+}
+
+/// Takes the share out of a share of `split_committed`, for use with `from_shares`
+pub fn unwrap_committed(share: &[u8]) -> Result<Vec<u8>> {
+    let committed: CommittedShare = bincode::deserialize(share)?;
+    Ok(committed.share)
+}
+
+// Leaves and nodes are hashed with a different prefix, so a node can't pass for a leaf
+fn leaf_hash(share: &[u8]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update([0u8])
+        .chain_update(share)
+        .finalize()
+        .into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update([1u8])
+        .chain_update(left)
+        .chain_update(right)
+        .finalize()
+        .into()
+}
+
+// The root of `to_shares_with_merkle` also commits to the number of shares, as paths of the same
+// shape come up in trees of other sizes, where they'd be taken for another position
+fn sized_root(tree_root: &[u8; 32], total: u8) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update([2u8, total])
+        .chain_update(tree_root)
+        .finalize()
+        .into()
+}
+
+/// The next level up the tree, a node without a sibling is carried up as it is
+fn merkle_level(level: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => node_hash(left, right),
+            [single] => *single,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+fn merkle_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = merkle_level(&level);
+    }
+    level.first().copied().unwrap_or_default()
+}
+
+fn merkle_proof(leaves: &[[u8; 32]], mut position: usize) -> Vec<([u8; 32], bool)> {
+    let mut proof: Vec<([u8; 32], bool)> = Vec::new();
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if let Some(hash) = level.get(sibling) {
+            proof.push((*hash, sibling > position));
+        }
+        level = merkle_level(&level);
+        position /= 2;
+    }
+    proof
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper::from_shares;
+
+    #[test]
+    fn shares_verify_against_published_commitments() {
+        let (shares, commitments) = split_with_commitments(b"secret".to_vec(), 2, 3).unwrap();
+        let published = serde_json::to_string(&commitments).unwrap();
+        let commitments: Commitments = serde_json::from_str(&published).unwrap();
+
+        assert_eq!(commitments.threshold, 2);
+        assert!(shares.iter().all(|share| commitments.verify(share)));
+
+        let mut changed = shares[1].clone();
+        *changed.last_mut().unwrap() ^= 1;
+        assert!(!commitments.verify(&changed));
+
+        let (others, _) = split_with_commitments(b"secret".to_vec(), 2, 3).unwrap();
+        assert!(!commitments.verify(&others[0]));
+    }
+
+    #[test]
+    fn committed_shares_verify_against_the_single_commitment() {
+        let (shares, commitment) = split_committed(b"secret".to_vec(), 3, 5).unwrap();
+        assert!(shares
+            .iter()
+            .all(|share| verify_against_commitment(share, &commitment)));
+
+        let unwrapped = shares
+            .iter()
+            .map(|share| unwrap_committed(share).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+        assert_eq!(from_shares(unwrapped[2..].to_vec()).unwrap(), b"secret");
+
+        let mut committed: CommittedShare = bincode::deserialize(&shares[4]).unwrap();
+        *committed.share.last_mut().unwrap() ^= 1;
+        let tampered = bincode::serialize(&committed).unwrap();
+        assert!(!verify_against_commitment(&tampered, &commitment));
+
+        let mut committed: CommittedShare = bincode::deserialize(&shares[0]).unwrap();
+        committed.proof[0].0[0] ^= 1;
+        let tampered = bincode::serialize(&committed).unwrap();
+        assert!(!verify_against_commitment(&tampered, &commitment));
+
+        let (_, other) = split_committed(b"secret".to_vec(), 3, 5).unwrap();
+        assert!(!verify_against_commitment(&shares[0], &other));
+        assert!(!verify_against_commitment(b"not a share", &commitment));
+    }
+
+    #[test]
+    fn merkle_proofs_verify_every_share() {
+        for count in [2, 5, 8] {
+            let (shares, root, proofs) = to_shares_with_merkle(b"secret", 2, count).unwrap();
+            assert_eq!(proofs.len(), shares.len());
+            for (position, (share, proof)) in shares.iter().zip(proofs.iter()).enumerate() {
+                assert!(proof.verify(share, &root, position as u8 + 1, count));
+            }
+
+            // The proofs travel apart from the shares, which are recovered from as they are
+            assert_eq!(from_shares(shares[..2].to_vec()).unwrap(), b"secret");
+        }
+    }
+
+    #[test]
+    fn merkle_proofs_only_hold_for_their_index_and_total() {
+        let (shares, root, proofs) = to_shares_with_merkle(b"secret", 3, 5).unwrap();
+        assert!(proofs[2].verify(&shares[2], &root, 3, 5));
+
+        assert!(!proofs[2].verify(&shares[2], &root, 2, 5));
+        assert!(!proofs[2].verify(&shares[2], &root, 0, 5));
+        assert!(!proofs[2].verify(&shares[2], &root, 3, 4));
+        assert!(!proofs[2].verify(&shares[2], &root, 3, 6));
+        assert!(!proofs[2].verify(&shares[2], &root, 6, 5));
+
+        // Another share's proof doesn't hold for this one, at either index
+        assert!(!proofs[1].verify(&shares[2], &root, 3, 5));
+        assert!(!proofs[1].verify(&shares[2], &root, 2, 5));
+    }
+
+    #[test]
+    fn tampered_shares_and_proofs_fail_to_verify() {
+        let (shares, root, proofs) = to_shares_with_merkle(b"secret", 3, 5).unwrap();
+
+        let mut tampered = shares[4].clone();
+        let middle = tampered.len() / 2;
+        tampered[middle] ^= 1;
+        assert!(!proofs[4].verify(&tampered, &root, 5, 5));
+
+        let mut tampered = proofs[0].clone();
+        tampered.siblings[0][0] ^= 1;
+        assert!(!tampered.verify(&shares[0], &root, 1, 5));
+
+        let mut longer = proofs[0].clone();
+        longer.siblings.push([0; 32]);
+        assert!(!longer.verify(&shares[0], &root, 1, 5));
+        let mut shorter = proofs[0].clone();
+        shorter.siblings.pop();
+        assert!(!shorter.verify(&shares[0], &root, 1, 5));
+    }
+
+    #[test]
+    fn merkle_proofs_fail_against_another_root() {
+        let (shares, root, proofs) = to_shares_with_merkle(b"secret", 3, 5).unwrap();
+        let (others, other_root, other_proofs) = to_shares_with_merkle(b"secret", 3, 5).unwrap();
+        assert_ne!(root, other_root);
+
+        for position in 0..5 {
+            let index = position as u8 + 1;
+            assert!(!proofs[position].verify(&shares[position], &other_root, index, 5));
+            assert!(!other_proofs[position].verify(&others[position], &root, index, 5));
+        }
+    }
+}
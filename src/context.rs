@@ -0,0 +1,138 @@
+use anyhow::Result;
+use std::env;
+
+use crate::wrapper;
+
+/// Threshold and count to split with, for callers that only learn them at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShamirContext {
+    pub threshold: u8,
+    pub count: u8,
+    /// The largest secret `combine` recovers, `wrapper::DEFAULT_MAX_SECRET_BYTES` unless changed
+    pub max_secret_bytes: usize,
+}
+
+impl ShamirContext {
+    pub fn new(threshold: u8, count: u8) -> Result<ShamirContext> {
+        if threshold == 0 || threshold > count {
+            return Err(anyhow::anyhow!(
+                "Threshold must be between 1 and the number of shares"
+            ));
+        }
+
+        Ok(ShamirContext {
+            threshold,
+            count,
+            max_secret_bytes: wrapper::DEFAULT_MAX_SECRET_BYTES,
+        })
+    }
+
+    /// Recovers secrets of up to `max_secret_bytes` in `combine`
+    pub fn with_max_secret_bytes(self, max_secret_bytes: usize) -> ShamirContext {
+        ShamirContext {
+            max_secret_bytes,
+            ..self
+        }
+    }
+
+    /// Splits the input into `count` shares, any `threshold` of which recover it
+    pub fn split(&self, input: Vec<u8>) -> Result<Vec<Vec<u8>>> {
+        wrapper::to_shares(input, self.threshold, self.count)
+    }
+
+    /// Recovers the input from its shares, as long as it is no larger than `max_secret_bytes`
+    pub fn combine(&self, shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+        wrapper::from_shares_guarded(shares, self.max_secret_bytes)
+    }
+}
+
+impl ShamirContext {
+    /// Reads the threshold and count from `SHAMIR_DEFAULT_THRESHOLD` and `SHAMIR_DEFAULT_COUNT`,
+    /// using 3 out of 5 for the ones that are not set
+    ///
+    /// Fails if a variable is not a number or they do not describe a valid configuration, so a
+    /// misconfigured deployment can refuse to start instead of splitting with unexpected parameters
+    pub fn from_env() -> Result<ShamirContext> {
+        let threshold = env_or("SHAMIR_DEFAULT_THRESHOLD", DEFAULT_THRESHOLD)?;
+        let count = env_or("SHAMIR_DEFAULT_COUNT", DEFAULT_COUNT)?;
+
+        ShamirContext::new(threshold, count).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid SHAMIR_DEFAULT_THRESHOLD={} and SHAMIR_DEFAULT_COUNT={}, expected 1 <= threshold <= count <= 255",
+                threshold,
+                count
+            )
+        })
+    }
+}
+
+const DEFAULT_THRESHOLD: u8 = 3;
+const DEFAULT_COUNT: u8 = 5;
+
+impl Default for ShamirContext {
+    /// The configuration of [`ShamirContext::from_env`], or 3 out of 5 when the variables are
+    /// invalid, use `from_env` directly to notice those
+    fn default() -> Self {
+        ShamirContext::from_env().unwrap_or(ShamirContext {
+            threshold: DEFAULT_THRESHOLD,
+            count: DEFAULT_COUNT,
+            max_secret_bytes: wrapper::DEFAULT_MAX_SECRET_BYTES,
+        })
+    }
+}
+
+fn env_or(name: &str, default: u8) -> Result<u8> {
+    match env::var(name) {
+        Ok(value) => value.trim().parse().map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid {}=\"{}\", expected a number between 1 and 255",
+                name,
+                value
+            )
+        }),
+        Err(_) => Ok(default),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(threshold: Option<&str>, count: Option<&str>) {
+        for (name, value) in [
+            ("SHAMIR_DEFAULT_THRESHOLD", threshold),
+            ("SHAMIR_DEFAULT_COUNT", count),
+        ] {
+            match value {
+                Some(value) => env::set_var(name, value),
+                None => env::remove_var(name),
+            }
+        }
+    }
+
+    // One test for all cases, as tests running in parallel would race on the variables
+    #[test]
+    fn the_configuration_is_read_from_the_environment() {
+        set(None, None);
+        assert_eq!(
+            ShamirContext::from_env().unwrap(),
+            ShamirContext::new(3, 5).unwrap()
+        );
+        assert_eq!(ShamirContext::default(), ShamirContext::new(3, 5).unwrap());
+
+        set(Some("2"), Some(" 4 "));
+        assert_eq!(
+            ShamirContext::from_env().unwrap(),
+            ShamirContext::new(2, 4).unwrap()
+        );
+        assert_eq!(ShamirContext::default(), ShamirContext::new(2, 4).unwrap());
+
+        for (threshold, count) in [("two", "4"), ("2", "256"), ("0", "4"), ("5", "4")] {
+            set(Some(threshold), Some(count));
+            assert!(ShamirContext::from_env().is_err());
+            assert_eq!(ShamirContext::default(), ShamirContext::new(3, 5).unwrap());
+        }
+
+        set(None, None);
+    }
+}
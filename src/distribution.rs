@@ -0,0 +1,194 @@
+use anyhow::Result;
+use sha3::{Digest, Sha3_256};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zeroize::Zeroize;
+
+use crate::wrapper::{share_index, to_shares};
+
+/// Hands a share to its custodian, like mailing it or uploading it to their vault
+pub trait Transport {
+    /// Sends the share with the given index, returning a receipt once the custodian has it
+    fn send(&mut self, index: u8, share: &[u8]) -> Result<Receipt>;
+}
+
+/// Proof that a share reached its custodian, to keep with the records of the split
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub index: u8,
+    /// SHA3-256 hash of the share that was sent, to check later that the custodian holds that one
+    pub fingerprint: [u8; 32],
+    /// Whatever the transport identifies the delivery by, like a message id
+    pub reference: String,
+    /// When the share was delivered, in seconds since the Unix epoch
+    pub delivered_at: u64,
+}
+
+impl Receipt {
+    /// A receipt for delivering the share now
+    pub fn new(index: u8, share: &[u8], reference: String) -> Receipt {
+        Receipt {
+            index,
+            fingerprint: Sha3_256::digest(share).into(),
+            reference,
+            delivered_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|since| since.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A share the transport failed to send
+#[derive(Debug)]
+pub struct FailedDelivery {
+    pub index: u8,
+    pub error: anyhow::Error,
+}
+
+/// The outcome of `distribute`, a receipt for every share that was sent and the error of every one
+/// that wasn't, both ordered by index
+#[derive(Debug, Default)]
+pub struct DistributionReport {
+    pub receipts: Vec<Receipt>,
+    pub failed: Vec<FailedDelivery>,
+}
+
+impl DistributionReport {
+    /// Whether every share reached its custodian
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// The indices of the shares that have to be sent again
+    pub fn failed_indices(&self) -> Vec<u8> {
+        self.failed.iter().map(|failed| failed.index).collect()
+    }
+}
+
+/// Sends every share with the transport, carrying on past the shares that fail to send
+///
+/// A share that can't be sent doesn't stop the others, so the report tells exactly which shares
+/// have to be sent again, instead of leaving some custodians with a share and others without.
+pub fn distribute(shares: &[Vec<u8>], transport: &mut impl Transport) -> DistributionReport {
+    let mut report = DistributionReport::default();
+    for (position, share) in shares.iter().enumerate() {
+        let position = u8::try_from(position + 1).unwrap_or(u8::MAX);
+        let index = share_index(share).unwrap_or(position);
+        match transport.send(index, share) {
+            Ok(receipt) => report.receipts.push(receipt),
+            Err(error) => report.failed.push(FailedDelivery { index, error }),
+        }
+    }
+
+    report
+}
+
+/// Splits the input like `to_shares` and sends the shares right away with `distribute`, the shares
+/// are zeroized afterwards, so the transport holds the only copies
+pub fn split_and_distribute(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    transport: &mut impl Transport,
+) -> Result<DistributionReport> {
+    let mut shares = to_shares(input.to_vec(), threshold, count)?;
+    let report = distribute(&shares, transport);
+    for share in shares.iter_mut() {
+        share.zeroize();
+    }
+
+    Ok(report)
+}
+
+/// Keeps the shares it is sent in memory, as a reference transport and to stand in for a real one
+/// in tests, also logging every attempt
+#[derive(Debug, Default)]
+pub struct MemoryTransport {
+    delivered: BTreeMap<u8, Vec<u8>>,
+    /// Shares with these indices fail to send, like an unreachable custodian
+    unreachable: Vec<u8>,
+    log: Vec<String>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> MemoryTransport {
+        MemoryTransport::default()
+    }
+
+    /// A transport that fails to send the shares with the given indices
+    pub fn with_unreachable(indices: &[u8]) -> MemoryTransport {
+        MemoryTransport {
+            unreachable: indices.to_vec(),
+            ..MemoryTransport::default()
+        }
+    }
+
+    /// The share delivered under the given index
+    pub fn delivered(&self, index: u8) -> Option<&Vec<u8>> {
+        self.delivered.get(&index)
+    }
+
+    /// A line for every attempt to send a share, in order
+    pub fn log(&self) -> &[String] {
+        &self.log
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&mut self, index: u8, share: &[u8]) -> Result<Receipt> {
+        if self.unreachable.contains(&index) {
+            self.log.push(format!("Failed to send share {}", index));
+            return Err(anyhow::anyhow!(
+                "Custodian of share {} is unreachable",
+                index
+            ));
+        }
+
+        self.delivered.insert(index, share.to_vec());
+        self.log.push(format!("Sent share {}", index));
+        Ok(Receipt::new(index, share, format!("memory:{}", index)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper::from_shares;
+
+    #[test]
+    fn a_failed_send_reports_exactly_which_index() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let mut transport = MemoryTransport::with_unreachable(&[2]);
+
+        let report = distribute(&shares, &mut transport);
+        assert!(!report.is_complete());
+        assert_eq!(report.failed_indices(), vec![2]);
+        assert!(report.failed[0].error.to_string().contains("share 2"));
+
+        let indices: Vec<u8> = report
+            .receipts
+            .iter()
+            .map(|receipt| receipt.index)
+            .collect();
+        assert_eq!(indices, vec![1, 3]);
+        for receipt in &report.receipts {
+            let share = transport.delivered(receipt.index).unwrap();
+            assert_eq!(
+                receipt.fingerprint,
+                <[u8; 32]>::from(Sha3_256::digest(share))
+            );
+        }
+        assert!(transport.delivered(2).is_none());
+        assert_eq!(
+            transport.log(),
+            ["Sent share 1", "Failed to send share 2", "Sent share 3"]
+        );
+
+        let delivered = vec![
+            transport.delivered(1).unwrap().clone(),
+            transport.delivered(3).unwrap().clone(),
+        ];
+        assert_eq!(from_shares(delivered).unwrap(), b"secret");
+    }
+}
@@ -0,0 +1,514 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use std::fmt;
+
+use crate::mnemonic;
+use crate::qr;
+use crate::sskr::{cbor_bytes, cbor_unwrap_bytes};
+use crate::wrapper;
+
+/// The text forms a share can be written in, which all hold the exact same share bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareFormat {
+    /// Lowercase hexadecimal
+    Hex,
+    /// Standard base64, with padding
+    Base64,
+    /// Words of the BIP39 English wordlist, see `mnemonic::to_words`
+    Mnemonic,
+    /// A single `ur:bytes` URI, which fits in one QR code
+    Qr,
+}
+
+/// How a share file was written, as detected by `read_share_file`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareEncoding {
+    /// The share itself, as written by the CLI
+    Binary,
+    /// The share in a frame with magic bytes and a checksum, see `wrapper::frame_share`
+    Framed,
+    /// One of the text forms
+    Text(ShareFormat),
+    /// One of the text forms, between `-----BEGIN ...-----` and `-----END ...-----` lines
+    Armored(ShareFormat),
+}
+
+impl fmt::Display for ShareFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareFormat::Hex => write!(f, "hex"),
+            ShareFormat::Base64 => write!(f, "base64"),
+            ShareFormat::Mnemonic => write!(f, "words"),
+            ShareFormat::Qr => write!(f, "UR"),
+        }
+    }
+}
+
+impl fmt::Display for ShareEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShareEncoding::Binary => write!(f, "binary"),
+            ShareEncoding::Framed => write!(f, "framed binary"),
+            ShareEncoding::Text(format) => write!(f, "{}", format),
+            ShareEncoding::Armored(format) => write!(f, "armored {}", format),
+        }
+    }
+}
+
+/// Converts a share from one text form to another, without needing the secret or any other share
+///
+/// The form of the input is detected, a `ur:bytes` URI may also be given as the parts of a
+/// multi-part UR on separate lines.
+pub fn reencode_share(input: &str, to: ShareFormat) -> Result<String> {
+    Ok(encode_share(&decode_share(input)?, to))
+}
+
+/// Writes a share in the given text form
+pub fn encode_share(share: &[u8], format: ShareFormat) -> String {
+    match format {
+        ShareFormat::Hex => share.iter().map(|b| format!("{:02x}", b)).collect(),
+        ShareFormat::Base64 => STANDARD.encode(share),
+        ShareFormat::Mnemonic => mnemonic::to_words(share),
+        ShareFormat::Qr => ur::encode(&cbor_bytes(share), &ur::Type::Bytes),
+    }
+}
+
+/// Reads a share written in any of the text forms
+///
+/// Shares pasted from email or chat are cleaned up first, see `strip_share_text`, and line breaks
+/// inside them are ignored.
+pub fn decode_share(input: &str) -> Result<Vec<u8>> {
+    let text = strip_share_text(input);
+    if text.is_empty() {
+        return Err(anyhow::anyhow!("Share is empty"));
+    }
+
+    match detect_format(text) {
+        ShareFormat::Hex => {
+            let hex = remove_noise(text);
+            Ok((0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+                .collect())
+        }
+        ShareFormat::Base64 => {
+            decode_base64(input).map_err(|e| e.context("Share is not hex, base64, words or a UR"))
+        }
+        ShareFormat::Mnemonic => mnemonic::from_words(&words(text).join(" ")),
+        ShareFormat::Qr => {
+            // The parts of a multi-part UR are on lines of their own, a single UR may be wrapped
+            let lines = text
+                .lines()
+                .map(remove_noise)
+                .filter(|l| !l.is_empty())
+                .collect::<Vec<String>>();
+            if lines.len() > 1
+                && lines
+                    .iter()
+                    .all(|l| l.to_ascii_lowercase().starts_with("ur:"))
+            {
+                return qr::from_parts(&lines);
+            }
+
+            let input = lines.concat();
+            if !input.to_ascii_lowercase().starts_with("ur:bytes/") {
+                return Err(anyhow::anyhow!("Unsupported UR type, expected \"bytes\""));
+            }
+            match ur::decode(&input) {
+                Ok((ur::ur::Kind::SinglePart, message)) => cbor_unwrap_bytes(&message),
+                Ok((ur::ur::Kind::MultiPart, _)) => qr::from_parts(&[input]),
+                Err(e) => Err(anyhow::anyhow!("Invalid UR \"{}\": {}", input, e)),
+            }
+        }
+    }
+}
+
+/// Reads the contents of a file holding a native share in any encoding, `None` if it holds none
+///
+/// The magic bytes of a binary share are checked first, then the lines of armor around text, and
+/// last the characters of the text tell its form, see `detect_format`. Text that is valid as more
+/// than one form always resolves the same way, hex before base64, so a share is only taken when
+/// it decodes to a native share. A framed share is only taken when it matches its checksum.
+pub fn read_share_file(contents: &[u8]) -> Option<(Vec<u8>, ShareEncoding)> {
+    if wrapper::is_share(contents) {
+        return Some((contents.to_vec(), ShareEncoding::Binary));
+    }
+    if let Ok(share) = wrapper::unframe_share(contents) {
+        return wrapper::is_share(share).then(|| (share.to_vec(), ShareEncoding::Framed));
+    }
+
+    let text = std::str::from_utf8(contents).ok()?;
+    let (text, armored) = match armor_body(text) {
+        Some(body) => (body, true),
+        None => (text, false),
+    };
+
+    let (share, format) = decode_text_share(text, wrapper::is_share)?;
+    match armored {
+        true => Some((share, ShareEncoding::Armored(format))),
+        false => Some((share, ShareEncoding::Text(format))),
+    }
+}
+
+/// Recovers the secret from native shares given as text, each in any of the forms
+/// `read_share_file` reads, so one may be hex, the next base64 between armor lines, and so on
+pub fn from_encoded_shares(shares: &[String]) -> Result<Vec<u8>> {
+    let mut decoded: Vec<Vec<u8>> = Vec::new();
+    for (position, share) in shares.iter().enumerate() {
+        match read_share_file(share.as_bytes()) {
+            Some((share, _)) => decoded.push(share),
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Share {} is not a native share in any encoding",
+                    position + 1
+                ))
+            }
+        }
+    }
+
+    wrapper::from_shares(decoded)
+}
+
+/// Reads the contents of a file holding any number of native shares, like armored blocks `cat`-ed
+/// together, a chat export, or binary shares back to back
+///
+/// Binary shares are read one after the other, by the lengths in their headers. Text is split into
+/// armored blocks, and the rest into paragraphs, which may hold a share wrapped over several lines,
+/// then into lines, and last into the word at the end of a line, like after the name of whoever
+/// sent it. Whatever is not a share is skipped, and exact copies are only kept once. A file with a
+/// single share is read like `read_share_file`.
+pub fn read_share_bundle(contents: &[u8]) -> Vec<(Vec<u8>, ShareEncoding)> {
+    let mut shares = match split_binary_shares(contents) {
+        Some(shares) => shares,
+        None => match std::str::from_utf8(contents) {
+            Ok(text) => split_text_shares(text),
+            Err(_) => Vec::new(),
+        },
+    };
+    if shares.len() <= 1 {
+        return read_share_file(contents).into_iter().collect();
+    }
+
+    let mut unique: Vec<(Vec<u8>, ShareEncoding)> = Vec::new();
+    for share in shares.drain(..) {
+        if !unique.iter().any(|(u, _)| *u == share.0) {
+            unique.push(share);
+        }
+    }
+    unique
+}
+
+/// The binary shares the bytes consist of, `None` if anything else is in between
+fn split_binary_shares(mut contents: &[u8]) -> Option<Vec<(Vec<u8>, ShareEncoding)>> {
+    let mut shares: Vec<(Vec<u8>, ShareEncoding)> = Vec::new();
+    while !contents.is_empty() {
+        let (share, rest) = contents.split_at(wrapper::share_len(contents)?);
+        shares.push((share.to_vec(), ShareEncoding::Binary));
+        contents = rest;
+    }
+    Some(shares)
+}
+
+fn split_text_shares(text: &str) -> Vec<(Vec<u8>, ShareEncoding)> {
+    let mut shares: Vec<(Vec<u8>, ShareEncoding)> = Vec::new();
+    let mut rest = text;
+    loop {
+        // The text up to the next armored block, or all of what is left
+        let (plain, block) = match rest.find("-----BEGIN ") {
+            Some(begin) => rest.split_at(begin),
+            None => (rest, ""),
+        };
+        shares.extend(split_plain_shares(plain));
+
+        let body = match armor_body(block) {
+            Some(body) => body,
+            None => break,
+        };
+        if let Some((share, format)) = decode_text_share(body, is_whole_share) {
+            shares.push((share, ShareEncoding::Armored(format)));
+        }
+
+        // Past the END line of the block
+        let end = body.as_ptr() as usize - block.as_ptr() as usize + body.len();
+        rest = match block[end..].find('\n') {
+            Some(newline) => &block[end + newline + 1..],
+            None => "",
+        };
+    }
+    shares
+}
+
+/// The shares in text without armor, by paragraph, line, or the last word of a line
+fn split_plain_shares(text: &str) -> Vec<(Vec<u8>, ShareEncoding)> {
+    let lines = text.lines().collect::<Vec<&str>>();
+    let mut shares: Vec<(Vec<u8>, ShareEncoding)> = Vec::new();
+    for paragraph in lines
+        .split(|l| l.trim().is_empty())
+        .filter(|p| !p.is_empty())
+    {
+        if let Some((share, format)) = decode_text_share(&paragraph.join("\n"), is_whole_share) {
+            shares.push((share, ShareEncoding::Text(format)));
+            continue;
+        }
+
+        for line in paragraph.iter() {
+            let last_word = words(line).last().copied().unwrap_or_default();
+            let found = decode_text_share(line, is_whole_share)
+                .or_else(|| decode_text_share(last_word, is_whole_share));
+            if let Some((share, format)) = found {
+                shares.push((share, ShareEncoding::Text(format)));
+            }
+        }
+    }
+    shares
+}
+
+/// Decodes text that holds a share in any of the text forms, if it passes the check
+fn decode_text_share(text: &str, check: fn(&[u8]) -> bool) -> Option<(Vec<u8>, ShareFormat)> {
+    let format = detect_format(strip_share_text(text));
+    let share = decode_share(text).ok().filter(|s| check(s))?;
+    Some((share, format))
+}
+
+/// Whether the bytes are exactly one share, the text of several shares run together decodes to
+/// one share followed by the others
+fn is_whole_share(bytes: &[u8]) -> bool {
+    wrapper::share_len(bytes) == Some(bytes.len())
+}
+
+/// The text between the BEGIN and END lines of armor, like the ones around a PEM key
+fn armor_body(text: &str) -> Option<&str> {
+    let begin = text.find("-----BEGIN ")?;
+    let start = begin + text[begin..].find('\n')? + 1;
+    let end = start + text[start..].find("-----END ")?;
+    Some(&text[start..end])
+}
+
+/// The share in text that went through email or chat, without the byte order marks, whitespace,
+/// quotes, and backticks around it
+pub fn strip_share_text(input: &str) -> &str {
+    input.trim_matches(|c: char| is_noise(c) || QUOTES.contains(&c))
+}
+
+/// Quotes and backticks, straight and curly, that a share gets wrapped in when it is sent
+const QUOTES: &[char] = &[
+    '"', '\'', '`', '\u{201c}', '\u{201d}', '\u{2018}', '\u{2019}', '\u{ab}', '\u{bb}',
+];
+
+/// Characters that are never part of a share, but end up in and around it when copying it
+fn is_noise(c: char) -> bool {
+    c.is_whitespace()
+        || matches!(
+            c,
+            '\u{feff}' | '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{2060}'
+        )
+}
+
+/// The text without whitespace, line breaks and invisible characters
+pub(crate) fn remove_noise(text: &str) -> String {
+    text.chars().filter(|c| !is_noise(*c)).collect()
+}
+
+/// The words of the text, split on any whitespace and invisible characters
+pub(crate) fn words(text: &str) -> Vec<&str> {
+    text.split(is_noise).filter(|w| !w.is_empty()).collect()
+}
+
+/// Decodes standard base64 that went through email or chat
+///
+/// When it can't be decoded, the error points at the first character of the input that is not
+/// base64, by its line and column.
+pub(crate) fn decode_base64(input: &str) -> Result<Vec<u8>> {
+    let text = strip_share_text(input);
+    let error = match STANDARD.decode(remove_noise(text)) {
+        Ok(bytes) => return Ok(bytes),
+        Err(e) => e,
+    };
+
+    // The stripped text starts somewhere within the input, the positions are of the input
+    let offset = text.as_ptr() as usize - input.as_ptr() as usize;
+    let invalid = text.char_indices().find(|(_, c)| {
+        !(is_noise(*c) || c.is_ascii_alphanumeric() || matches!(c, '+' | '/' | '='))
+    });
+
+    match invalid {
+        Some((i, c)) => {
+            let before = &input[..offset + i];
+            let line = before.matches('\n').count() + 1;
+            let column = before
+                .rsplit('\n')
+                .next()
+                .unwrap_or_default()
+                .chars()
+                .count()
+                + 1;
+            Err(anyhow::anyhow!(
+                "Unexpected character '{}' at line {}, column {}",
+                c.escape_debug(),
+                line,
+                column
+            ))
+        }
+        None => Err(anyhow::anyhow!("Invalid base64: {}", error)),
+    }
+}
+
+/// The text form of a stripped share, by the characters in it
+///
+/// Checked in order: a UR starts with `ur:`, words are short and only letters, hex has an even
+/// number of hex digits, and anything else is taken for base64.
+fn detect_format(text: &str) -> ShareFormat {
+    let words = words(text);
+    let compact = remove_noise(text);
+
+    if text.to_ascii_lowercase().starts_with("ur:") {
+        ShareFormat::Qr
+    } else if words.len() > 1
+        && words
+            .iter()
+            .all(|w| w.len() <= 8 && w.chars().all(|c| c.is_ascii_alphabetic()))
+    {
+        // BIP39 words are at most 8 letters, lines of wrapped base64 are much longer
+        ShareFormat::Mnemonic
+    } else if compact.len().is_multiple_of(2) && compact.chars().all(|c| c.is_ascii_hexdigit()) {
+        ShareFormat::Hex
+    } else {
+        ShareFormat::Base64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn framed_share_files_are_read() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+        let framed = wrapper::frame_share(&share);
+        assert_eq!(
+            read_share_file(&framed),
+            Some((share, ShareEncoding::Framed))
+        );
+
+        let mut damaged = framed.clone();
+        damaged[20] ^= 1;
+        assert_eq!(read_share_file(&damaged), None);
+    }
+
+    #[test]
+    fn reencoding_keeps_the_share_bytes() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+
+        let hex = encode_share(&share, ShareFormat::Hex);
+        let mnemonic = reencode_share(&hex, ShareFormat::Mnemonic).unwrap();
+        assert_eq!(decode_share(&mnemonic).unwrap(), share);
+        let base64 = reencode_share(&mnemonic, ShareFormat::Base64).unwrap();
+        assert_eq!(decode_share(&base64).unwrap(), share);
+        let ur = reencode_share(&base64, ShareFormat::Qr).unwrap();
+        assert_eq!(decode_share(&ur).unwrap(), share);
+
+        assert_eq!(reencode_share(&ur, ShareFormat::Hex).unwrap(), hex);
+    }
+
+    #[test]
+    fn mangled_text_shares_are_decoded() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+        let base64 = encode_share(&share, ShareFormat::Base64);
+        let hex = encode_share(&share, ShareFormat::Hex);
+        let words = encode_share(&share, ShareFormat::Mnemonic);
+
+        // Wrapped at 76 characters like email does, with CRLF line ends
+        let wrapped = base64
+            .as_bytes()
+            .chunks(76)
+            .map(|line| std::str::from_utf8(line).unwrap())
+            .collect::<Vec<&str>>()
+            .join("\r\n");
+        let mangled = [
+            format!("\u{feff}{}\r\n", wrapped),
+            format!("\u{201c}{}\u{201d}", base64),
+            format!("```\n{}\n```\n", hex),
+            format!("'{}'", hex.replace("a", "a\u{200b}")),
+            format!("  {}\n\n", words.replace(' ', "\n")),
+        ];
+        for text in mangled.iter() {
+            assert_eq!(decode_share(text).unwrap(), share, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn undecodable_shares_point_at_the_offending_character() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+        let base64 = encode_share(&share, ShareFormat::Base64);
+        let text = format!("\"{}\n{}!{}\"", &base64[..8], &base64[8..12], &base64[12..]);
+
+        let error = decode_share(&text).unwrap_err();
+        assert_eq!(
+            error.root_cause().to_string(),
+            "Unexpected character '!' at line 2, column 5"
+        );
+    }
+
+    #[test]
+    fn share_file_encodings_are_detected() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+        let base64 = encode_share(&share, ShareFormat::Base64);
+        let armored = format!(
+            "-----BEGIN SHAMIR SHARE-----\n{}\n-----END SHAMIR SHARE-----\n",
+            base64
+        );
+
+        let files = [
+            (share.clone(), ShareEncoding::Binary),
+            (wrapper::frame_share(&share), ShareEncoding::Framed),
+            (
+                base64.into_bytes(),
+                ShareEncoding::Text(ShareFormat::Base64),
+            ),
+            (
+                armored.into_bytes(),
+                ShareEncoding::Armored(ShareFormat::Base64),
+            ),
+        ];
+        for format in [ShareFormat::Hex, ShareFormat::Mnemonic, ShareFormat::Qr] {
+            let text = encode_share(&share, format);
+            assert_eq!(
+                read_share_file(text.as_bytes()),
+                Some((share.clone(), ShareEncoding::Text(format)))
+            );
+        }
+        for (contents, encoding) in files {
+            assert_eq!(read_share_file(&contents), Some((share.clone(), encoding)));
+        }
+    }
+
+    #[test]
+    fn text_valid_as_hex_and_base64_is_read_as_hex() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+
+        // Hex digits are all base64 characters as well
+        let hex = encode_share(&share, ShareFormat::Hex);
+        assert!(hex.chars().all(|c| c.is_ascii_alphanumeric()));
+        for _ in 0..3 {
+            assert_eq!(
+                read_share_file(hex.as_bytes()),
+                Some((share.clone(), ShareEncoding::Text(ShareFormat::Hex)))
+            );
+        }
+
+        // Text that is neither hex nor base64 of a share is no share in either form
+        assert_eq!(read_share_file(b"deadbeefdeadbeef"), None);
+    }
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+
+/// Errors callers may want to tell apart, they are returned wrapped in an `anyhow::Error` and can
+/// be recovered with `downcast_ref`
+///
+/// With the `serde` feature they serialize to the name of the variant along with its fields, like
+/// `{"error":"SecretTooLarge","claimed":1000,"limit":100}`, for JSON error responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize), serde(tag = "error"))]
+pub enum ShamirError {
+    /// The shares do not come from the same split, or some of them are damaged
+    ///
+    /// Shares that don't record their threshold also fail with it when too few are given, as that
+    /// can't be told apart from damage.
+    InconsistentShares,
+    /// Fewer shares were given than are needed to recover the secret
+    ///
+    /// `need` is the threshold, when it is known, most shares don't record theirs. Serialized it is
+    /// left out when it is not known.
+    NotEnoughShares {
+        have: usize,
+        #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+        need: Option<usize>,
+    },
+    /// There is nothing to split
+    EmptySecret,
+    /// The operating system could not provide any randomness
+    RngUnavailable,
+    /// The secret or the parameters are outside of what the share format supports
+    UnsupportedParameters,
+    /// A share was changed after it was created, like being given a different index
+    ShareTampered,
+    /// The shares claim a secret larger than the caller allows, so nothing was allocated for it
+    SecretTooLarge { claimed: usize, limit: usize },
+    /// A share was asked for beyond the number of shares the secret was split into
+    ShareIndexOutOfRange { index: u8, max: u8 },
+    /// More shares were asked for than there are indices in the field, which has `max` of them
+    TooManyShares { max: u8 },
+    /// A share ends before the lengths of its fields, or its scheme, could be read
+    TruncatedHeader,
+    /// A share has an empty info fragment, or a length that runs past the end of the share
+    LengthOutOfBounds,
+    /// A share has a scheme or fields no version of the share format knows, or is in a frame of a
+    /// version `wrapper::unframe_share` can't read
+    UnsupportedVersion,
+    /// Bytes given as a framed share don't start with `wrapper::FRAME_MAGIC`
+    BadMagic,
+    /// A framed share does not match the checksum of its frame, it was damaged after framing
+    ChecksumMismatch,
+    /// A buffer given to write into is shorter than what has to be written
+    BufferTooSmall { needed: usize, available: usize },
+    /// The shares are past the expiry they were split with, in seconds since the Unix epoch, and
+    /// the grace period after it
+    ShareExpired { expires_at: u64 },
+    /// The encrypted data of a share is shorter than the length recorded for it, as after an
+    /// incomplete download
+    TruncatedCiphertext { expected: usize, got: usize },
+    /// Fewer distinct shares were given than the quorum the shares were split with, exact copies
+    /// of a share only count once
+    QuorumNotMet { given: usize, required: u8 },
+}
+
+impl fmt::Display for ShamirError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShamirError::InconsistentShares => write!(f, "Shares are inconsistent"),
+            ShamirError::NotEnoughShares {
+                have,
+                need: Some(need),
+            } => write!(
+                f,
+                "Not enough shares to recover the secret, {} of the {} needed are given",
+                have, need
+            ),
+            ShamirError::NotEnoughShares { have, need: None } => write!(
+                f,
+                "Not enough shares to recover the secret, {} are given",
+                have
+            ),
+            ShamirError::EmptySecret => write!(f, "Secret is empty"),
+            ShamirError::RngUnavailable => write!(f, "No randomness available from the system"),
+            ShamirError::UnsupportedParameters => {
+                write!(f, "Parameters are not supported by the share format")
+            }
+            ShamirError::ShareTampered => write!(f, "Share was tampered with"),
+            ShamirError::SecretTooLarge { claimed, limit } => write!(
+                f,
+                "Shares claim a secret of {} bytes, more than the limit of {} bytes",
+                claimed, limit
+            ),
+            ShamirError::ShareIndexOutOfRange { index, max } => write!(
+                f,
+                "Share index {} is out of range, the secret has {} shares",
+                index, max
+            ),
+            ShamirError::TooManyShares { max } => {
+                write!(
+                    f,
+                    "Too many shares, a secret can be split into at most {}",
+                    max
+                )
+            }
+            ShamirError::TruncatedHeader => write!(f, "Share is cut short"),
+            ShamirError::LengthOutOfBounds => {
+                write!(f, "Share has a length that does not fit the share")
+            }
+            ShamirError::UnsupportedVersion => {
+                write!(f, "Share is of a version this program can't read")
+            }
+            ShamirError::BadMagic => write!(f, "Share is not framed"),
+            ShamirError::ChecksumMismatch => {
+                write!(f, "Share does not match the checksum of its frame")
+            }
+            ShamirError::BufferTooSmall { needed, available } => write!(
+                f,
+                "Buffer of {} bytes is too small, {} bytes are needed",
+                available, needed
+            ),
+            ShamirError::ShareExpired { .. } => write!(f, "Shares are past their expiry"),
+            ShamirError::TruncatedCiphertext { expected, got } => write!(
+                f,
+                "Encrypted data is cut short, it has {} of its {} bytes",
+                got, expected
+            ),
+            ShamirError::QuorumNotMet { given, required } => write!(
+                f,
+                "Only {} of the {} shares the quorum requires are given",
+                given, required
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ShamirError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_known_thresholds_are_displayed() {
+        let known = ShamirError::NotEnoughShares {
+            have: 2,
+            need: Some(4),
+        };
+        assert_eq!(
+            known.to_string(),
+            "Not enough shares to recover the secret, 2 of the 4 needed are given"
+        );
+        let unknown = ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        };
+        assert_eq!(
+            unknown.to_string(),
+            "Not enough shares to recover the secret, 0 are given"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_to_variant_and_fields() {
+        let error = ShamirError::NotEnoughShares {
+            have: 2,
+            need: Some(3),
+        };
+        assert_eq!(
+            serde_json::to_string(&error).unwrap(),
+            r#"{"error":"NotEnoughShares","have":2,"need":3}"#
+        );
+        let unknown = ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        };
+        assert_eq!(
+            serde_json::to_string(&unknown).unwrap(),
+            r#"{"error":"NotEnoughShares","have":0}"#
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serializes_variant_without_fields() {
+        assert_eq!(
+            serde_json::to_string(&ShamirError::EmptySecret).unwrap(),
+            r#"{"error":"EmptySecret"}"#
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::Result;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use ureq::config::RedirectAuthHeaders;
+use ureq::tls::TlsConfig;
+
+use crate::wrapper;
+
+/// The environment variable holding the bearer token sent along with requests to https URLs
+pub const TOKEN_VARIABLE: &str = "SHAMIR_FETCH_TOKEN";
+
+/// The most bytes read from a URL, enough for a share of the largest secret `from_shares` recovers
+pub const DEFAULT_MAX_BYTES: usize = wrapper::DEFAULT_MAX_SECRET_BYTES + 1024 * 1024;
+
+/// The time a request may take by default, from connecting to reading the last byte
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How shares are fetched from URLs
+#[derive(Debug, Clone)]
+pub struct FetchOptions {
+    pub max_bytes: usize,
+    pub timeout: Duration,
+    /// Sent as `Authorization: Bearer <token>`, also to the same host after a redirect
+    pub bearer_token: Option<String>,
+    /// Skips checking the certificate of the server, which lets anyone on the way read and change
+    /// the shares
+    pub insecure_tls: bool,
+}
+
+impl Default for FetchOptions {
+    /// The default limits, with the bearer token read from `SHAMIR_FETCH_TOKEN` when it is set
+    fn default() -> Self {
+        FetchOptions {
+            max_bytes: DEFAULT_MAX_BYTES,
+            timeout: DEFAULT_TIMEOUT,
+            bearer_token: env::var(TOKEN_VARIABLE).ok().filter(|t| !t.is_empty()),
+            insecure_tls: false,
+        }
+    }
+}
+
+/// Whether the input is a URL instead of a path, URLs of unsupported schemes included so they fail
+/// with a clear error
+pub fn is_url(input: &str) -> bool {
+    let input = input.to_ascii_lowercase();
+    ["https://", "http://", "file://"]
+        .iter()
+        .any(|scheme| input.starts_with(scheme))
+}
+
+/// Reads a share from an `https://` or `file://` URL, failing with an error that names the URL
+///
+/// Plain http is refused, also when a server redirects to it, as shares are only fetched over TLS.
+pub fn fetch_share(url: &str, options: &FetchOptions) -> Result<Vec<u8>> {
+    fetch(url, options).map_err(|e| e.context(format!("Failed to fetch \"{}\"", url)))
+}
+
+fn fetch(url: &str, options: &FetchOptions) -> Result<Vec<u8>> {
+    let scheme_end = url.find("://").map_or(0, |i| i + 3);
+    match url[..scheme_end].to_ascii_lowercase().as_str() {
+        "https://" => {}
+        "file://" => {
+            let path = file_url_path(&url[scheme_end..])?;
+            let size = fs::metadata(&path)?.len();
+            if size > options.max_bytes as u64 {
+                return Err(anyhow::anyhow!(
+                    "File is {} bytes, more than the limit of {} bytes",
+                    size,
+                    options.max_bytes
+                ));
+            }
+            return Ok(fs::read(path)?);
+        }
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Only https:// and file:// URLs are supported"
+            ))
+        }
+    }
+
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        .https_only(true)
+        .timeout_global(Some(options.timeout))
+        .redirect_auth_headers(RedirectAuthHeaders::SameHost)
+        .tls_config(
+            TlsConfig::builder()
+                .disable_verification(options.insecure_tls)
+                .build(),
+        )
+        .build()
+        .into();
+
+    let mut request = agent.get(url);
+    if let Some(token) = options.bearer_token.as_deref() {
+        request = request.header("Authorization", &format!("Bearer {}", token));
+    }
+
+    let mut response = request.call()?;
+    let share = response
+        .body_mut()
+        .with_config()
+        .limit(options.max_bytes as u64)
+        .read_to_vec()?;
+    Ok(share)
+}
+
+/// The local path of a `file://` URL without its scheme, which has no host or `localhost`
+fn file_url_path(rest: &str) -> Result<PathBuf> {
+    let path = match rest.strip_prefix("localhost") {
+        Some(path) => path,
+        None => rest,
+    };
+    if !path.starts_with('/') {
+        return Err(anyhow::anyhow!(
+            "Only file:// URLs of local files are supported"
+        ));
+    }
+
+    // Bytes that are not allowed in URLs, like spaces, are written as %XX
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut rest = path.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        let escaped = tail
+            .get(..2)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(decoded) if byte == b'%' => {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            }
+            _ => {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+    }
+
+    Ok(PathBuf::from(String::from_utf8(bytes)?))
+}
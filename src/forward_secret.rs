@@ -0,0 +1,180 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Key,
+};
+use anyhow::Result;
+use rand::rngs::StdRng;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+use crate::error::ShamirError;
+use crate::shamir::{self, secure_rng};
+use crate::wrapper::{share_index, to_shares};
+
+/// A share encrypted to the key of its custodian, with an ephemeral key used for this share only
+#[derive(Debug, Serialize, Deserialize)]
+struct WrappedShare {
+    /// The index of the share, in the clear to tell the shares apart
+    index: u8,
+    ephemeral_public: [u8; 32],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+/// Splits the input like `to_shares` into one share for every custodian, each wrapped for the
+/// X25519 public key of its custodian, in the same order
+///
+/// This is forward-secret share generation: every share is wrapped with a key agreed with a fresh
+/// ephemeral X25519 key, whose secret is used once and zeroized right after, as are the plain
+/// shares and the wrapping keys. Once the wrapped shares are handed out, the dealer holds nothing
+/// that could reproduce or unwrap them, so compromising the dealer machine afterwards gives nothing
+/// away. Custodians unwrap their share with `unwrap_share` before recovering.
+pub fn to_shares_forward_secret(
+    input: &[u8],
+    threshold: u8,
+    custodians: &[[u8; 32]],
+) -> Result<Vec<Vec<u8>>> {
+    let count = shamir::checked_share_count(custodians.len())?;
+    let mut shares = to_shares(input.to_vec(), threshold, count)?;
+
+    let wrapped = secure_rng().and_then(|mut rng| {
+        shares
+            .iter()
+            .zip(custodians)
+            .map(|(share, custodian)| wrap_share(share, custodian, &mut rng))
+            .collect::<Result<Vec<Vec<u8>>>>()
+    });
+    for share in shares.iter_mut() {
+        share.zeroize();
+    }
+
+    wrapped
+}
+
+fn wrap_share(share: &[u8], custodian: &[u8; 32], rng: &mut StdRng) -> Result<Vec<u8>> {
+    let custodian = PublicKey::from(*custodian);
+    let ephemeral = EphemeralSecret::random_from_rng(&mut *rng);
+    let ephemeral_public = PublicKey::from(&ephemeral);
+
+    // Agreeing on the key consumes the ephemeral secret, which is zeroized as it is dropped
+    let shared = ephemeral.diffie_hellman(&custodian);
+    if !shared.was_contributory() {
+        return Err(anyhow::anyhow!(
+            "Custodian key is not a valid X25519 public key"
+        ));
+    }
+
+    let mut key = wrapping_key(shared.as_bytes(), &ephemeral_public, &custodian);
+    let nonce = Aes256Gcm::generate_nonce(&mut *rng);
+    let ciphertext = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)).encrypt(&nonce, share);
+    key.zeroize();
+
+    let wrapped = WrappedShare {
+        index: share_index(share).ok_or(ShamirError::InconsistentShares)?,
+        ephemeral_public: ephemeral_public.to_bytes(),
+        nonce: nonce.into(),
+        ciphertext: ciphertext.map_err(|_| anyhow::anyhow!("Encryption failed"))?,
+    };
+    Ok(bincode::serialize(&wrapped)?)
+}
+
+/// Unwraps a share of `to_shares_forward_secret` with the X25519 secret key of its custodian
+pub fn unwrap_share(wrapped: &[u8], secret_key: &[u8; 32]) -> Result<Vec<u8>> {
+    let wrapped: WrappedShare = bincode::deserialize(wrapped)
+        .map_err(|_| anyhow::anyhow!("Share is not a wrapped share"))?;
+
+    let secret = StaticSecret::from(*secret_key);
+    let custodian = PublicKey::from(&secret);
+    let ephemeral_public = PublicKey::from(wrapped.ephemeral_public);
+    let shared = secret.diffie_hellman(&ephemeral_public);
+
+    let mut key = wrapping_key(shared.as_bytes(), &ephemeral_public, &custodian);
+    let share = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+        .decrypt(&wrapped.nonce.into(), wrapped.ciphertext.as_ref());
+    key.zeroize();
+
+    let share = share.map_err(|_| anyhow::anyhow!("Share is not wrapped for this key"))?;
+    if share_index(&share) != Some(wrapped.index) {
+        return Err(anyhow::Error::new(ShamirError::ShareTampered)
+            .context("Wrapped share does not have the index it claims"));
+    }
+
+    Ok(share)
+}
+
+/// The index of a wrapped share, which can be read without unwrapping it
+pub fn wrapped_share_index(wrapped: &[u8]) -> Option<u8> {
+    bincode::deserialize::<WrappedShare>(wrapped)
+        .ok()
+        .map(|wrapped| wrapped.index)
+}
+
+/// The X25519 public key a custodian hands to the dealer for their secret key
+pub fn custodian_public_key(secret_key: &[u8; 32]) -> [u8; 32] {
+    PublicKey::from(&StaticSecret::from(*secret_key)).to_bytes()
+}
+
+/// Derives the key a share is wrapped with, bound to both public keys of the agreement
+fn wrapping_key(shared: &[u8; 32], ephemeral: &PublicKey, custodian: &PublicKey) -> [u8; 32] {
+    Sha3_256::new()
+        .chain_update(b"shamir forward-secret share wrapping")
+        .chain_update(shared)
+        .chain_update(ephemeral.as_bytes())
+        .chain_update(custodian.as_bytes())
+        .finalize()
+        .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper::from_shares;
+    use x25519_dalek::SharedSecret;
+
+    /// Plain bytes need no dropping, so a type of them that does has a `Drop` that zeroizes it
+    fn zeroized_on_drop<T: Zeroize>() -> bool {
+        std::mem::needs_drop::<T>()
+    }
+
+    #[test]
+    fn wrapped_shares_recover_with_the_custodian_keys() {
+        let secret_keys = [[1u8; 32], [2u8; 32], [3u8; 32]];
+        let custodians = secret_keys.map(|key| custodian_public_key(&key));
+        let wrapped = to_shares_forward_secret(b"secret", 2, &custodians).unwrap();
+        assert_eq!(wrapped.len(), 3);
+
+        let shares = wrapped
+            .iter()
+            .zip(secret_keys.iter())
+            .skip(1)
+            .map(|(share, key)| unwrap_share(share, key).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+        assert_eq!(from_shares(shares).unwrap(), b"secret");
+        assert_eq!(wrapped_share_index(&wrapped[2]), Some(3));
+        assert!(unwrap_share(&wrapped[0], &secret_keys[1]).is_err());
+    }
+
+    #[test]
+    fn ephemeral_keys_are_used_once_and_wiped() {
+        // The secrets of the agreement are wiped as they are dropped, right after wrapping a share
+        assert!(zeroized_on_drop::<EphemeralSecret>());
+        assert!(zeroized_on_drop::<SharedSecret>());
+
+        let custodian = custodian_public_key(&[1u8; 32]);
+        let ephemeral_public = |wrapped: &[u8]| {
+            bincode::deserialize::<WrappedShare>(wrapped)
+                .unwrap()
+                .ephemeral_public
+        };
+        let first = to_shares_forward_secret(b"secret", 2, &[custodian, custodian]).unwrap();
+        let second = to_shares_forward_secret(b"secret", 2, &[custodian, custodian]).unwrap();
+        let keys = first
+            .iter()
+            .chain(second.iter())
+            .map(|wrapped| ephemeral_public(wrapped))
+            .collect::<Vec<[u8; 32]>>();
+        assert!((1..keys.len()).all(|i| !keys[..i].contains(&keys[i])));
+    }
+}
@@ -0,0 +1,52 @@
+use anyhow::Result;
+use yubikey::{MgmKey, YubiKey};
+
+/// The PIV data object shares are stored in, from the range YubiKeys leave free for applications
+pub const SHARE_OBJECT: u32 = 0x5f_ff00;
+
+/// The largest data object a YubiKey can store
+pub const MAX_SHARE_LEN: usize = 3063;
+
+/// Writes a share to a PIV data object of the inserted YubiKey
+///
+/// Writing needs the PIV management key, which is taken to be the factory default. Anyone can read
+/// the object back without the PIN, `load_share` only asks for it so that reconstructing needs
+/// both the token and the PIN.
+pub fn store_share(share: &[u8]) -> Result<()> {
+    if share.len() > MAX_SHARE_LEN {
+        return Err(anyhow::anyhow!(
+            "Share is {} bytes, but a hardware token holds at most {} bytes, split a key to the \
+             file instead of the file itself",
+            share.len(),
+            MAX_SHARE_LEN
+        ));
+    }
+
+    let mut yubikey = open()?;
+    yubikey.authenticate(MgmKey::default()).map_err(|_| {
+        anyhow::anyhow!("Hardware token does not use the default PIV management key")
+    })?;
+    yubikey.save_object(SHARE_OBJECT, &mut share.to_vec())?;
+
+    Ok(())
+}
+
+/// Reads a share back from the inserted YubiKey, after verifying its PIN
+pub fn load_share(pin: &[u8]) -> Result<Vec<u8>> {
+    let mut yubikey = open()?;
+    yubikey
+        .verify_pin(pin)
+        .map_err(|_| anyhow::anyhow!("Hardware token rejected the PIN"))?;
+
+    match yubikey.fetch_object(SHARE_OBJECT) {
+        Ok(share) => Ok(share.to_vec()),
+        Err(yubikey::Error::NotFound) => {
+            Err(anyhow::anyhow!("Hardware token does not hold a share"))
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn open() -> Result<YubiKey> {
+    YubiKey::open().map_err(|_| anyhow::anyhow!("No hardware token found, insert a YubiKey"))
+}
@@ -0,0 +1,3 @@
+pub mod sharks;
+pub mod sss;
+pub mod vault;
@@ -0,0 +1,176 @@
+use anyhow::Result;
+use rand::RngCore;
+
+use crate::error::ShamirError;
+use crate::shamir::secure_rng;
+
+/// The low byte of the field polynomial sharks uses, x^8 + x^4 + x^3 + x^2 + 1 (0x11d)
+///
+/// `SecretData` uses the AES polynomial instead, so sharks shares can't be interpolated with it
+const REDUCTION: u8 = 0x1d;
+
+/// Splits a secret into shares laid out like the `sharks` crate's, the x coordinate followed by
+/// one y byte for every byte of the secret
+///
+/// Like the sharks dealer, the shares are evaluated at x = 1, 2, 3 and so on
+pub fn to_shares(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    if secret.is_empty() {
+        return Err(ShamirError::EmptySecret.into());
+    }
+
+    if threshold == 0 || count < threshold {
+        return Err(anyhow::anyhow!(
+            "Threshold must be between 1 and the number of shares"
+        ));
+    }
+
+    // One polynomial for every byte, with the secret byte as its constant term
+    let mut rng = secure_rng()?;
+    let mut polynomials: Vec<Vec<u8>> = Vec::new();
+    for byte in secret.iter() {
+        let mut polynomial = vec![0u8; threshold as usize];
+        rng.fill_bytes(&mut polynomial[1..]);
+        polynomial[0] = *byte;
+        polynomials.push(polynomial);
+    }
+
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for x in 1..=count {
+        let mut share = vec![x];
+        for polynomial in polynomials.iter() {
+            // Horner's method, starting at the highest coefficient
+            let y = polynomial
+                .iter()
+                .rev()
+                .fold(0u8, |acc, c| gf256_mul(acc, x) ^ c);
+            share.push(y);
+        }
+        shares.push(share);
+    }
+
+    Ok(shares)
+}
+
+/// Recovers the secret from at least a threshold of sharks shares
+pub fn from_shares(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let length = shares[0].len();
+    if length < 2 || shares.iter().any(|s| s.len() != length) {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    let xs: Vec<u8> = shares.iter().map(|s| s[0]).collect();
+    for (i, x) in xs.iter().enumerate() {
+        if *x == 0 || xs[..i].contains(x) {
+            return Err(anyhow::anyhow!("Shares have duplicate or invalid x coordinates"));
+        }
+    }
+
+    // Lagrange interpolation at x = 0, where subtraction is the same as addition
+    let mut basis: Vec<u8> = Vec::new();
+    for (i, x_i) in xs.iter().enumerate() {
+        let mut product = 1u8;
+        for (j, x_j) in xs.iter().enumerate() {
+            if i != j {
+                product = gf256_mul(product, gf256_mul(*x_j, gf256_inv(x_j ^ x_i)));
+            }
+        }
+        basis.push(product);
+    }
+
+    let mut secret: Vec<u8> = Vec::new();
+    for byte in 1..length {
+        let mut value = 0u8;
+        for (share, b) in shares.iter().zip(basis.iter()) {
+            value ^= gf256_mul(share[byte], *b);
+        }
+        secret.push(value);
+    }
+
+    Ok(secret)
+}
+
+fn gf256_mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0u8;
+    while b != 0 {
+        if b & 1 != 0 {
+            product ^= a;
+        }
+        let carry = a & 0x80 != 0;
+        a <<= 1;
+        if carry {
+            a ^= REDUCTION;
+        }
+        b >>= 1;
+    }
+    product
+}
+
+/// The multiplicative inverse, a^254, as every non-zero element satisfies a^255 = 1
+fn gf256_inv(a: u8) -> u8 {
+    let mut result = 1u8;
+    let mut base = a;
+    let mut exponent = 254u8;
+    while exponent != 0 {
+        if exponent & 1 != 0 {
+            result = gf256_mul(result, base);
+        }
+        base = gf256_mul(base, base);
+        exponent >>= 1;
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Shares of "sharks secret" from `Sharks(3).dealer`, written by version 0.5.0 of the crate
+    const SHARKS_SHARES: [&str; 5] = [
+        "0153eaebad98d688c1df8e595f9d",
+        "026c2091030169e33a3bad3da982",
+        "034ca21bdcf2cc4b88814016936b",
+        "042ca15fffc16eb92105c6883a61",
+        "050c23d52032cb1193bf2ba30088",
+    ];
+
+    fn sharks_shares(positions: &[usize]) -> Vec<Vec<u8>> {
+        positions
+            .iter()
+            .map(|&i| {
+                let hex = SHARKS_SHARES[i];
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|j| u8::from_str_radix(&hex[j..j + 2], 16).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sharks_shares_are_recovered() {
+        for positions in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [4, 3, 2]] {
+            let secret = from_shares(sharks_shares(&positions)).unwrap();
+            assert_eq!(secret, b"sharks secret");
+        }
+
+        assert_ne!(
+            from_shares(sharks_shares(&[0, 1])).unwrap(),
+            b"sharks secret"
+        );
+    }
+
+    #[test]
+    fn shares_round_trip() {
+        let shares = to_shares(b"sharks secret", 3, 5).unwrap();
+        assert!(shares.iter().zip(1..).all(|(share, x)| share[0] == x));
+        assert_eq!(from_shares(shares[2..].to_vec()).unwrap(), b"sharks secret");
+    }
+}
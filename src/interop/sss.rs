@@ -0,0 +1,159 @@
+use anyhow::Result;
+use crypto_secretbox::{
+    aead::{Aead, KeyInit},
+    XSalsa20Poly1305,
+};
+use rand::RngCore;
+
+use crate::error::ShamirError;
+use crate::shamir::{secure_rng, SecretData};
+
+/// The exact size of the secrets sss shares
+pub const DATA_SIZE: usize = 64;
+
+/// The size of a key share, the x coordinate followed by 32 bytes of the key
+const KEYSHARE_SIZE: usize = 33;
+
+/// The size of a full share, the key share followed by the encrypted secret and its tag
+pub const SHARE_SIZE: usize = KEYSHARE_SIZE + DATA_SIZE + 16;
+
+/// Splits a 64 byte secret into shares readable by dsprenkels' sss
+///
+/// Like sss, a random key is split in the same GF(2^8) field as `SecretData`, and the secret is
+/// encrypted with XSalsa20-Poly1305 under that key and an all zero nonce
+pub fn to_shares(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    if secret.len() != DATA_SIZE {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context(format!("sss secrets must be exactly {} bytes", DATA_SIZE)));
+    }
+
+    if count == 0 || threshold == 0 || threshold > count {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context("sss needs a threshold between 1 and the number of shares"));
+    }
+
+    let mut key = [0u8; 32];
+    secure_rng()?.fill_bytes(&mut key);
+
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    let ciphertext = cipher
+        .encrypt(&[0u8; 24].into(), secret)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let secret_data = SecretData::with_secret(key.to_vec(), threshold)?;
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for i in 1..=count {
+        let mut share = secret_data.get_share(i)?;
+        share.extend_from_slice(&ciphertext);
+        shares.push(share);
+    }
+
+    Ok(shares)
+}
+
+/// Recovers the secret from a quorum of sss shares
+pub fn from_shares(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    if shares.iter().any(|s| s.len() != SHARE_SIZE) {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context(format!("sss shares must be exactly {} bytes", SHARE_SIZE)));
+    }
+
+    let keyshares = shares
+        .iter()
+        .map(|s| s[..KEYSHARE_SIZE].to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let key: [u8; 32] = SecretData::recover_secret(keyshares)?
+        .try_into()
+        .map_err(|_| ShamirError::InconsistentShares)?;
+
+    // Like sss, any share whose ciphertext opens with the key will do
+    let cipher = XSalsa20Poly1305::new(&key.into());
+    for share in shares.iter() {
+        if let Ok(secret) = cipher.decrypt(&[0u8; 24].into(), &share[KEYSHARE_SIZE..]) {
+            return Ok(secret);
+        }
+    }
+
+    // The key is wrong, either too few shares were given, or they are not from the same split
+    Err(anyhow::Error::new(ShamirError::InconsistentShares)
+        .context("No share decrypts with the key"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &[u8; DATA_SIZE] =
+        b"The quick brown fox jumps over the lazy dog, in sixty four bytes";
+
+    /// The encrypted secret and its tag, the same at the end of every share of one split
+    const CIPHERTEXT: &str = "bb3200061618ae247e6591bc9dfc97da44b153bf52635ddbb23dad3af16d0039a2d96255\
+                              eba90d43f68c558a42ba18914e24e7451d8b6e02c3a865674386b289b9b5a63de9e2f9\
+                              57c30980b7f925d228";
+
+    /// The key shares of `sss_create_shares` for a 3 of 5 split of `SECRET`, written by the
+    /// reference C implementation through version 0.1.7 of its `shamirsecretsharing` crate
+    const KEYSHARES: [&str; 5] = [
+        "01ac1944049982eef11f0a57281eef8a9d81ddab1f340d41fa44427169bbf55061",
+        "02f634252ce421a214f5933c53ef117fb5200cd992e3ac22efee8634a3d24e5f11",
+        "0378b318111502067f7548413a03fbdd93bd1d5a752234e787bec56936903ab529",
+        "04f01b59b4a48f5be694c49b489d6ca6b410f24ce6d3af0fea8810a2e12598c329",
+        "057e9c648955acff8d141fe621718604928de3cf011237ca82d853ff7467ec2911",
+    ];
+
+    fn sss_shares(positions: &[usize]) -> Vec<Vec<u8>> {
+        positions
+            .iter()
+            .map(|&i| {
+                let hex = format!("{}{}", KEYSHARES[i], CIPHERTEXT);
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|j| u8::from_str_radix(&hex[j..j + 2], 16).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn sss_shares_are_recovered() {
+        for positions in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [4, 3, 2]] {
+            assert_eq!(from_shares(sss_shares(&positions)).unwrap(), SECRET);
+        }
+
+        let error = from_shares(sss_shares(&[0, 1])).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::InconsistentShares)
+        );
+    }
+
+    #[test]
+    fn out_of_range_parameters_are_rejected() {
+        for result in [
+            to_shares(b"too short", 2, 3),
+            to_shares(SECRET, 4, 3),
+            to_shares(SECRET, 0, 3),
+        ] {
+            let error = result.unwrap_err();
+            assert_eq!(
+                error.downcast_ref::<ShamirError>(),
+                Some(&ShamirError::UnsupportedParameters)
+            );
+        }
+    }
+
+    #[test]
+    fn shares_round_trip() {
+        let shares = to_shares(SECRET, 3, 5).unwrap();
+        assert!(shares.iter().all(|share| share.len() == SHARE_SIZE));
+        assert_eq!(from_shares(shares[2..].to_vec()).unwrap(), SECRET);
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rand::seq::SliceRandom;
+
+use crate::encoding;
+use crate::shamir::{secure_rng, SecretData};
+
+/// Splits a secret into shares laid out like HashiCorp Vault's unseal key shares
+///
+/// Vault works in the same GF(2^8) field as `SecretData`, but appends the x coordinate to the end
+/// of every share instead of prepending it, and picks the x coordinates at random
+pub fn to_shares(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    if threshold < 2 || count < threshold {
+        return Err(anyhow::anyhow!(
+            "Vault requires a threshold of at least 2 and no more than the number of shares"
+        ));
+    }
+
+    let secret_data = SecretData::with_secret(secret.to_vec(), threshold)?;
+
+    let mut xs: Vec<u8> = (1..=255).collect();
+    xs.shuffle(&mut secure_rng()?);
+
+    let mut shares: Vec<Vec<u8>> = vec![];
+    for x in xs.iter().take(count as usize) {
+        let mut share = secret_data.get_share(*x)?;
+        share.rotate_left(1);
+        shares.push(share);
+    }
+
+    Ok(shares)
+}
+
+/// Recovers the secret from a quorum of Vault style shares
+pub fn from_shares(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    if shares.len() < 2 {
+        return Err(anyhow::anyhow!("Vault shares need a threshold of at least 2"));
+    }
+
+    let mut raw_shares: Vec<Vec<u8>> = vec![];
+    for mut share in shares {
+        if share.len() < 2 {
+            return Err(anyhow::anyhow!("Vault share is too short"));
+        }
+        share.rotate_right(1);
+        raw_shares.push(share);
+    }
+
+    SecretData::recover_secret(raw_shares)
+}
+
+/// Encodes a share the way `vault operator init` prints unseal keys
+pub fn to_base64(share: &[u8]) -> String {
+    STANDARD.encode(share)
+}
+
+/// Parses an unseal key share, in either the base64 or the hex form Vault prints
+pub fn parse_share(text: &str) -> Result<Vec<u8>> {
+    let hex = encoding::remove_noise(encoding::strip_share_text(text));
+
+    if hex.len().is_multiple_of(2) && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Ok((0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect());
+    }
+
+    encoding::decode_base64(text).map_err(|e| e.context("Invalid Vault unseal key share"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The unseal keys of the `vault operator init` example in Vault's getting started guide, a
+    /// 3 of 5 split
+    const UNSEAL_KEYS: [&str; 5] = [
+        "4jYbl2CBIv6SpkKj6Hos9iD32k5RfGkLzlosrrq/JgOm",
+        "B05G1DRtfYckFV5BbdBvXq0wkK5HFqB9g2jcDmNfTQiS",
+        "Arig0N9rN9ezkTRo7qTB7gsIZDaonOcc53EHo83F5chA",
+        "0cZE0C/gEk3YHaKjIWxhyyfs8REhqkRW/CSXTnmTilv+",
+        "fYhZOseRgzxmJCmIqUdxEm9C3jB5Q27AowER9w4FC2Ck",
+    ];
+
+    /// The key every 3 of the unseal keys recover
+    const UNSEAL_KEY: [u8; 32] = [
+        0xd2, 0x4e, 0x15, 0xf5, 0xd9, 0x92, 0x3e, 0xf2, 0x40, 0xb2, 0x69, 0xf0, 0x5a, 0xec, 0x3e,
+        0x3f, 0x74, 0x73, 0x1e, 0x9d, 0x43, 0xa8, 0x86, 0xe4, 0x0c, 0x9f, 0xf6, 0xe7, 0x2c, 0x35,
+        0x85, 0x35,
+    ];
+
+    fn unseal_keys(positions: &[usize]) -> Vec<Vec<u8>> {
+        positions
+            .iter()
+            .map(|&i| parse_share(UNSEAL_KEYS[i]).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn vault_unseal_keys_are_recovered() {
+        for positions in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [4, 3, 2]] {
+            assert_eq!(from_shares(unseal_keys(&positions)).unwrap(), UNSEAL_KEY);
+        }
+
+        // Vault shares carry no threshold, too few of them recover something else
+        assert_ne!(from_shares(unseal_keys(&[0, 1])).unwrap(), UNSEAL_KEY);
+    }
+
+    #[test]
+    fn vault_unseal_keys_are_read_as_hex() {
+        let share = parse_share(UNSEAL_KEYS[0]).unwrap();
+        let hex: String = share.iter().map(|b| format!("{:02x}", b)).collect();
+        assert_eq!(parse_share(&hex).unwrap(), share);
+        assert_eq!(to_base64(&share), UNSEAL_KEYS[0]);
+    }
+
+    #[test]
+    fn shares_round_trip() {
+        let shares = to_shares(&UNSEAL_KEY, 3, 5).unwrap();
+        assert!(shares.iter().all(|share| share.len() == 33));
+        assert_eq!(from_shares(shares[2..].to_vec()).unwrap(), UNSEAL_KEY);
+    }
+}
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use keyring::Entry;
+
+use crate::wrapper::share_set_id;
+
+/// The service every entry is stored under in the platform credential store
+const SERVICE: &str = "distributed_encrypt";
+
+/// The entry listing every stored share, as the credential stores can't be enumerated portably
+const INDEX_USER: &str = "index";
+
+/// A share stored in the platform credential store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyringEntry {
+    pub slot: String,
+    pub set_id: [u8; 32],
+}
+
+/// Stores a native share in the platform credential store, under the slot and the share's split
+pub fn store_share(slot: &str, share: &[u8]) -> Result<()> {
+    if slot.is_empty() || slot.contains(['\n', '\t', ':']) {
+        return Err(anyhow::anyhow!("Invalid keyring slot \"{}\"", slot));
+    }
+
+    let set_id = share_set_id(share).ok_or_else(|| anyhow::anyhow!("Invalid share"))?;
+    share_entry(slot, &set_id)?.set_secret(share)?;
+
+    let mut entries = list_entries()?;
+    if !entries.iter().any(|e| e.slot == slot && e.set_id == set_id) {
+        entries.push(KeyringEntry {
+            slot: slot.to_string(),
+            set_id,
+        });
+        write_index(&entries)?;
+    }
+
+    Ok(())
+}
+
+/// Loads a share from the platform credential store
+///
+/// Without a set id, the slot must hold a share of only one split
+pub fn load_share(slot: &str, set_id: Option<[u8; 32]>) -> Result<Vec<u8>> {
+    let matching = list_entries()?
+        .into_iter()
+        .filter(|e| e.slot == slot && set_id.is_none_or(|id| e.set_id == id))
+        .collect::<Vec<KeyringEntry>>();
+
+    match matching.as_slice() {
+        [entry] => Ok(share_entry(slot, &entry.set_id)?.get_secret()?),
+        [] => Err(anyhow::anyhow!(
+            "Keyring slot \"{}\" holds no share of this split",
+            slot
+        )),
+        _ => Err(anyhow::anyhow!(
+            "Keyring slot \"{}\" holds shares of several splits",
+            slot
+        )),
+    }
+}
+
+/// Lists every share stored in the platform credential store
+pub fn list_entries() -> Result<Vec<KeyringEntry>> {
+    let index = match Entry::new(SERVICE, INDEX_USER)?.get_password() {
+        Ok(index) => index,
+        Err(keyring::Error::NoEntry) => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut entries: Vec<KeyringEntry> = Vec::new();
+    for line in index.lines().filter(|l| !l.is_empty()) {
+        let entry = line
+            .split_once('\t')
+            .and_then(|(slot, set_id)| {
+                Some(KeyringEntry {
+                    slot: slot.to_string(),
+                    set_id: from_hex(set_id)?,
+                })
+            })
+            .ok_or_else(|| anyhow::anyhow!("Keyring index is damaged"))?;
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
+
+fn write_index(entries: &[KeyringEntry]) -> Result<()> {
+    let index = entries
+        .iter()
+        .map(|e| format!("{}\t{}\n", e.slot, to_hex(&e.set_id)))
+        .collect::<String>();
+    Entry::new(SERVICE, INDEX_USER)?.set_password(&index)?;
+    Ok(())
+}
+
+/// Entries are namespaced by split, so one slot can hold shares of several splits
+fn share_entry(slot: &str, set_id: &[u8; 32]) -> Result<Entry> {
+    Ok(Entry::new(
+        SERVICE,
+        &format!("{}:{}", slot, to_hex(set_id)),
+    )?)
+}
+
+/// Formats a set id the way `list_entries` is shown to users
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(bytes)
+}
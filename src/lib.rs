@@ -0,0 +1,34 @@
+pub mod additive;
+pub mod age;
+pub mod ceremony;
+pub mod chunked;
+pub mod collection;
+pub mod commitments;
+pub mod context;
+pub mod distribution;
+pub mod encoding;
+pub mod error;
+pub mod forward_secret;
+#[cfg(feature = "http")]
+pub mod fetch;
+#[cfg(feature = "hardware")]
+pub mod hardware;
+pub mod interop;
+#[cfg(feature = "keyring")]
+pub mod keychain;
+pub mod mnemonic;
+pub mod passphrase;
+pub mod pem;
+pub mod pinentry;
+#[cfg(feature = "bls12_381")]
+pub mod prime_field;
+pub mod qr;
+pub mod shamir;
+pub mod sskr;
+pub mod ssss;
+#[cfg(feature = "stego")]
+pub mod stego;
+#[cfg(feature = "object_storage")]
+pub mod storage;
+pub mod wrapper;
+pub mod zones;
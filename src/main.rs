@@ -1,13 +1,31 @@
-mod shamir;
-mod wrapper;
-
-use std::{path::PathBuf, io::{Write, stdout}};
+use std::{
+    io::{stdout, Write},
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Ok, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
+use zeroize::Zeroizing;
 
-use crate::wrapper::from_shares;
+use distributed_encrypt::ceremony::SplitManifest;
+use distributed_encrypt::error::ShamirError;
+#[cfg(feature = "http")]
+use distributed_encrypt::fetch;
+#[cfg(feature = "hardware")]
+use distributed_encrypt::hardware;
+use distributed_encrypt::interop::{sharks, vault};
+#[cfg(feature = "keyring")]
+use distributed_encrypt::keychain;
+#[cfg(feature = "qr")]
+use distributed_encrypt::qr;
+use distributed_encrypt::wrapper::{Scheme, Threshold};
+use distributed_encrypt::{
+    age, encoding, mnemonic, passphrase, pem, pinentry, sskr, ssss, wrapper,
+};
 
 /// A program that helps you encrypt and decrypt files using Shamir's Secret Sharing
 #[derive(Parser, Debug)]
@@ -23,21 +41,249 @@ enum SubCommand {
     Encrypt(EncryptCommand),
     /// Decrypt a file
     Decrypt(DecryptCommand),
+    /// Check that shares belong to the same split, without decrypting them
+    Verify(VerifyCommand),
+    /// Print which versions of the share format this program can read and write
+    FormatVersion,
+    /// Overwrite files with random data and delete them, for decrypted files that should not stay
+    /// behind
+    Shred(ShredCommand),
+    /// Recover shares of another format and split the secret again into native shares
+    MigrateFrom(MigrateFromCommand),
+    /// Inspect the settings profiles of the config file
+    #[command(subcommand)]
+    Config(ConfigCommand),
+    /// Manage shares stored in the platform credential store
+    #[cfg(feature = "keyring")]
+    #[command(subcommand)]
+    Keyring(KeyringCommand),
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Parser, Debug)]
+enum KeyringCommand {
+    /// List the shares stored in the platform credential store
+    List,
+}
+
+#[derive(Parser, Debug)]
+enum ConfigCommand {
+    /// Print the settings `encrypt` uses, merged from the defaults and a profile
+    Show {
+        /// The profile to merge over the defaults
+        #[clap(long)]
+        profile: Option<String>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ShareFormat {
+    /// This program's own encrypted share format
+    #[value(alias = "wrapped")]
+    Native,
+    /// Native shares encrypted with a key derived from a passphrase, which is asked for when
+    /// encrypting and decrypting
+    Passphrase,
+    /// Shamir's Secret Sharing of the file itself, without encryption or integrity checks
+    Raw,
+    /// Blockchain Commons' SSKR, written as ByteWords and as a `ur:crypto-sskr` URI
+    Sskr,
+    /// The `index-hexdata` lines of the classic `ssss-split` and `ssss-combine` tools
+    Ssss,
+    /// HashiCorp Vault's unseal key shares, written as base64
+    Vault,
+    /// The binary shares of the `sharks` crate, an x coordinate followed by the y values
+    Sharks,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum SharingScheme {
+    /// Additive when every share is required, Shamir otherwise
+    Auto,
+    /// Shamir's Secret Sharing
+    Shamir,
+    /// XOR based sharing, only possible when the threshold equals the number of shares
+    Additive,
+    /// Shamir's Secret Sharing, detecting shares that were given another index
+    IndexedShamir,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum NativeEncoding {
+    /// The share bytes themselves
+    Binary,
+    /// The share bytes in a frame with magic bytes and a checksum, which tells a damaged share
+    Framed,
+    /// Lowercase hexadecimal
+    Hex,
+    /// Standard base64, with padding
+    Base64,
+    /// Words of the BIP39 English wordlist
+    Words,
+    /// A single `ur:bytes` URI
+    Ur,
+}
+
+impl NativeEncoding {
+    /// The text form shares are written in, `None` for binary shares
+    fn text_format(self) -> Option<encoding::ShareFormat> {
+        match self {
+            NativeEncoding::Binary | NativeEncoding::Framed => None,
+            NativeEncoding::Hex => Some(encoding::ShareFormat::Hex),
+            NativeEncoding::Base64 => Some(encoding::ShareFormat::Base64),
+            NativeEncoding::Words => Some(encoding::ShareFormat::Mnemonic),
+            NativeEncoding::Ur => Some(encoding::ShareFormat::Qr),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PinentryMode {
+    /// Use pinentry when gpg-agent.conf names one or one is on the PATH, the terminal otherwise
+    Auto,
+    /// Always use pinentry, failing when there is none
+    Always,
+    /// Always ask on the terminal
+    Never,
 }
 
 #[derive(Parser, Debug)]
 struct EncryptCommand {
-    /// The number of shares to create
-    #[clap(short, long, default_value = "5")]
-    shares: u8,
+    /// The profile of the config file to take the settings from, the flags given here take
+    /// precedence over it
+    #[clap(long)]
+    profile: Option<String>,
 
-    /// The threshold of shares needed to decrypt
-    #[clap(short, long, default_value = "3")]
-    threshold: u8,
+    /// The number of shares to create [default: 5]
+    #[clap(short, long)]
+    shares: Option<u8>,
 
-    /// The output folder
+    /// The threshold of shares needed to decrypt, as a number or a percentage like "60%"
+    /// [default: 3]
     #[clap(short, long)]
-    output: PathBuf,
+    threshold: Option<Threshold>,
+
+    /// The format to write the shares in
+    #[clap(short, long, value_enum, default_value = "native")]
+    format: ShareFormat,
+
+    /// How the native format splits the key between the shares [default: auto]
+    #[clap(long, value_enum)]
+    scheme: Option<SharingScheme>,
+
+    /// How native shares are written to their files [default: binary]
+    #[clap(long, value_enum)]
+    encoding: Option<NativeEncoding>,
+
+    /// An SSKR group as "<threshold>-of-<count>", can be repeated (defaults to a single group
+    /// using --threshold and --shares)
+    #[clap(long = "group")]
+    groups: Vec<String>,
+
+    /// The number of SSKR groups needed to decrypt
+    #[clap(long, default_value = "1")]
+    group_threshold: u8,
+
+    /// A token to prefix ssss shares with, like `ssss-split -w`
+    #[clap(long)]
+    token: Option<String>,
+
+    /// Disable the ssss diffusion layer, like `ssss-split -D`
+    #[clap(long)]
+    no_diffusion: bool,
+
+    /// The file is a BIP39 mnemonic, only its entropy is shared
+    #[clap(long)]
+    bip39: bool,
+
+    /// The file is a PEM encoded key, only its DER contents and layout are shared
+    #[clap(long, conflicts_with = "bip39")]
+    pem: bool,
+
+    /// The file is an age identity file, only the key of one identity is shared
+    #[clap(long, conflicts_with_all = ["bip39", "pem"])]
+    age: bool,
+
+    /// Which identity of an age identity file to share, by position (starting at 1) or recipient
+    #[clap(long, requires = "age")]
+    select: Option<String>,
+
+    /// The last day the native shares can be decrypted on, as YYYY-MM-DD in UTC
+    ///
+    /// This program refuses to decrypt them after it, but the expiry is a policy for honest
+    /// holders, not a protection: anyone holding enough shares can decrypt them with a program
+    /// that ignores it.
+    #[clap(long)]
+    expires: Option<String>,
+
+    /// Also write every native share as a folder of multi-part UR QR code frames, for moving it
+    /// across an air gap
+    #[cfg(feature = "qr")]
+    #[clap(long)]
+    qr_ur: bool,
+
+    /// Write a JSON manifest of the native shares to this file, recording everything about the
+    /// split that is not secret, for `verify` and `decrypt` to check the shares against later
+    ///
+    /// It also holds a fingerprint of the file, which lets anyone holding the manifest test guesses
+    /// of the file, so don't write one for a file that can be guessed, like a short password.
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+
+    /// A note on what was split, to record in the manifest and the instruction sheets
+    #[clap(long)]
+    label: Option<String>,
+
+    /// Write an instruction sheet for the holder of every native share written to this folder,
+    /// saying what the share is and what to do with it
+    #[clap(long)]
+    instructions: Option<PathBuf>,
+
+    /// Also write every instruction sheet as markdown
+    #[clap(long, requires = "instructions")]
+    instructions_markdown: bool,
+
+    /// A template to write the instruction sheets with instead of the built-in one, can be given
+    /// twice, a file ending in ".md" replaces the markdown template and any other the plain text
+    /// one
+    ///
+    /// The placeholders {label}, {index}, {count}, {threshold}, {fingerprint}, {share_file},
+    /// {expires}, {contact} and {command} are filled in.
+    #[clap(long = "instructions-template", requires = "instructions")]
+    instructions_templates: Vec<PathBuf>,
+
+    /// Who share holders should contact about their share, for the instruction sheets
+    #[clap(long, requires = "instructions")]
+    contact: Option<String>,
+
+    /// Recover the file from a random threshold of the shares before writing any of them, which is
+    /// the default for all formats but SSKR
+    #[clap(long, overrides_with = "no_verify")]
+    verify: bool,
+
+    /// Write the shares without recovering the file from them first
+    #[clap(long, overrides_with = "verify")]
+    no_verify: bool,
+
+    /// Store the last native share in this slot of the platform credential store, instead of
+    /// writing it to the output folder
+    #[cfg(feature = "keyring")]
+    #[clap(long)]
+    keyring_slot: Option<String>,
+
+    /// Store the last native share on the inserted hardware token, instead of writing it to the
+    /// output folder
+    #[cfg(feature = "hardware")]
+    #[clap(long)]
+    hardware: bool,
+
+    /// How to ask for the passphrase of passphrase shares
+    #[clap(long, value_enum, default_value = "auto")]
+    pinentry: PinentryMode,
+
+    /// The output folder, can be left out when the profile has an `out_dir`
+    #[clap(short, long)]
+    output: Option<PathBuf>,
 
     /// The file to encrypt
     file: PathBuf,
@@ -49,7 +295,161 @@ struct DecryptCommand {
     #[clap(short, long)]
     output: Option<PathBuf>,
 
-    /// The files to decrypt
+    /// Only write the decrypted file to stdout, refusing to write it to any file
+    #[clap(long, conflicts_with = "output")]
+    stdout_only: bool,
+
+    /// Put the decrypted file on the clipboard instead of writing it, for a password or anything
+    /// else that is short text, only printing that it was copied
+    #[cfg(feature = "clipboard")]
+    #[clap(long, conflicts_with_all = ["output", "stdout_only"])]
+    copy: bool,
+
+    /// Clear the clipboard again after this long, like "30s" or "2m", unless something else was
+    /// copied in the meantime
+    #[cfg(feature = "clipboard")]
+    #[clap(long, requires = "copy", value_parser = parse_duration)]
+    clear_after: Option<std::time::Duration>,
+
+    /// Only write the output file to a memory backed file system like tmpfs, so the decrypted file
+    /// never reaches a disk
+    #[clap(long, requires = "output")]
+    memory_backed: bool,
+
+    /// The format the shares are in (defaults to native, or raw when none of the shares is native)
+    #[clap(short, long, value_enum)]
+    format: Option<ShareFormat>,
+
+    /// The threshold the ssss shares were created with (defaults to the number of shares given)
+    #[clap(short, long)]
+    threshold: Option<u8>,
+
+    /// The ssss shares were created without the diffusion layer, like `ssss-combine -D`
+    #[clap(long)]
+    no_diffusion: bool,
+
+    /// The shares hold BIP39 entropy, write it back out as the original mnemonic
+    #[clap(long)]
+    bip39: bool,
+
+    /// The shares hold a PEM key, write it back out as the original PEM file
+    #[clap(long, conflicts_with = "bip39")]
+    pem: bool,
+
+    /// The shares hold an age identity, write it back out as an identity file
+    #[clap(long, conflicts_with_all = ["bip39", "pem"])]
+    age: bool,
+
+    /// The fingerprint printed when encrypting, in its short or full form, fail if the native
+    /// shares are of another split
+    #[clap(long)]
+    expect_fingerprint: Option<String>,
+
+    /// Fail on any damaged or mismatched native share, instead of leaving it out and recovering
+    /// from the others
+    #[clap(long)]
+    strict: bool,
+
+    /// Print how every native share file was encoded, as binary, hex, base64, words or a UR
+    #[clap(short, long)]
+    verbose: bool,
+
+    /// Decrypt native shares past their expiry
+    #[clap(long)]
+    ignore_expiry: bool,
+
+    /// The manifest written when encrypting, fail unless every native share is one it records,
+    /// and the decrypted file is the one it was made for
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+
+    /// When recovering native shares fails, write a JSON report of every share and the stage it
+    /// failed at to this file, which holds nothing of the secret or the key
+    #[clap(long)]
+    diagnostics: Option<PathBuf>,
+
+    /// Also use the native share stored in this slot of the platform credential store
+    #[cfg(feature = "keyring")]
+    #[clap(long)]
+    from_keyring: Option<String>,
+
+    /// Also use the native share stored on the inserted hardware token, asking for its PIN
+    #[cfg(feature = "hardware")]
+    #[clap(long)]
+    hardware: bool,
+
+    /// How to ask for the passphrase of passphrase shares, and the PIN of the hardware token
+    #[clap(long, value_enum, default_value = "auto")]
+    pinentry: PinentryMode,
+
+    /// Do not check the certificates of the servers shares are fetched from, which lets anyone on
+    /// the way read and change the shares
+    #[cfg(feature = "http")]
+    #[clap(long)]
+    insecure_tls: bool,
+
+    /// The files to decrypt, a native share may also be a folder of QR code frames, and with the
+    /// http feature any file an https:// or file:// URL, sending the bearer token in
+    /// SHAMIR_FETCH_TOKEN if it is set
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct MigrateFromCommand {
+    /// The format the shares are in now
+    #[clap(value_enum)]
+    format: ShareFormat,
+
+    /// The number of native shares to create
+    #[clap(short, long, default_value = "5")]
+    shares: u8,
+
+    /// The threshold of native shares needed to decrypt, as a number or a percentage like "60%"
+    #[clap(short, long, default_value = "3")]
+    threshold: Threshold,
+
+    /// The threshold the ssss shares were created with (defaults to the number of shares given)
+    #[clap(long)]
+    source_threshold: Option<u8>,
+
+    /// The ssss shares were created without the diffusion layer, like `ssss-combine -D`
+    #[clap(long)]
+    no_diffusion: bool,
+
+    /// How to ask for the passphrase of passphrase shares
+    #[clap(long, value_enum, default_value = "auto")]
+    pinentry: PinentryMode,
+
+    /// The output folder
+    #[clap(short, long)]
+    output: PathBuf,
+
+    /// The share files to migrate
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct ShredCommand {
+    /// The files to shred
+    files: Vec<PathBuf>,
+}
+
+#[derive(Parser, Debug)]
+struct VerifyCommand {
+    /// Also look for a subset of the shares that recovers the secret, by recovering only the key
+    /// and checking it against the encrypted data, which is never decrypted
+    #[clap(long)]
+    deep: bool,
+
+    /// The number of subsets --deep tries at most, smaller subsets first
+    #[clap(long, default_value = "10000", requires = "deep")]
+    max_subsets: usize,
+
+    /// The manifest written when encrypting, also check that every share is one it records
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+
+    /// The share files to check
     files: Vec<PathBuf>,
 }
 
@@ -59,24 +459,48 @@ fn main() -> Result<()> {
     match args.command {
         SubCommand::Encrypt(arguments) => handle_encrypt(arguments)?,
         SubCommand::Decrypt(arguments) => handle_decrypt(arguments)?,
+        SubCommand::Verify(arguments) => handle_verify(arguments)?,
+        SubCommand::FormatVersion => handle_format_version(),
+        SubCommand::Shred(arguments) => handle_shred(arguments)?,
+        SubCommand::MigrateFrom(arguments) => handle_migrate_from(arguments)?,
+        SubCommand::Config(ConfigCommand::Show { profile }) => handle_config_show(profile)?,
+        #[cfg(feature = "keyring")]
+        SubCommand::Keyring(KeyringCommand::List) => handle_keyring_list()?,
     };
 
     Ok(())
 }
 
 fn handle_encrypt(arguments: EncryptCommand) -> Result<()> {
+    // Flags take precedence over the profile, which takes precedence over the defaults
+    let defaults = match arguments.profile.as_deref() {
+        Some(name) => EncryptSettings::from_profile(name, &load_profile(name)?)?,
+        None => EncryptSettings::default(),
+    };
+    let count = arguments.shares.unwrap_or(defaults.shares);
+    let scheme = arguments.scheme.unwrap_or(defaults.scheme);
+    let share_encoding = arguments.encoding.unwrap_or(defaults.encoding);
+    let output = match arguments.output.clone().or(defaults.output) {
+        Some(output) => output,
+        None => {
+            return Err(anyhow::anyhow!(
+                "No output folder, give one with --output or as the out_dir of a profile"
+            ))
+        }
+    };
+
     // Get reference to output folder, and check if it exists and is a folder, don't create it
-    if !arguments.output.exists() {
+    if !output.exists() {
         return Err(anyhow::anyhow!(
             "Output folder \"{}\" does not exist",
-            arguments.output.display()
+            output.display()
         ));
     }
 
-    if !arguments.output.is_dir() {
+    if !output.is_dir() {
         return Err(anyhow::anyhow!(
             "Output folder \"{}\" is not a folder",
-            arguments.output.display()
+            output.display()
         ));
     }
 
@@ -96,21 +520,490 @@ fn handle_encrypt(arguments: EncryptCommand) -> Result<()> {
     }
 
     // Read file into vec
-    let file_data = fs::read(arguments.file)?;
-    let shares = wrapper::to_shares(file_data, arguments.threshold, arguments.shares)?;
+    let mut file_data = fs::read(&arguments.file)?;
+
+    // Only share the entropy of a mnemonic, which is much smaller than its words
+    if arguments.bip39 {
+        file_data = mnemonic::to_entropy(&String::from_utf8(file_data)?)?;
+        eprintln!(
+            "Note: a BIP39 passphrase is not part of the mnemonic, and is not included in the shares"
+        );
+    }
+
+    if arguments.pem {
+        file_data = pem::to_payload(&file_data)?;
+    }
+
+    #[cfg(feature = "qr")]
+    if arguments.qr_ur && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("QR code frames are only supported for native shares"));
+    }
+
+    if arguments.encoding.is_some() && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Share encodings are only supported for native shares"));
+    }
+
+    if arguments.expires.is_some() && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can expire"));
+    }
+
+    if arguments.manifest.is_some() && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Manifests are only supported for native shares"));
+    }
+
+    if arguments.instructions.is_some() && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Instruction sheets are only supported for native shares"));
+    }
+
+    if arguments.label.is_some() && arguments.manifest.is_none() && arguments.instructions.is_none()
+    {
+        return Err(anyhow::anyhow!("A label is only written with --manifest or --instructions"));
+    }
+    let expires_at = match arguments.expires.as_deref() {
+        Some(date) => Some(parse_expiry_date(date)?),
+        None => None,
+    };
+    if expires_at.is_some_and(|expires_at| expires_at < unix_now()) {
+        return Err(anyhow::anyhow!("Expiry date has already passed"));
+    }
+
+    #[cfg(feature = "keyring")]
+    if arguments.keyring_slot.is_some() && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can be stored in the keyring"));
+    }
+
+    #[cfg(feature = "hardware")]
+    if arguments.hardware && !matches!(arguments.format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can be stored on a hardware token"));
+    }
+
+    if arguments.age {
+        let identity_file = String::from_utf8(file_data)?;
+        file_data = age::to_payload(&identity_file, arguments.select.as_deref())?;
+    }
+
+    // --verify is the default, the flags only override each other
+    let verify = arguments.verify || !arguments.no_verify;
+    let threshold = arguments
+        .threshold
+        .unwrap_or(defaults.threshold)
+        .resolve(count)?;
+
+    // Templates are read before any share is written, so a missing one doesn't leave shares behind
+    let instructions = match arguments.instructions.as_ref() {
+        Some(folder) if !folder.is_dir() => {
+            return Err(anyhow::anyhow!(
+                "Instructions folder \"{}\" is not a folder",
+                folder.display()
+            ))
+        }
+        Some(_) => Some(InstructionSheets::new(&arguments, count, threshold)?),
+        None => None,
+    };
+
+    match arguments.format {
+        ShareFormat::Native => {
+            let scheme = match scheme {
+                SharingScheme::Auto => Scheme::for_threshold(threshold, count),
+                SharingScheme::Shamir => Scheme::Shamir,
+                SharingScheme::Additive => Scheme::Additive,
+                SharingScheme::IndexedShamir => Scheme::IndexedShamir,
+            };
+            let shares = match expires_at {
+                Some(expires_at) => wrapper::to_shares_with_expiry(
+                    file_data.clone(),
+                    threshold,
+                    count,
+                    scheme,
+                    expires_at,
+                )?,
+                None => {
+                    wrapper::to_shares_with_scheme(file_data.clone(), threshold, count, scheme)?
+                }
+            };
+            if verify {
+                self_test(&shares, threshold, &file_data, |s| {
+                    wrapper::from_shares_guarded(s, file_data.len())
+                })?;
+            }
+
+            // The manifest records all shares, also the ones stored outside of the output folder
+            let mut manifest = match arguments.manifest {
+                Some(_) => Some(SplitManifest::new(&shares, threshold, &file_data)?),
+                None => None,
+            };
+
+            #[cfg(feature = "keyring")]
+            let shares = match arguments.keyring_slot.as_deref() {
+                Some(slot) => {
+                    let (stored, rest) = shares.split_last().unwrap();
+                    keychain::store_share(slot, stored)?;
+                    println!("Stored share {} in keyring slot \"{}\"", rest.len(), slot);
+                    rest.to_vec()
+                }
+                None => shares,
+            };
+
+            #[cfg(feature = "hardware")]
+            let shares = match arguments.hardware {
+                true => {
+                    let (stored, rest) = shares.split_last().unwrap();
+                    hardware::store_share(stored)?;
+                    println!("Stored share {} on the hardware token", rest.len());
+                    rest.to_vec()
+                }
+                false => shares,
+            };
+
+            write_native_shares(&output, &shares, share_encoding)?;
+            #[cfg(feature = "qr")]
+            if arguments.qr_ur {
+                write_qr_frames(&output, &shares)?;
+            }
+
+            if let (Some(path), Some(manifest)) = (arguments.manifest.as_ref(), manifest.as_mut()) {
+                manifest.label = arguments.label.clone();
+                manifest.encoding = value_name(share_encoding);
+                for (i, entry) in manifest.shares.iter_mut().take(shares.len()).enumerate() {
+                    entry.file = Some(native_share_file(i, share_encoding));
+                }
+                fs::write(path, manifest.to_json() + "\n")?;
+                println!("Wrote the manifest to \"{}\"", path.display());
+            }
+
+            if let (Some(folder), Some(sheets)) = (arguments.instructions.as_ref(), &instructions) {
+                sheets.write(folder, &shares, share_encoding)?;
+                println!("Wrote the instruction sheets to \"{}\"", folder.display());
+            }
+
+            // Shares stored elsewhere are of the same split, any share has its fingerprint
+            if let Some(fingerprint) = shares.first().and_then(|s| wrapper::split_fingerprint(s)) {
+                println!(
+                    "Fingerprint of the split: {} (in full: {})",
+                    &fingerprint[..wrapper::SHORT_FINGERPRINT_LEN],
+                    fingerprint
+                );
+            }
+            if let Some(expires_at) = expires_at {
+                println!("Shares expire at {}", format_utc(expires_at));
+            }
+        }
+        ShareFormat::Passphrase => {
+            let passphrase = ask_new_passphrase(arguments.pinentry)?;
+            let shares =
+                passphrase::to_shares_with_passphrase(&file_data, threshold, count, &passphrase)?;
+            if verify {
+                self_test(&shares, threshold, &file_data, |shares| {
+                    passphrase::from_shares_with_passphrase(shares, &passphrase)
+                })?;
+            }
+
+            for (i, share) in shares.iter().enumerate() {
+                let share_path = output.join(format!("share{}.pass", i));
+                fs::write(share_path, share)?;
+            }
+        }
+        ShareFormat::Raw => {
+            eprintln!(
+                "Note: raw shares are not encrypted, damaged or mismatched shares recover a wrong \
+                 file without any error"
+            );
+            let shares = wrapper::to_raw_shares(&file_data, threshold, count)?;
+            if verify {
+                self_test(&shares, threshold, &file_data, wrapper::from_raw_shares)?;
+            }
+
+            for (i, share) in shares.iter().enumerate() {
+                let share_path = output.join(format!("share{}.raw", i));
+                fs::write(share_path, share)?;
+            }
+        }
+        ShareFormat::Sskr => write_sskr_shares(&arguments, &output, threshold, count, file_data)?,
+        ShareFormat::Ssss => {
+            let shares = ssss::to_shares(
+                &file_data,
+                threshold,
+                count,
+                arguments.token.as_deref(),
+                !arguments.no_diffusion,
+            )?;
+            if verify {
+                self_test(&shares, threshold, &file_data, |shares| {
+                    ssss::from_shares(&shares, threshold, !arguments.no_diffusion)
+                })?;
+            }
+
+            for (i, share) in shares.iter().enumerate() {
+                let share_path = output.join(format!("share{}.ssss", i));
+                fs::write(share_path, format!("{}\n", share))?;
+            }
+        }
+        ShareFormat::Vault => {
+            let shares = vault::to_shares(&file_data, threshold, count)?;
+            if verify {
+                self_test(&shares, threshold, &file_data, vault::from_shares)?;
+            }
+
+            for (i, share) in shares.iter().enumerate() {
+                let share_path = output.join(format!("share{}.vault", i));
+                fs::write(share_path, format!("{}\n", vault::to_base64(share)))?;
+            }
+        }
+        ShareFormat::Sharks => {
+            let shares = sharks::to_shares(&file_data, threshold, count)?;
+            if verify {
+                self_test(&shares, threshold, &file_data, sharks::from_shares)?;
+            }
+
+            for (i, share) in shares.iter().enumerate() {
+                let share_path = output.join(format!("share{}.sharks", i));
+                fs::write(share_path, share)?;
+            }
+        }
+    }
+
+    println!("Done");
 
+    Ok(())
+}
+
+fn write_native_shares(
+    output: &Path,
+    shares: &[Vec<u8>],
+    share_encoding: NativeEncoding,
+) -> Result<()> {
     // Write shares to output folder
     for (i, share) in shares.iter().enumerate() {
-        let share_path = arguments.output.join(format!("share{}.ss", i));
-        fs::write(share_path, share)?;
+        let share_path = output.join(native_share_file(i, share_encoding));
+        match share_encoding.text_format() {
+            Some(format) => {
+                fs::write(share_path, format!("{}\n", encoding::encode_share(share, format)))?;
+            }
+            None if matches!(share_encoding, NativeEncoding::Framed) => {
+                fs::write(share_path, wrapper::frame_share(share))?
+            }
+            None => fs::write(share_path, share)?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes every native share as a folder of multi-part UR QR code frames
+#[cfg(feature = "qr")]
+fn write_qr_frames(output: &Path, shares: &[Vec<u8>]) -> Result<()> {
+    for (i, share) in shares.iter().enumerate() {
+        let frames_path = output.join(format!("share{}.qr", i));
+        fs::create_dir(&frames_path)?;
+        let parts = qr::to_parts(share, qr::DEFAULT_FRAGMENT_LEN)?;
+        for (n, part) in parts.iter().enumerate() {
+            let frame_path = frames_path.join(format!("frame{:04}.png", n));
+            fs::write(frame_path, qr::to_png(part)?)?;
+        }
     }
 
-    println!("Done");
+    Ok(())
+}
+
+/// The name of the file the native share at the given position is written to
+fn native_share_file(position: usize, share_encoding: NativeEncoding) -> String {
+    match share_encoding.text_format() {
+        Some(_) => format!("share{}.txt", position),
+        None => format!("share{}.ss", position),
+    }
+}
+
+/// Reads the manifest `encrypt --manifest` wrote
+fn load_manifest(path: &Path) -> Result<SplitManifest> {
+    let json = fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Cannot read manifest \"{}\": {}", path.display(), e))?;
+    SplitManifest::from_json(&json)
+}
+
+/// The instruction sheet for the holder of a share, as plain text
+const INSTRUCTIONS_TEXT: &str = "\
+SHARE {index} OF {count}: {label}
+
+You are holding one share of a secret that was split between several people, so that no one of
+them holds it alone. Your share on its own reveals nothing about the secret, {threshold} of the
+{count} shares are needed to recover it.
+
+Keep the share file \"{share_file}\" safe and unchanged, and don't hand it to anyone unless
+{contact} asks you to. Backups are fine, as long as they are kept just as safe.
+
+When you are asked for your share, first check that you are told this fingerprint:
+
+    {fingerprint}
+
+Then hand the share file to whoever recovers the secret, who will run:
+
+    {command}
+
+The shares expire: {expires}
+";
+
+/// The instruction sheet for the holder of a share, as markdown
+const INSTRUCTIONS_MARKDOWN: &str = "\
+# Share {index} of {count}: {label}
+
+You are holding one share of a secret that was split between several people, so that no one of
+them holds it alone. Your share on its own reveals nothing about the secret, **{threshold} of the
+{count} shares** are needed to recover it.
+
+Keep the share file `{share_file}` safe and unchanged, and don't hand it to anyone unless
+{contact} asks you to. Backups are fine, as long as they are kept just as safe.
+
+When you are asked for your share, first check that you are told this fingerprint:
+
+    {fingerprint}
+
+Then hand the share file to whoever recovers the secret, who will run:
+
+    {command}
+
+The shares expire: {expires}
+";
+
+/// The instruction sheets `encrypt --instructions` writes, one for the holder of every share
+struct InstructionSheets {
+    text: String,
+    markdown: Option<String>,
+    label: String,
+    contact: String,
+    count: u8,
+    threshold: u8,
+}
+
+impl InstructionSheets {
+    fn new(arguments: &EncryptCommand, count: u8, threshold: u8) -> Result<InstructionSheets> {
+        let mut text = INSTRUCTIONS_TEXT.to_string();
+        let mut markdown = arguments
+            .instructions_markdown
+            .then(|| INSTRUCTIONS_MARKDOWN.to_string());
+        for path in arguments.instructions_templates.iter() {
+            let template = fs::read_to_string(path).map_err(|e| {
+                anyhow::anyhow!("Cannot read template \"{}\": {}", path.display(), e)
+            })?;
+            match path.extension().is_some_and(|extension| extension == "md") {
+                true => markdown = Some(template),
+                false => text = template,
+            }
+        }
+
+        Ok(InstructionSheets {
+            text,
+            markdown,
+            label: arguments
+                .label
+                .clone()
+                .unwrap_or("a shared secret".to_string()),
+            contact: arguments
+                .contact
+                .clone()
+                .unwrap_or("whoever gave you the share".to_string()),
+            count,
+            threshold,
+        })
+    }
+
+    /// Writes `instructions{i}.txt`, and `instructions{i}.md` when asked for, for the share in
+    /// `share{i}` of the output folder
+    fn write(
+        &self,
+        folder: &Path,
+        shares: &[Vec<u8>],
+        share_encoding: NativeEncoding,
+    ) -> Result<()> {
+        for (i, share) in shares.iter().enumerate() {
+            let fingerprint = wrapper::split_fingerprint(share).unwrap_or_default();
+            let command = format!(
+                "{} decrypt --expect-fingerprint {} --output ./recovered <share files>",
+                clap::crate_name!(),
+                &fingerprint[..wrapper::SHORT_FINGERPRINT_LEN.min(fingerprint.len())]
+            );
+            let expires = match wrapper::share_expiry(share) {
+                Some(expires_at) => format_utc(expires_at),
+                None => "never".to_string(),
+            };
+            let fill = |template: &str| {
+                template
+                    .replace("{label}", &self.label)
+                    .replace("{index}", &(i + 1).to_string())
+                    .replace("{count}", &self.count.to_string())
+                    .replace("{threshold}", &self.threshold.to_string())
+                    .replace("{fingerprint}", &fingerprint)
+                    .replace("{share_file}", &native_share_file(i, share_encoding))
+                    .replace("{expires}", &expires)
+                    .replace("{contact}", &self.contact)
+                    .replace("{command}", &command)
+            };
+
+            let sheet_path = folder.join(format!("instructions{}.txt", i));
+            fs::write(sheet_path, fill(&self.text))?;
+            if let Some(markdown) = self.markdown.as_deref() {
+                let sheet_path = folder.join(format!("instructions{}.md", i));
+                fs::write(sheet_path, fill(markdown))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_sskr_shares(
+    arguments: &EncryptCommand,
+    output: &Path,
+    threshold: u8,
+    count: u8,
+    file_data: Vec<u8>,
+) -> Result<()> {
+    // Without explicit groups, use a single group built from the regular threshold and count
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    for group in arguments.groups.iter() {
+        let parsed = group
+            .split_once("-of-")
+            .and_then(|(t, c)| Some((t.parse::<usize>().ok()?, c.parse::<usize>().ok()?)));
+        match parsed {
+            Some(group) => groups.push(group),
+            None => {
+                return Err(anyhow::anyhow!(
+                    "Invalid group \"{}\", expected \"<threshold>-of-<count>\"",
+                    group
+                ))
+            }
+        }
+    }
+    if groups.is_empty() {
+        groups.push((threshold as usize, count as usize));
+    }
+
+    let shares = sskr::to_shares(&file_data, arguments.group_threshold as usize, &groups)?;
+
+    // Write every share as both ByteWords and UR, either can be used to decrypt
+    for (g, group) in shares.iter().enumerate() {
+        for (m, share) in group.iter().enumerate() {
+            let share_path = output.join(format!("share{}-{}.sskr", g, m));
+            let contents = format!("{}\n{}\n", sskr::to_bytewords(share), sskr::to_ur(share));
+            fs::write(share_path, contents)?;
+        }
+    }
 
     Ok(())
 }
 
 fn handle_decrypt(arguments: DecryptCommand) -> Result<()> {
+    // A shell redirect would still put the decrypted file on disk
+    #[cfg(unix)]
+    if arguments.stdout_only && fs::metadata("/dev/stdout").is_ok_and(|m| m.is_file()) {
+        return Err(anyhow::anyhow!("stdout is redirected to a file, not writing to it"));
+    }
+
+    decrypt_to(arguments, &mut stdout())
+}
+
+/// Decrypts the shares, writing the decrypted file to `out` unless it goes to an output file or
+/// the clipboard instead
+fn decrypt_to(arguments: DecryptCommand, out: &mut impl Write) -> Result<()> {
     if let Some(output) = arguments.output.to_owned() {
         // Check if output file is creatable, (as in, it is in a folder that exists)
         if !output.parent().unwrap().exists() {
@@ -119,40 +1012,1622 @@ fn handle_decrypt(arguments: DecryptCommand) -> Result<()> {
                 output.display()
             ));
         }
-    }
 
-    // Check if input files exist and are files
-    for file in arguments.files.iter() {
-        if !file.exists() {
+        if arguments.memory_backed && !is_memory_backed(output.parent().unwrap())? {
             return Err(anyhow::anyhow!(
-                "File \"{}\" does not exist",
-                file.display()
+                "Output file \"{}\" is not on a memory backed file system",
+                output.display()
             ));
         }
+    }
 
-        if !file.is_file() {
-            return Err(anyhow::anyhow!("File \"{}\" is not a file", file.display()));
-        }
+    // Shares given as a URL are fetched before anything else, so a failing one is found early
+    #[cfg(feature = "http")]
+    let files = ShareFiles::fetch(
+        &arguments.files,
+        &fetch::FetchOptions {
+            insecure_tls: arguments.insecure_tls,
+            ..Default::default()
+        },
+    )?;
+    #[cfg(not(feature = "http"))]
+    let files = ShareFiles::local(&arguments.files);
+
+    let format = match arguments.format {
+        Some(format) => format,
+        None => detect_format(&files)?,
+    };
+
+    if arguments.expect_fingerprint.is_some() && !matches!(format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares have a fingerprint"));
     }
 
-    // Read shares
-    let mut shares: Vec<Vec<u8>> = Vec::new();
-    for file in arguments.files.iter() {
-        shares.push(fs::read(file)?);
+    if arguments.strict && !matches!(format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can be checked before recovering"));
     }
 
-    // Decrypt shares
-    let decrypted = from_shares(shares)?;
+    if arguments.diagnostics.is_some() && !matches!(format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can be diagnosed"));
+    }
 
-    // Write decrypted data to output file
-    if let Some(output) = arguments.output.to_owned() {
-        fs::write(output, decrypted)?;
+    if arguments.manifest.is_some() && !matches!(format, ShareFormat::Native) {
+        return Err(anyhow::anyhow!("Only native shares can be checked against a manifest"));
+    }
+    let manifest = match arguments.manifest.as_deref() {
+        Some(path) => Some(load_manifest(path)?),
+        None => None,
+    };
 
-        println!("Done");
-    } else {
-        // Write to stdout
-        stdout().write_all(&decrypted)?;
+    let native = NativeOptions {
+        strict: arguments.strict,
+        verbose: arguments.verbose,
+        expect_fingerprint: arguments.expect_fingerprint.as_deref(),
+        ignore_expiry: arguments.ignore_expiry,
+        diagnostics: arguments.diagnostics.as_deref(),
+        manifest: manifest.as_ref(),
+    };
+
+    #[cfg(any(feature = "keyring", feature = "hardware"))]
+    if has_stored_shares(&arguments) {
+        if !matches!(format, ShareFormat::Native) {
+            return Err(anyhow::anyhow!("Only native shares can be stored outside of files"));
+        }
+        return write_decrypted(
+            &arguments,
+            out,
+            recover_with_stored_shares(&arguments, &files, &native)?,
+        );
     }
 
-    Ok(())
+    let decrypted = recover_from_files(
+        format,
+        &files,
+        arguments.threshold,
+        arguments.no_diffusion,
+        arguments.pinentry,
+        &native,
+    )?;
+
+    write_decrypted(&arguments, out, decrypted)
+}
+
+/// The format of the share files when none is given, native unless none of them is a native share
+///
+/// Raw shares have no framing of their own, so any file that is not a native share could be one.
+/// Mixing the two is an error, as the files that are not native shares may just be damaged ones.
+/// Passphrase shares are told apart by their layout, and can't be mixed with any other shares.
+fn detect_format(files: &ShareFiles) -> Result<ShareFormat> {
+    let mut native: Vec<String> = Vec::new();
+    let mut wrapped: Vec<String> = Vec::new();
+    let mut other: Vec<String> = Vec::new();
+    for file in files.paths.iter() {
+        // Folders are of native shares or QR code frames, missing files are reported later on
+        let name = format!("\"{}\"", file.display());
+        if !files.exists(file) {
+            continue;
+        }
+        // A framed share is native even when it is damaged, recovery tells what is wrong with it
+        let contents = match file.is_dir() {
+            true => Vec::new(),
+            false => files.read(file)?,
+        };
+        if file.is_dir()
+            || contents.starts_with(&wrapper::FRAME_MAGIC)
+            || !encoding::read_share_bundle(&contents).is_empty()
+        {
+            native.push(name);
+        } else if passphrase::is_passphrase_share(&contents) {
+            wrapped.push(name);
+        } else {
+            other.push(name);
+        }
+    }
+
+    if !wrapped.is_empty() {
+        if native.is_empty() && other.is_empty() {
+            return Ok(ShareFormat::Passphrase);
+        }
+        return Err(anyhow::anyhow!(
+            "Passphrase shares ({}) can't be combined with other shares",
+            wrapped.join(", ")
+        ));
+    }
+    if other.is_empty() {
+        return Ok(ShareFormat::Native);
+    }
+    if native.is_empty() {
+        return Ok(ShareFormat::Raw);
+    }
+
+    Err(anyhow::anyhow!(
+        "Native shares ({}) can't be combined with raw ones ({}), use --format native to leave \
+         out the others as damaged shares, or --format raw",
+        native.join(", "),
+        other.join(", ")
+    ))
+}
+
+fn write_decrypted(
+    arguments: &DecryptCommand,
+    out: &mut impl Write,
+    decrypted: Vec<u8>,
+) -> Result<()> {
+    // Wipe the decrypted data from memory when done with it, also when anything fails
+    let decrypted = Zeroizing::new(decrypted);
+    let decrypted = if arguments.bip39 {
+        let mnemonic = Zeroizing::new(mnemonic::from_entropy(&decrypted)?);
+        Zeroizing::new(format!("{}\n", *mnemonic).into_bytes())
+    } else if arguments.pem {
+        Zeroizing::new(pem::from_payload(&decrypted)?)
+    } else if arguments.age {
+        Zeroizing::new(age::from_payload(&decrypted)?.into_bytes())
+    } else {
+        decrypted
+    };
+
+    #[cfg(feature = "clipboard")]
+    if arguments.copy {
+        return copy_to_clipboard(&decrypted, arguments.clear_after);
+    }
+
+    // Write decrypted data to output file
+    if let Some(output) = arguments.output.to_owned() {
+        fs::write(output, decrypted)?;
+
+        println!("Done");
+    } else {
+        // Write to stdout
+        out.write_all(&decrypted)?;
+    }
+
+    Ok(())
+}
+
+/// The largest decrypted file `decrypt --copy` puts on the clipboard, 64 KB
+#[cfg(feature = "clipboard")]
+const MAX_CLIPBOARD_BYTES: usize = 64 * 1024;
+
+/// Puts the decrypted file on the clipboard, and clears it after `clear_after` if it still holds it
+///
+/// The secret is never printed instead, when the clipboard can't be used this fails.
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(decrypted: &[u8], clear_after: Option<std::time::Duration>) -> Result<()> {
+    #[cfg(target_os = "macos")]
+    use arboard::SetExtApple;
+    #[cfg(all(unix, not(target_os = "macos")))]
+    use arboard::SetExtLinux;
+    #[cfg(windows)]
+    use arboard::SetExtWindows;
+
+    let text = std::str::from_utf8(decrypted).map_err(|_| {
+        anyhow::anyhow!("The decrypted file is not text, write it to a file with --output instead")
+    })?;
+    if text.len() > MAX_CLIPBOARD_BYTES {
+        return Err(anyhow::anyhow!(
+            "The decrypted file is too large for the clipboard, write it to a file with --output \
+             instead"
+        ));
+    }
+
+    // A password file ends in a line break, which would submit the form it is pasted in
+    let text = match text.strip_suffix('\n') {
+        Some(text) => text.strip_suffix('\r').unwrap_or(text),
+        None => text,
+    };
+
+    let unavailable = |e: arboard::Error| {
+        anyhow::anyhow!(
+            "Cannot use the clipboard ({}), write the decrypted file with --output instead",
+            e
+        )
+    };
+    let mut clipboard = arboard::Clipboard::new().map_err(unavailable)?;
+
+    // Keep the secret out of clipboard managers and the clipboard synced to other devices
+    #[cfg(windows)]
+    let set = clipboard.set().exclude_from_cloud().exclude_from_history();
+    #[cfg(not(windows))]
+    let set = clipboard.set().exclude_from_history();
+
+    // On X11 and Wayland the clipboard is only filled as long as this program runs to hand it out
+    #[cfg(all(unix, not(target_os = "macos")))]
+    let set = {
+        println!(
+            "Copied the decrypted file to the clipboard, keeping it there until something else is \
+             copied"
+        );
+        match clear_after {
+            Some(after) => set.wait_until(std::time::Instant::now() + after),
+            None => set.wait(),
+        }
+    };
+
+    set.text(text).map_err(unavailable)?;
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    println!("Copied the decrypted file to the clipboard");
+
+    // X11 and Wayland already waited for it while handing out the text
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    if let Some(after) = clear_after {
+        std::thread::sleep(after);
+    }
+
+    if clear_after.is_some() && clipboard.get_text().is_ok_and(|copied| copied == text) {
+        clipboard.clear().map_err(unavailable)?;
+        println!("Cleared the clipboard");
+    }
+
+    Ok(())
+}
+
+/// Reads a duration like "30s", "2m" or "1h", a number alone is in seconds
+#[cfg(feature = "clipboard")]
+fn parse_duration(text: &str) -> Result<std::time::Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(split) => text.split_at(split),
+        None => (text, "s"),
+    };
+    let invalid = || anyhow::anyhow!("Invalid duration \"{}\", expected like \"30s\"", text);
+    let multiplier = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+
+    number
+        .checked_mul(multiplier)
+        .map(std::time::Duration::from_secs)
+        .ok_or_else(invalid)
+}
+
+fn recover_from_files(
+    format: ShareFormat,
+    files: &ShareFiles,
+    threshold: Option<u8>,
+    no_diffusion: bool,
+    pinentry: PinentryMode,
+    native: &NativeOptions,
+) -> Result<Vec<u8>> {
+    // Check if input files exist and are files
+    for file in files.paths.iter().filter(|f| files.fetched(f).is_none()) {
+        if !file.exists() {
+            return Err(anyhow::anyhow!(
+                "File \"{}\" does not exist",
+                file.display()
+            ));
+        }
+
+        // Native shares can also be given as a folder of QR code frames
+        let frames = file.is_dir() && matches!(format, ShareFormat::Native);
+        if !file.is_file() && !frames {
+            return Err(anyhow::anyhow!("File \"{}\" is not a file", file.display()));
+        }
+    }
+
+    // Read and decrypt shares
+    let decrypted = match format {
+        ShareFormat::Native => {
+            let shares = read_native_shares(files, native.strict, native.verbose)?;
+            recover_native(shares, native)?
+        }
+        ShareFormat::Passphrase => {
+            let mut shares: Vec<Vec<u8>> = Vec::new();
+            for file in files.paths.iter() {
+                shares.push(files.read(file)?);
+            }
+            let passphrase = ask_secret(
+                pinentry,
+                "Enter the passphrase the shares were encrypted with",
+                "Passphrase:",
+            )?;
+            passphrase::from_shares_with_passphrase(shares, &passphrase)?
+        }
+        ShareFormat::Raw => {
+            let mut shares: Vec<Vec<u8>> = Vec::new();
+            for file in files.paths.iter() {
+                let share = files.read(file)?;
+                if encoding::read_share_file(&share).is_some() {
+                    return Err(anyhow::anyhow!(
+                        "\"{}\" is a native share, raw and native shares can't be combined",
+                        file.display()
+                    ));
+                }
+                shares.push(share);
+            }
+            wrapper::from_raw_shares(shares)?
+        }
+        ShareFormat::Sskr => {
+            let mut shares: Vec<Vec<u8>> = Vec::new();
+            for file in files.paths.iter() {
+                shares.push(read_sskr_share(file, &files.read_to_string(file)?)?);
+            }
+            sskr::from_shares(shares)?
+        }
+        ShareFormat::Ssss => {
+            // ssss shares are lines of text, so a single file may hold several of them
+            let mut shares: Vec<String> = Vec::new();
+            for file in files.paths.iter() {
+                for line in files.read_to_string(file)?.lines() {
+                    if !line.trim().is_empty() {
+                        shares.push(line.to_string());
+                    }
+                }
+            }
+            let threshold = threshold.unwrap_or(u8::try_from(shares.len()).unwrap_or(u8::MAX));
+            ssss::from_shares(&shares, threshold, !no_diffusion)?
+        }
+        ShareFormat::Vault => {
+            let mut shares: Vec<Vec<u8>> = Vec::new();
+            for file in files.paths.iter() {
+                shares.push(vault::parse_share(&files.read_to_string(file)?)?);
+            }
+            vault::from_shares(shares)?
+        }
+        ShareFormat::Sharks => {
+            let mut shares: Vec<Vec<u8>> = Vec::new();
+            for file in files.paths.iter() {
+                shares.push(files.read(file)?);
+            }
+            sharks::from_shares(shares)?
+        }
+    };
+
+    Ok(decrypted)
+}
+
+/// Asks for a passphrase or PIN, with pinentry or on the terminal as the mode says
+///
+/// In auto mode a pinentry that fails, like one that can't open its window over SSH, falls back
+/// to the terminal too, but cancelling its dialog does not.
+fn ask_secret(mode: PinentryMode, description: &str, prompt: &str) -> Result<Zeroizing<String>> {
+    let on_terminal = || -> Result<Zeroizing<String>> {
+        Ok(Zeroizing::new(rpassword::prompt_password(format!(
+            "{} ",
+            prompt
+        ))?))
+    };
+
+    let program = match mode {
+        PinentryMode::Never => return on_terminal(),
+        PinentryMode::Auto | PinentryMode::Always => pinentry::find_program(),
+    };
+    let program = match (program, mode) {
+        (Some(program), _) => program,
+        (None, PinentryMode::Always) => {
+            return Err(anyhow::anyhow!(
+                "No pinentry program found, set pinentry-program in gpg-agent.conf or use \
+                 --pinentry never"
+            ))
+        }
+        (None, _) => return on_terminal(),
+    };
+
+    match pinentry::get_pin(&program, description, prompt) {
+        Err(e) if matches!(mode, PinentryMode::Auto) => {
+            eprintln!(
+                "Note: pinentry failed, asking on the terminal instead: {}",
+                e
+            );
+            on_terminal()
+        }
+        answer => answer?.ok_or_else(|| anyhow::anyhow!("Pinentry was cancelled")),
+    }
+}
+
+/// Asks for the passphrase to encrypt passphrase shares with, twice, so a typo doesn't lock the
+/// shares away
+fn ask_new_passphrase(mode: PinentryMode) -> Result<Zeroizing<String>> {
+    let passphrase = ask_secret(
+        mode,
+        "Enter a passphrase to encrypt the shares with",
+        "Passphrase:",
+    )?;
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("Passphrase is empty"));
+    }
+
+    let repeated = ask_secret(mode, "Enter the passphrase again", "Passphrase:")?;
+    if *passphrase != *repeated {
+        return Err(anyhow::anyhow!("Passphrases do not match"));
+    }
+
+    Ok(passphrase)
+}
+
+/// Recovers the secret from a random `threshold` of the shares, before any of them is written
+///
+/// This catches a broken split while the original is still at hand. The recovered copy is wiped
+/// from memory afterwards.
+fn self_test<T: Clone>(
+    shares: &[T],
+    threshold: u8,
+    original: &[u8],
+    recover: impl Fn(Vec<T>) -> Result<Vec<u8>>,
+) -> Result<()> {
+    let amount = (threshold as usize).min(shares.len());
+    let picked = rand::seq::index::sample(&mut rand::rngs::OsRng, shares.len(), amount)
+        .into_iter()
+        .map(|i| shares[i].clone())
+        .collect::<Vec<T>>();
+
+    let recovered = Zeroizing::new(
+        recover(picked).map_err(|e| e.context("Self-test failed, no shares were written"))?,
+    );
+    if recovered.as_slice() != original {
+        return Err(anyhow::anyhow!(
+            "Self-test failed, the shares recover something other than the file, no shares were \
+             written"
+        ));
+    }
+
+    Ok(())
+}
+
+/// Whether the folder is on a file system kept in memory, which Linux reports in /proc/mounts
+fn is_memory_backed(folder: &Path) -> Result<bool> {
+    let folder = fs::canonicalize(folder)?;
+    let mounts = fs::read_to_string("/proc/mounts").map_err(|_| {
+        anyhow::anyhow!("Cannot tell whether a folder is memory backed on this system")
+    })?;
+
+    // The folder is on the file system mounted closest to it
+    let mut closest: Option<(PathBuf, bool)> = None;
+    for line in mounts.lines() {
+        let fields = line.split(' ').collect::<Vec<&str>>();
+        if fields.len() < 3 {
+            continue;
+        }
+
+        let mount_point = PathBuf::from(fields[1].replace("\\040", " "));
+        let memory = matches!(fields[2], "tmpfs" | "ramfs");
+        if folder.starts_with(&mount_point)
+            && closest.as_ref().is_none_or(|(c, _)| mount_point.starts_with(c))
+        {
+            closest = Some((mount_point, memory));
+        }
+    }
+
+    Ok(closest.is_some_and(|(_, memory)| memory))
+}
+
+/// The options of `decrypt` that apply to native shares
+#[derive(Default)]
+struct NativeOptions<'a> {
+    strict: bool,
+    verbose: bool,
+    expect_fingerprint: Option<&'a str>,
+    ignore_expiry: bool,
+    /// The file to write a `DiagnosticsReport` to when recovering fails
+    diagnostics: Option<&'a Path>,
+    /// The manifest every share, and the recovered secret, must match
+    manifest: Option<&'a SplitManifest>,
+}
+
+/// Recovers native shares, naming the files skipped while reading them when that fails, and writing
+/// a diagnostics report if one is asked for
+fn recover_native(native: NativeShares, options: &NativeOptions) -> Result<Vec<u8>> {
+    // Shares that are not the ones of the manifest are the wrong artifacts, not damaged shares
+    if let Some(manifest) = options.manifest {
+        let mismatched = native
+            .shares
+            .iter()
+            .zip(native.sources.iter())
+            .filter_map(|(share, source)| {
+                let e = manifest.check_share(share).err()?;
+                Some(format!("{} ({})", source, e))
+            })
+            .collect::<Vec<String>>();
+        if !mismatched.is_empty() {
+            return Err(anyhow::anyhow!(
+                "{} share(s) don't match the manifest: {}",
+                mismatched.len(),
+                mismatched.join(", ")
+            ));
+        }
+    }
+
+    // The shares are only kept around for the report, they are not needed otherwise
+    let kept = options.diagnostics.map(|_| native.shares.clone());
+
+    let recovered = recover_checked(native.shares, &native.sources, options)
+        .map_err(|e| note_skipped(e, &native.skipped));
+
+    if let (Err(e), Some(path), Some(shares)) = (&recovered, options.diagnostics, kept) {
+        let report = diagnose(shares, &native.inputs, options, e);
+        let written = serde_json::to_string_pretty(&report)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| Ok(fs::write(path, json + "\n")?));
+        match written {
+            std::result::Result::Ok(()) => {
+                eprintln!("Wrote diagnostics to \"{}\"", path.display())
+            }
+            Err(w) => eprintln!("Failed to write diagnostics to \"{}\": {}", path.display(), w),
+        }
+    }
+
+    if let (std::result::Result::Ok(plaintext), Some(manifest)) = (&recovered, options.manifest) {
+        manifest.check_plaintext(plaintext)?;
+    }
+
+    recovered
+}
+
+/// The report `decrypt --diagnostics` writes when recovering native shares fails, as JSON
+///
+/// The layout is stable for runbooks to parse, fields are only ever added, along with a higher
+/// `schema_version`. It holds what the headers of the shares say and where recovering failed,
+/// never key material, share info, or anything recovered.
+#[derive(Serialize)]
+struct DiagnosticsReport {
+    /// Version of this layout, 2
+    ///
+    /// Version 2 added `expires_at` to the header fields of every input, version 1 has no other
+    /// differences.
+    schema_version: u32,
+    /// "grouping", "quorum", "decryption" or "integrity", see `wrapper::FailureStage`, null when
+    /// the shares recover the secret and something after that failed
+    stage: Option<wrapper::FailureStage>,
+    /// The error recovering failed with
+    error: String,
+    /// Every share given, ordered by index, without exact copies
+    inputs: Vec<InputDiagnostics>,
+}
+
+/// A share in a `DiagnosticsReport`
+#[derive(Serialize)]
+struct InputDiagnostics {
+    /// The path or URL the share was read from, or where it was stored otherwise
+    source: String,
+    /// How the share was written, like "binary" or "armored base64", null when it is not a share
+    encoding: Option<String>,
+    /// "ok", or why the share was left out, like "can't be read"
+    status: String,
+    /// The "index", "scheme", "info_length", "data_length", "checksum_ok", "fingerprint" and
+    /// "expires_at" the header of the share holds, see `wrapper::ShareHeader`
+    #[serde(flatten)]
+    header: wrapper::ShareHeader,
+}
+
+/// Works out the stage recovering failed at the same way `recover_checked` recovers
+fn diagnose(
+    shares: Vec<Vec<u8>>,
+    inputs: &[(String, Option<String>)],
+    options: &NativeOptions,
+    error: &anyhow::Error,
+) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport {
+        schema_version: 2,
+        stage: Some(wrapper::FailureStage::Grouping),
+        error: format!("{:#}", error),
+        inputs: Vec::new(),
+    };
+    for (share, (source, encoding)) in shares.iter().zip(inputs) {
+        report.inputs.push(InputDiagnostics {
+            source: source.to_owned(),
+            encoding: encoding.to_owned(),
+            status: "ok".to_string(),
+            header: wrapper::share_header(share),
+        });
+    }
+
+    let (healthy, excluded) = wrapper::exclude_damaged_shares(shares);
+    for share in excluded.iter() {
+        report.inputs[share.position].status = share.reason.to_string();
+    }
+
+    if healthy.is_empty() || (options.strict && !excluded.is_empty()) {
+        return report;
+    }
+    if let Some(expected) = options.expect_fingerprint {
+        if wrapper::check_fingerprint(&healthy[0], expected).is_err() {
+            return report;
+        }
+    }
+
+    let limit = healthy.iter().map(Vec::len).max().unwrap_or(0);
+    report.stage = wrapper::recovery_failure_stage(healthy, limit);
+    report
+}
+
+/// Recovers native shares, checking that they are of the split with the expected fingerprint
+///
+/// Damaged and mismatched shares are left out, and named by their source, unless `strict` is set,
+/// which fails on them instead.
+fn recover_checked(
+    shares: Vec<Vec<u8>>,
+    sources: &[String],
+    options: &NativeOptions,
+) -> Result<Vec<u8>> {
+    let (shares, excluded) = wrapper::exclude_damaged_shares(shares);
+    let excluded = excluded
+        .iter()
+        .map(|e| format!("{} {}", sources[e.position], e.reason))
+        .collect::<Vec<String>>();
+
+    if options.strict && !excluded.is_empty() {
+        return Err(anyhow::anyhow!(
+            "{} share(s) failed their checks: {}",
+            excluded.len(),
+            excluded.join(", ")
+        ));
+    }
+
+    for share in excluded.iter() {
+        eprintln!("Leaving out {}", share);
+    }
+
+    let recovered = if shares.is_empty() && !excluded.is_empty() {
+        Err(anyhow::anyhow!("None of the shares passed their checks"))
+    } else {
+        recover_sized_by_shares(shares, options.expect_fingerprint, options.ignore_expiry)
+    };
+
+    recovered.map_err(|e| match excluded.is_empty() {
+        true => e,
+        false => e.context(format!(
+            "{} share(s) were left out: {}",
+            excluded.len(),
+            excluded.join(", ")
+        )),
+    })
+}
+
+/// Recovers native shares, checking their fingerprint first if one is expected
+///
+/// The secret is never longer than the encrypted data in the shares, which are already read in
+/// full, so they are the limit on the size of the secret, instead of the default of `from_shares`.
+fn recover_sized_by_shares(
+    shares: Vec<Vec<u8>>,
+    expect_fingerprint: Option<&str>,
+    ignore_expiry: bool,
+) -> Result<Vec<u8>> {
+    if let Some(expected) = expect_fingerprint {
+        let first = shares.first().ok_or(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })?;
+        wrapper::check_fingerprint(first, expected)?;
+    }
+
+    let expires_at = shares
+        .first()
+        .and_then(|share| wrapper::share_expiry(share));
+    let limit = shares.iter().map(Vec::len).max().unwrap_or(0);
+    let recovered = match ignore_expiry {
+        true => wrapper::from_shares_ignoring_expiry(shares, limit),
+        false => wrapper::from_shares_guarded(shares, limit),
+    };
+
+    match (&recovered, expires_at) {
+        (Err(e), Some(expires_at)) if is_expired(e) => {
+            return recovered.map_err(|e| {
+                e.context(format!(
+                    "The shares expired at {}, and the grace period of {} hours for clocks that \
+                     are off has passed too. The expiry is a policy of whoever split them, not a \
+                     protection, decrypt them anyway with --ignore-expiry",
+                    format_utc(expires_at),
+                    wrapper::EXPIRY_GRACE_SECS / 3600
+                ))
+            })
+        }
+        (std::result::Result::Ok(_), Some(expires_at)) if expires_at < unix_now() => {
+            let reason = match ignore_expiry {
+                true => "they were decrypted as --ignore-expiry was given",
+                false => "they are decrypted during the grace period for clocks that are off",
+            };
+            eprintln!(
+                "Warning: the shares expired at {}, {}",
+                format_utc(expires_at),
+                reason
+            )
+        }
+        _ => {}
+    }
+
+    recovered
+}
+
+fn is_expired(e: &anyhow::Error) -> bool {
+    matches!(
+        e.downcast_ref::<ShamirError>(),
+        Some(ShamirError::ShareExpired { .. })
+    )
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default()
+}
+
+const SECS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Reads a date as YYYY-MM-DD, into the last second of that day in UTC
+fn parse_expiry_date(text: &str) -> Result<u64> {
+    let invalid = || anyhow::anyhow!("Invalid date \"{}\", expected YYYY-MM-DD", text);
+    let parts = text
+        .trim()
+        .split('-')
+        .map(|part| part.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<Vec<u32>>>()?;
+    let (year, month, day) = match parts[..] {
+        [year, month, day] => (year as i64, month, day),
+        _ => return Err(invalid()),
+    };
+
+    // Dates that don't exist, like February 30th, come back as another date
+    let days = days_from_civil(year, month, day);
+    if !(1..=12).contains(&month) || civil_from_days(days) != (year, month, day) || days < 0 {
+        return Err(invalid());
+    }
+
+    Ok(days as u64 * SECS_PER_DAY + SECS_PER_DAY - 1)
+}
+
+/// Writes seconds since the Unix epoch as a date and time in UTC
+fn format_utc(secs: u64) -> String {
+    let (year, month, day) = civil_from_days((secs / SECS_PER_DAY) as i64);
+    let time = secs % SECS_PER_DAY;
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+/// Days since the Unix epoch of a date in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month as i64 + 9) % 12) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// The date of a number of days since the Unix epoch, the inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = match shifted_month < 10 {
+        true => shifted_month + 3,
+        false => shifted_month - 9,
+    } as u32;
+    let year = year_of_era + era * 400;
+    (if month <= 2 { year + 1 } else { year }, month, day)
+}
+
+/// How long until or since a moment, as "in 3 days" or "5 hours ago"
+fn time_from_now(secs: u64) -> String {
+    let now = unix_now();
+    let distance = secs.abs_diff(now);
+    let amount = match distance {
+        d if d >= 2 * SECS_PER_DAY => format!("{} days", d / SECS_PER_DAY),
+        d if d >= 2 * 3600 => format!("{} hours", d / 3600),
+        d => format!("{} minutes", d / 60),
+    };
+    match secs >= now {
+        true => format!("in {}", amount),
+        false => format!("{} ago", amount),
+    }
+}
+
+/// Whether a native share kept in the keyring or on a hardware token is to be used
+#[cfg(any(feature = "keyring", feature = "hardware"))]
+fn has_stored_shares(arguments: &DecryptCommand) -> bool {
+    #[cfg(feature = "keyring")]
+    if arguments.from_keyring.is_some() {
+        return true;
+    }
+
+    #[cfg(feature = "hardware")]
+    if arguments.hardware {
+        return true;
+    }
+
+    false
+}
+
+#[cfg(any(feature = "keyring", feature = "hardware"))]
+fn recover_with_stored_shares(
+    arguments: &DecryptCommand,
+    files: &ShareFiles,
+    options: &NativeOptions,
+) -> Result<Vec<u8>> {
+    let mut native = read_native_shares(files, options.strict, options.verbose)?;
+
+    // The slot may hold shares of several splits, pick the one the other shares belong to
+    #[cfg(feature = "keyring")]
+    if let Some(slot) = arguments.from_keyring.as_deref() {
+        let set_id = native.shares.first().and_then(|s| wrapper::share_set_id(s));
+        native.shares.push(keychain::load_share(slot, set_id)?);
+        native
+            .sources
+            .push(format!("the share in keyring slot \"{}\"", slot));
+        native
+            .inputs
+            .push((format!("keyring slot \"{}\"", slot), None));
+    }
+
+    #[cfg(feature = "hardware")]
+    if arguments.hardware {
+        let pin = ask_secret(
+            arguments.pinentry,
+            "Enter the PIN of the hardware token holding a share",
+            "PIN:",
+        )?;
+        native.shares.push(hardware::load_share(pin.as_bytes())?);
+        native
+            .sources
+            .push("the share on the hardware token".to_string());
+        native.inputs.push(("hardware token".to_string(), None));
+    }
+
+    recover_native(native, options)
+}
+
+#[cfg(feature = "keyring")]
+fn handle_keyring_list() -> Result<()> {
+    let entries = keychain::list_entries()?;
+    if entries.is_empty() {
+        println!("No shares are stored in the keyring");
+    }
+
+    for entry in entries.iter() {
+        println!("{}\t{}", entry.slot, keychain::to_hex(&entry.set_id));
+    }
+
+    Ok(())
+}
+
+fn handle_migrate_from(arguments: MigrateFromCommand) -> Result<()> {
+    if !arguments.output.is_dir() {
+        return Err(anyhow::anyhow!(
+            "Output folder \"{}\" is not a folder",
+            arguments.output.display()
+        ));
+    }
+
+    let secret = recover_from_files(
+        arguments.format,
+        &ShareFiles::local(&arguments.files),
+        arguments.source_threshold,
+        arguments.no_diffusion,
+        arguments.pinentry,
+        &NativeOptions::default(),
+    )?;
+
+    let threshold = arguments.threshold.resolve(arguments.shares)?;
+    let shares = wrapper::to_shares(secret, threshold, arguments.shares)?;
+    write_native_shares(&arguments.output, &shares, NativeEncoding::Binary)?;
+
+    println!("Done");
+
+    Ok(())
+}
+
+/// The config file, with settings profiles as `[profile.<name>]` tables
+#[derive(Deserialize, Default)]
+struct Config {
+    #[serde(default)]
+    profile: BTreeMap<String, Profile>,
+    /// Keys that are not known, which are warned about rather than failing
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// The settings of `encrypt` a profile can give, any of them can be left out
+#[derive(Deserialize, Default)]
+struct Profile {
+    /// A number, or a percentage like "60%"
+    threshold: Option<toml::Value>,
+    shares: Option<u8>,
+    scheme: Option<String>,
+    encoding: Option<String>,
+    /// The output folder, where a leading `~` is the home folder
+    out_dir: Option<String>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, toml::Value>,
+}
+
+/// The settings of `encrypt` that profiles can give, before the flags are applied
+struct EncryptSettings {
+    shares: u8,
+    threshold: Threshold,
+    scheme: SharingScheme,
+    encoding: NativeEncoding,
+    output: Option<PathBuf>,
+}
+
+impl Default for EncryptSettings {
+    fn default() -> Self {
+        EncryptSettings {
+            shares: 5,
+            threshold: Threshold::Absolute(3),
+            scheme: SharingScheme::Auto,
+            encoding: NativeEncoding::Binary,
+            output: None,
+        }
+    }
+}
+
+impl EncryptSettings {
+    /// The settings of a profile, with the defaults for the ones it leaves out
+    fn from_profile(name: &str, profile: &Profile) -> Result<EncryptSettings> {
+        let invalid = |key: &str| anyhow::anyhow!("Invalid {} in profile \"{}\"", key, name);
+        let defaults = EncryptSettings::default();
+
+        let threshold = match &profile.threshold {
+            None => defaults.threshold,
+            Some(toml::Value::Integer(threshold)) => u8::try_from(*threshold)
+                .map(Threshold::Absolute)
+                .map_err(|_| invalid("threshold"))?,
+            Some(toml::Value::String(threshold)) => threshold
+                .parse()
+                .map_err(|e: anyhow::Error| e.context(invalid("threshold")))?,
+            Some(_) => return Err(invalid("threshold")),
+        };
+        let scheme = match &profile.scheme {
+            Some(scheme) => SharingScheme::from_str(scheme, true).map_err(|_| invalid("scheme"))?,
+            None => defaults.scheme,
+        };
+        let encoding = match &profile.encoding {
+            Some(encoding) => {
+                NativeEncoding::from_str(encoding, true).map_err(|_| invalid("encoding"))?
+            }
+            None => defaults.encoding,
+        };
+
+        Ok(EncryptSettings {
+            shares: profile.shares.unwrap_or(defaults.shares),
+            threshold,
+            scheme,
+            encoding,
+            output: profile.out_dir.as_deref().map(expand_home),
+        })
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Replaces a leading `~` of a path with the home folder
+fn expand_home(path: &str) -> PathBuf {
+    let rest = match path.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with(['/', '\\']) => rest,
+        _ => return PathBuf::from(path),
+    };
+
+    match home_dir() {
+        Some(home) => home.join(rest.trim_start_matches(['/', '\\'])),
+        None => PathBuf::from(path),
+    }
+}
+
+/// The config file, in `$XDG_CONFIG_HOME/shamir` or else in `~/.config/shamir`
+fn config_path() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")))
+        .map(|dir| dir.join("shamir").join("config.toml"))
+}
+
+/// Reads the config file, warning about the keys it does not know
+fn load_config(path: &Path) -> Result<Config> {
+    let contents = fs::read_to_string(path).map_err(|e| {
+        anyhow::anyhow!("Can't read config file \"{}\": {}", path.display(), e)
+    })?;
+    let config: Config = toml::from_str(&contents)
+        .map_err(|e| anyhow::anyhow!("Invalid config file \"{}\": {}", path.display(), e))?;
+
+    for key in config.unknown.keys() {
+        eprintln!("Warning: unknown key \"{}\" in the config file is ignored", key);
+    }
+    for (name, profile) in config.profile.iter() {
+        for key in profile.unknown.keys() {
+            eprintln!(
+                "Warning: unknown key \"{}\" in profile \"{}\" is ignored",
+                key, name
+            );
+        }
+    }
+
+    Ok(config)
+}
+
+fn load_profile(name: &str) -> Result<Profile> {
+    let path = match config_path() {
+        Some(path) => path,
+        None => return Err(anyhow::anyhow!("No config file, as the home folder is not known")),
+    };
+
+    match load_config(&path)?.profile.remove(name) {
+        Some(profile) => Ok(profile),
+        None => Err(anyhow::anyhow!(
+            "Profile \"{}\" is not in config file \"{}\"",
+            name,
+            path.display()
+        )),
+    }
+}
+
+fn handle_config_show(name: Option<String>) -> Result<()> {
+    let path = config_path();
+    let config = match path.as_deref() {
+        Some(path) if path.exists() => load_config(path)?,
+        _ => Config::default(),
+    };
+    match path.as_deref() {
+        Some(path) => println!("Config file: {}", path.display()),
+        None => println!("Config file: none, as the home folder is not known"),
+    }
+    if !config.profile.is_empty() {
+        let names: Vec<&str> = config.profile.keys().map(|name| name.as_str()).collect();
+        println!("Profiles: {}", names.join(", "));
+    }
+
+    let profile = match name.as_deref() {
+        Some(name) => match config.profile.get(name) {
+            Some(profile) => profile,
+            None => return Err(anyhow::anyhow!("Profile \"{}\" is not in the config file", name)),
+        },
+        None => &Profile::default(),
+    };
+    let settings = EncryptSettings::from_profile(name.as_deref().unwrap_or_default(), profile)?;
+    let source = |given: bool| match (given, name.as_deref()) {
+        (true, Some(name)) => format!("profile \"{}\"", name),
+        _ => "default".to_string(),
+    };
+
+    println!();
+    println!(
+        "shares = {} ({})",
+        settings.shares,
+        source(profile.shares.is_some())
+    );
+    let threshold = match settings.threshold {
+        Threshold::Absolute(threshold) => threshold.to_string(),
+        Threshold::Percent(percent) => format!("{}%", percent),
+    };
+    println!(
+        "threshold = {} ({})",
+        threshold,
+        source(profile.threshold.is_some())
+    );
+    println!(
+        "scheme = {} ({})",
+        value_name(settings.scheme),
+        source(profile.scheme.is_some())
+    );
+    println!(
+        "encoding = {} ({})",
+        value_name(settings.encoding),
+        source(profile.encoding.is_some())
+    );
+    match settings.output {
+        Some(output) => println!(
+            "out_dir = {} ({})",
+            output.display(),
+            source(profile.out_dir.is_some())
+        ),
+        None => println!("out_dir = none, --output is needed"),
+    }
+
+    Ok(())
+}
+
+/// The name a value of a flag is given as on the command line
+fn value_name<T: ValueEnum>(value: T) -> String {
+    value
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+/// The share files given on the command line, along with the contents of the ones given as a URL,
+/// which are fetched once up front
+struct ShareFiles {
+    paths: Vec<PathBuf>,
+    fetched: Vec<(PathBuf, Vec<u8>)>,
+}
+
+impl ShareFiles {
+    fn local(paths: &[PathBuf]) -> ShareFiles {
+        ShareFiles {
+            paths: paths.to_vec(),
+            fetched: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "http")]
+    fn fetch(paths: &[PathBuf], options: &fetch::FetchOptions) -> Result<ShareFiles> {
+        let mut fetched: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+        for path in paths.iter() {
+            let url = path.to_string_lossy();
+            if fetch::is_url(&url) {
+                fetched.push((path.to_owned(), fetch::fetch_share(&url, options)?));
+            }
+        }
+
+        Ok(ShareFiles {
+            paths: paths.to_vec(),
+            fetched,
+        })
+    }
+
+    fn fetched(&self, path: &Path) -> Option<&Vec<u8>> {
+        self.fetched
+            .iter()
+            .find(|(p, _)| p == path)
+            .map(|(_, contents)| contents)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        self.fetched(path).is_some() || path.exists()
+    }
+
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        match self.fetched(path) {
+            Some(contents) => Ok(contents.clone()),
+            None => Ok(fs::read(path)?),
+        }
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|_| anyhow::anyhow!("File \"{}\" is not text", path.display()))
+    }
+}
+
+/// Native shares read from files, ordered by index
+struct NativeShares {
+    shares: Vec<Vec<u8>>,
+    /// The file every share came from, to name it in messages
+    sources: Vec<String>,
+    /// The path or URL of every share, and how it was encoded, `None` when it is not a share
+    inputs: Vec<(String, Option<String>)>,
+    /// The files skipped as they are not shares
+    skipped: Vec<PathBuf>,
+}
+
+/// Reads native shares from files, folders of shares, and folders of QR code frames, also
+/// returning the files in folders that were skipped as they are not shares
+fn read_native_shares(files: &ShareFiles, strict: bool, verbose: bool) -> Result<NativeShares> {
+    let mut candidates: Vec<(PathBuf, Vec<u8>, Option<String>)> = Vec::new();
+    let mut skipped: Vec<PathBuf> = Vec::new();
+    for file in files.paths.iter() {
+        // Files that are not shares in any encoding are left to recovery to report as damaged
+        if !file.is_dir() {
+            let contents = files.read(file)?;
+            let found = encoding::read_share_bundle(&contents);
+            if found.is_empty() {
+                candidates.push((file.to_owned(), contents, None));
+            } else if found.len() > 1 {
+                eprintln!("Found {} shares in \"{}\"", found.len(), file.display());
+            }
+
+            for (share, form) in found {
+                if verbose {
+                    eprintln!("Reading \"{}\" as {}", file.display(), form);
+                }
+                candidates.push((file.to_owned(), share, Some(form.to_string())));
+            }
+            continue;
+        }
+
+        // A folder holding any shares is a folder of shares, which may also hold READMEs and the
+        // like, otherwise it is a folder of QR code frames
+        let mut paths = fs::read_dir(file)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<std::io::Result<Vec<PathBuf>>>()?;
+        paths.sort();
+
+        let mut found: Vec<(PathBuf, Vec<u8>, Option<String>)> = Vec::new();
+        let mut others: Vec<PathBuf> = Vec::new();
+        for path in paths.into_iter().filter(|p| p.is_file()) {
+            let shares = encoding::read_share_bundle(&fs::read(&path)?);
+            if shares.is_empty() {
+                others.push(path);
+                continue;
+            }
+            if shares.len() > 1 {
+                eprintln!("Found {} shares in \"{}\"", shares.len(), path.display());
+            }
+
+            for (share, form) in shares {
+                if verbose {
+                    eprintln!("Reading \"{}\" as {}", path.display(), form);
+                }
+                found.push((path.clone(), share, Some(form.to_string())));
+            }
+        }
+
+        if found.is_empty() {
+            #[cfg(feature = "qr")]
+            {
+                if verbose {
+                    eprintln!("Reading \"{}\" as QR code frames", file.display());
+                }
+                let frames = Some("QR code frames".to_string());
+                candidates.push((file.to_owned(), read_qr_frames(file)?, frames));
+                continue;
+            }
+            #[cfg(not(feature = "qr"))]
+            return Err(anyhow::anyhow!(
+                "Folder \"{}\" contains no shares, and reading QR code frames needs the qr feature",
+                file.display()
+            ));
+        }
+
+        for other in others.iter() {
+            eprintln!("Skipping \"{}\", it is not a share", other.display());
+        }
+        candidates.extend(found);
+        skipped.extend(others);
+    }
+
+    // The same share often shows up twice, from globs matching backup copies, so exact copies are
+    // dropped, and the rest is ordered by index. Different shares with the same index are left to
+    // recovery to leave out, unless that should fail on them.
+    let mut shares: Vec<(Option<u8>, PathBuf, _, Option<String>)> = Vec::new();
+    for (file, share, form) in candidates {
+        let index = wrapper::share_index(&share);
+
+        match shares.iter().find(|(i, _, _, _)| index.is_some() && *i == index) {
+            Some((_, _, other_share, _)) if *other_share == share => {
+                eprintln!(
+                    "Share index {} provided twice, using one copy",
+                    index.unwrap()
+                );
+            }
+            Some((_, other, _, _)) if strict => {
+                return Err(anyhow::anyhow!(
+                    "\"{}\" and \"{}\" are both share {}, but are different",
+                    other.display(),
+                    file.display(),
+                    index.unwrap()
+                ));
+            }
+            _ => shares.push((index, file, share, form)),
+        }
+    }
+
+    shares.sort_by_key(|(index, _, _, _)| *index);
+    let sources = shares
+        .iter()
+        .map(|(_, file, _, _)| format!("\"{}\"", file.display()))
+        .collect();
+    let inputs = shares
+        .iter()
+        .map(|(_, file, _, form)| (file.display().to_string(), form.clone()))
+        .collect();
+    let shares = shares.into_iter().map(|(_, _, share, _)| share).collect();
+    Ok(NativeShares {
+        shares,
+        sources,
+        inputs,
+        skipped,
+    })
+}
+
+/// Adds the files that were skipped while reading shares to a failed recovery
+fn note_skipped(e: anyhow::Error, skipped: &[PathBuf]) -> anyhow::Error {
+    if skipped.is_empty() {
+        return e;
+    }
+
+    let names = skipped
+        .iter()
+        .map(|p| format!("\"{}\"", p.display()))
+        .collect::<Vec<String>>();
+    e.context(format!(
+        "{} file(s) were skipped as they are not shares: {}",
+        skipped.len(),
+        names.join(", ")
+    ))
+}
+
+#[cfg(feature = "qr")]
+fn read_qr_frames(folder: &PathBuf) -> Result<Vec<u8>> {
+    // Every image in the folder may hold a part, frames that can't be read are skipped, as the
+    // fountain coding can make up for a few missing ones
+    let mut parts: Vec<String> = Vec::new();
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        match qr::read_image(&fs::read(&path)?) {
+            std::result::Result::Ok(contents) => parts.extend(contents),
+            Err(_) => eprintln!("Skipping \"{}\", no QR code found", path.display()),
+        }
+    }
+
+    if parts.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Folder \"{}\" contains no QR code frames",
+            folder.display()
+        ));
+    }
+
+    qr::from_parts(&parts)
+}
+
+fn read_sskr_share(file: &Path, contents: &str) -> Result<Vec<u8>> {
+    // A share file may contain the ByteWords, the UR, or both; use the first line that parses
+    let mut last_error = anyhow::anyhow!("File \"{}\" contains no SSKR share", file.display());
+    for line in contents.lines().filter(|l| !l.trim().is_empty()) {
+        match sskr::parse_share(line) {
+            std::result::Result::Ok(share) => return Ok(share),
+            Err(e) => last_error = e,
+        }
+    }
+
+    Err(last_error)
+}
+
+fn handle_format_version() {
+    print!("{}", format_version_report());
+}
+
+fn format_version_report() -> String {
+    format!(
+        "{} {}\nWrites share format version {}\nReads share format versions {} to {}\n",
+        clap::crate_name!(),
+        clap::crate_version!(),
+        wrapper::FORMAT_VERSION,
+        wrapper::MIN_FORMAT_VERSION,
+        wrapper::FORMAT_VERSION
+    )
+}
+
+/// Overwrites the files before deleting them, which does not reach old copies that copy on write
+/// file systems or SSD wear leveling keep, decrypt with --memory-backed to avoid those
+fn handle_shred(arguments: ShredCommand) -> Result<()> {
+    for file in arguments.files.iter() {
+        if !file.is_file() {
+            return Err(anyhow::anyhow!("File \"{}\" is not a file", file.display()));
+        }
+    }
+
+    for file in arguments.files.iter() {
+        let length = fs::metadata(file)?.len() as usize;
+        let mut noise = vec![0u8; length];
+        rand::rngs::OsRng.fill_bytes(&mut noise);
+
+        let mut handle = fs::OpenOptions::new().write(true).open(file)?;
+        handle.write_all(&noise)?;
+        handle.sync_all()?;
+        drop(handle);
+
+        fs::remove_file(file)?;
+        println!("Shredded \"{}\"", file.display());
+    }
+
+    Ok(())
+}
+
+fn handle_verify(arguments: VerifyCommand) -> Result<()> {
+    // Check if input files exist and are files
+    for file in arguments.files.iter() {
+        if !file.exists() {
+            return Err(anyhow::anyhow!(
+                "File \"{}\" does not exist",
+                file.display()
+            ));
+        }
+
+        if !file.is_file() {
+            return Err(anyhow::anyhow!("File \"{}\" is not a file", file.display()));
+        }
+    }
+
+    // Shares in a text form are checked as the share they decode to
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for file in arguments.files.iter() {
+        let contents = fs::read(file)?;
+        match encoding::read_share_file(&contents) {
+            Some((share, form)) => {
+                if form != encoding::ShareEncoding::Binary {
+                    println!("\"{}\" holds its share as {}", file.display(), form);
+                }
+                shares.push(share);
+            }
+            None => shares.push(contents),
+        }
+    }
+
+    // Report everything that is off with the shares, without recovering anything from them
+    let report = wrapper::analyze_shares(&shares)?;
+    for (position, reason) in report.failed.iter() {
+        println!("\"{}\" {}", arguments.files[*position].display(), reason);
+    }
+
+    let manifest = match arguments.manifest.as_deref() {
+        Some(path) => Some(load_manifest(path)?),
+        None => None,
+    };
+    let mut mismatched = 0;
+    for (file, share) in arguments.files.iter().zip(shares.iter()) {
+        if let Some(Err(e)) = manifest.as_ref().map(|m| m.check_share(share)) {
+            println!("\"{}\" does not match the manifest: {}", file.display(), e);
+            mismatched += 1;
+        }
+    }
+    for index in report.duplicates.iter() {
+        println!("Share {} is given more than once", index);
+    }
+    for index in report.conflicting.iter() {
+        println!("Different shares claim index {}, at most one of them is genuine", index);
+    }
+
+    // Whether the shares recover the secret does not depend on what their headers claim
+    let quorum = arguments.deep.then(|| wrapper::preview_quorum(&shares, arguments.max_subsets));
+    if let Some(preview) = quorum.as_ref() {
+        match preview.subset.as_ref() {
+            Some(subset) => {
+                let names = subset
+                    .iter()
+                    .map(|p| format!("\"{}\"", arguments.files[*p].display()))
+                    .collect::<Vec<String>>();
+                println!(
+                    "{} recover the secret, of a split of {} shares",
+                    names.join(", "),
+                    preview.count.unwrap_or_default()
+                );
+
+                for position in preview.disagreeing.iter() {
+                    println!(
+                        "\"{}\" does not agree with them, it or one of them is damaged",
+                        arguments.files[*position].display()
+                    );
+                }
+            }
+            None if preview.truncated => println!(
+                "None of the first {} subsets of the shares recover the secret, more subsets \
+                 remain to be tried with a higher --max-subsets",
+                preview.tried
+            ),
+            None => println!(
+                "None of the {} subsets of the shares recover the secret",
+                preview.tried
+            ),
+        }
+    }
+
+    let disagreeing = quorum.as_ref().map_or(0, |preview| preview.disagreeing.len());
+    let problems = report.failed.len()
+        + report.duplicates.len()
+        + report.conflicting.len()
+        + disagreeing
+        + mismatched;
+    if problems > 0 {
+        return Err(anyhow::anyhow!("Found {} problem(s) with the shares", problems));
+    }
+
+    if quorum.is_some_and(|preview| preview.subset.is_none()) {
+        return Err(anyhow::anyhow!("The shares do not recover the secret"));
+    }
+
+    if !report.missing.is_empty() {
+        let missing = report
+            .missing
+            .iter()
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        println!("Shares {} are not given", missing.join(", "));
+    }
+
+    // The shares of one split carry the same expiry
+    if let Some(expires_at) = shares.iter().find_map(|share| wrapper::share_expiry(share)) {
+        let expired = match expires_at < unix_now() {
+            true => "expired",
+            false => "expire",
+        };
+        println!(
+            "The shares {} at {}, {}",
+            expired,
+            format_utc(expires_at),
+            time_from_now(expires_at)
+        );
+    }
+
+    println!("All {} shares belong to the same split", shares.len());
+    if manifest.is_some() {
+        println!("All {} shares match the manifest", shares.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folders_of_shares_skip_other_files() {
+        let folder = std::env::temp_dir().join(format!("shamir-messy-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let shares = wrapper::to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        fs::write(folder.join("share-1"), &shares[0]).unwrap();
+        fs::write(folder.join("README.md"), "# Shares\n\nKeep these safe.\n").unwrap();
+        let ds_store = [0, 0, 0, 1, b'B', b'u', b'd', b'1', 0, 0];
+        fs::write(folder.join(".DS_Store"), ds_store).unwrap();
+        fs::write(folder.join("manifest.json"), "{\"threshold\": 2}").unwrap();
+
+        let files = ShareFiles::local(std::slice::from_ref(&folder));
+        let read = read_native_shares(&files, false, false).unwrap();
+        assert_eq!(read.shares, vec![shares[0].clone()]);
+        assert_eq!(read.skipped.len(), 3);
+
+        let error = wrapper::from_shares(read.shares)
+            .map_err(|e| note_skipped(e, &read.skipped))
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("3 file(s) were skipped as they are not shares: "));
+
+        fs::write(folder.join("share-3"), &shares[2]).unwrap();
+        let read = read_native_shares(&files, false, false).unwrap();
+        assert_eq!(read.skipped.len(), 3);
+        assert_eq!(wrapper::from_shares(read.shares).unwrap(), b"secret");
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
+
+    #[test]
+    fn stdout_only_never_creates_a_file() {
+        let folder = std::env::temp_dir().join(format!("shamir-stdout-{}", std::process::id()));
+        fs::create_dir_all(&folder).unwrap();
+        let shares = wrapper::to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        fs::write(folder.join("share-1"), &shares[0]).unwrap();
+        fs::write(folder.join("share-2"), &shares[1]).unwrap();
+
+        let command = |extra: &[&str]| {
+            let mut args = vec!["distributed_encrypt", "decrypt", "--stdout-only"];
+            args.extend_from_slice(extra);
+            let share_1 = folder.join("share-1");
+            let share_2 = folder.join("share-2");
+            args.extend([share_1.to_str().unwrap(), share_2.to_str().unwrap()]);
+            Cli::try_parse_from(args).map(|cli| cli.command)
+        };
+        let output = folder.join("secret");
+        assert!(command(&["--output", output.to_str().unwrap()]).is_err());
+
+        let arguments = match command(&[]).unwrap() {
+            SubCommand::Decrypt(arguments) => arguments,
+            _ => unreachable!(),
+        };
+        let mut out: Vec<u8> = Vec::new();
+        decrypt_to(arguments, &mut out).unwrap();
+        assert_eq!(out, b"secret");
+
+        let mut files = fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect::<Vec<_>>();
+        files.sort();
+        assert_eq!(files, ["share-1", "share-2"]);
+
+        fs::remove_dir_all(&folder).unwrap();
+    }
 }
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use bip39::{Language, Mnemonic};
+use sha3::{Digest, Sha3_256};
+
+/// Parses a BIP39 mnemonic, validating its wordlist and checksum, and returns the entropy it
+/// encodes
+///
+/// Only the entropy is returned, a passphrase (the "25th word") is not part of the mnemonic and so
+/// can never be recovered from it
+pub fn to_entropy(mnemonic: &str) -> Result<Vec<u8>> {
+    let words = mnemonic.split_whitespace().collect::<Vec<&str>>().join(" ");
+    let mnemonic = Mnemonic::parse_normalized(&words)
+        .map_err(|e| anyhow::anyhow!("Invalid BIP39 mnemonic: {}", e))?;
+
+    Ok(mnemonic.to_entropy())
+}
+
+/// Encodes recovered entropy back into the BIP39 mnemonic it was taken from
+pub fn from_entropy(entropy: &[u8]) -> Result<String> {
+    let mnemonic = Mnemonic::from_entropy(entropy)
+        .map_err(|e| anyhow::anyhow!("Recovered data is not BIP39 entropy: {}", e))?;
+
+    Ok(mnemonic.to_string())
+}
+
+/// Writes any bytes as words of the BIP39 English wordlist, for shares that are too long to be a
+/// BIP39 mnemonic themselves
+///
+/// Every word holds 11 bits. The bytes are followed by a single set bit that marks where they end,
+/// padded with zeros to a whole word, and one more word holds a checksum of the bytes.
+pub fn to_words(bytes: &[u8]) -> String {
+    let mut bits: Vec<bool> = bytes
+        .iter()
+        .flat_map(|b| (0..8).rev().map(move |i| b >> i & 1 == 1))
+        .collect();
+    bits.push(true);
+    while !bits.len().is_multiple_of(11) {
+        bits.push(false);
+    }
+
+    let words = Language::English.word_list();
+    let mut mnemonic = bits
+        .chunks(11)
+        .map(|chunk| words[chunk.iter().fold(0, |acc, bit| acc << 1 | *bit as usize)])
+        .collect::<Vec<&str>>();
+    mnemonic.push(words[checksum(bytes) as usize]);
+
+    mnemonic.join(" ")
+}
+
+/// Reads back the bytes written by `to_words`
+pub fn from_words(mnemonic: &str) -> Result<Vec<u8>> {
+    let mut indices: Vec<u16> = Vec::new();
+    for word in mnemonic.split_whitespace() {
+        let word = word.to_lowercase();
+        let index = Language::English
+            .find_word(&word)
+            .ok_or_else(|| anyhow::anyhow!("\"{}\" is not a BIP39 word", word))?;
+        indices.push(index);
+    }
+
+    let expected = indices
+        .pop()
+        .ok_or_else(|| anyhow::anyhow!("Mnemonic has no words"))?;
+
+    let mut bits: Vec<bool> = indices
+        .iter()
+        .flat_map(|i| (0..11).rev().map(move |b| i >> b & 1 == 1))
+        .collect();
+    while bits.last() == Some(&false) {
+        bits.pop();
+    }
+    if bits.pop() != Some(true) || !bits.len().is_multiple_of(8) {
+        return Err(anyhow::anyhow!("Mnemonic is not a share written as words"));
+    }
+
+    let bytes = bits
+        .chunks(8)
+        .map(|chunk| chunk.iter().fold(0u8, |acc, bit| acc << 1 | *bit as u8))
+        .collect::<Vec<u8>>();
+    if checksum(&bytes) != expected {
+        return Err(anyhow::anyhow!(
+            "Mnemonic checksum does not match, check the words for typos"
+        ));
+    }
+
+    Ok(bytes)
+}
+
+/// The first 11 bits of the hash of the bytes
+fn checksum(bytes: &[u8]) -> u16 {
+    let hash = Sha3_256::digest(bytes);
+    u16::from_be_bytes([hash[0], hash[1]]) >> 5
+}
@@ -0,0 +1,239 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Key,
+};
+use anyhow::Result;
+use argon2::{Algorithm, Argon2, Params, Version};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use subtle::ConstantTimeEq;
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::error::ShamirError;
+use crate::shamir::secure_rng;
+use crate::wrapper::{from_shares, share_index, to_shares};
+
+/// A share encrypted with a key derived from a passphrase
+#[derive(Debug, Serialize, Deserialize)]
+struct PassphraseShare {
+    /// The index of the share, in the clear to tell the shares apart
+    index: u8,
+    salt: [u8; 16],
+    /// Argon2id memory cost in KiB, number of passes, and degree of parallelism
+    cost: (u32, u32, u32),
+    /// Derived from the passphrase along with the key, to check a passphrase without unwrapping
+    check: [u8; 32],
+    nonce: [u8; 12],
+    ciphertext: Vec<u8>,
+}
+
+/// Splits the input like `to_shares`, and wraps every share with a key derived from the passphrase
+///
+/// The key is derived with Argon2id under its default cost, with one salt for all shares. Every
+/// share also carries a token derived along with the key, so `verify_passphrase` tells from a
+/// single share whether a passphrase is right, before the other shares are gathered.
+pub fn to_shares_with_passphrase(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    passphrase: &str,
+) -> Result<Vec<Vec<u8>>> {
+    wrap_shares(input, threshold, count, passphrase, Params::default())
+}
+
+fn wrap_shares(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    passphrase: &str,
+    params: Params,
+) -> Result<Vec<Vec<u8>>> {
+    let mut rng = secure_rng()?;
+    let mut salt = [0u8; 16];
+    rng.fill_bytes(&mut salt);
+    let cost = (params.m_cost(), params.t_cost(), params.p_cost());
+    let (key, check) = derive(passphrase, &salt, cost)?;
+
+    let mut shares = to_shares(input.to_vec(), threshold, count)?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key[..]));
+    let wrapped = shares
+        .iter()
+        .map(|share| {
+            let nonce = Aes256Gcm::generate_nonce(&mut rng);
+            let wrapped = PassphraseShare {
+                index: share_index(share).ok_or(ShamirError::InconsistentShares)?,
+                salt,
+                cost,
+                check,
+                nonce: nonce.into(),
+                ciphertext: cipher
+                    .encrypt(&nonce, share.as_slice())
+                    .map_err(|_| anyhow::anyhow!("Encryption failed"))?,
+            };
+            Ok(bincode::serialize(&wrapped)?)
+        })
+        .collect::<Result<Vec<Vec<u8>>>>();
+    for share in shares.iter_mut() {
+        share.zeroize();
+    }
+
+    wrapped
+}
+
+/// Whether the passphrase is the one a share of `to_shares_with_passphrase` was wrapped with
+///
+/// This only takes the one share, which is the point, but it also means anyone holding a share can
+/// tell a right guess of the passphrase from a wrong one. The Argon2id cost of every guess is all
+/// that stands in the way, so the passphrase has to be strong.
+pub fn verify_passphrase(share: &[u8], passphrase: &str) -> bool {
+    let wrapped: PassphraseShare = match bincode::deserialize(share) {
+        Ok(wrapped) => wrapped,
+        Err(_) => return false,
+    };
+
+    match derive(passphrase, &wrapped.salt, wrapped.cost) {
+        Ok((_, check)) => check.ct_eq(&wrapped.check).into(),
+        Err(_) => false,
+    }
+}
+
+/// Whether the bytes are a share of `to_shares_with_passphrase`, without checking any passphrase
+pub fn is_passphrase_share(bytes: &[u8]) -> bool {
+    // Bytes that merely start like a wrapped share don't count, the whole of them has to be one
+    bincode::deserialize::<PassphraseShare>(bytes).is_ok_and(|wrapped| {
+        bincode::serialized_size(&wrapped).is_ok_and(|size| size == bytes.len() as u64)
+    })
+}
+
+/// Unwraps a share of `to_shares_with_passphrase`, for use with `from_shares`
+pub fn unwrap_share(share: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+    let wrapped: PassphraseShare = bincode::deserialize(share)
+        .map_err(|_| anyhow::anyhow!("Share is not wrapped with a passphrase"))?;
+    let (key, check) = derive(passphrase, &wrapped.salt, wrapped.cost)?;
+    if !bool::from(check.ct_eq(&wrapped.check)) {
+        return Err(anyhow::anyhow!("Wrong passphrase"));
+    }
+
+    let share = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key[..]))
+        .decrypt(&wrapped.nonce.into(), wrapped.ciphertext.as_ref())
+        .map_err(|_| anyhow::Error::new(ShamirError::ShareTampered))?;
+    if share_index(&share) != Some(wrapped.index) {
+        return Err(anyhow::Error::new(ShamirError::ShareTampered)
+            .context("Wrapped share does not have the index it claims"));
+    }
+
+    Ok(share)
+}
+
+/// Recovers the input from shares of `to_shares_with_passphrase`
+pub fn from_shares_with_passphrase(shares: Vec<Vec<u8>>, passphrase: &str) -> Result<Vec<u8>> {
+    let unwrapped = shares
+        .iter()
+        .map(|share| unwrap_share(share, passphrase))
+        .collect::<Result<Vec<Vec<u8>>>>()?;
+    from_shares(unwrapped)
+}
+
+/// The highest Argon2id memory cost a share may ask for, 1 GiB, as crafted shares could otherwise
+/// ask for more memory than there is
+const MAX_M_COST: u32 = 1024 * 1024;
+
+/// The most passes a share may ask for, the number `to_shares_with_passphrase` writes, as crafted
+/// shares could otherwise keep Argon2id running for as long as they like
+const MAX_T_COST: u32 = Params::DEFAULT_T_COST;
+
+/// The highest degree of parallelism a share may ask for, the one `to_shares_with_passphrase`
+/// writes
+const MAX_P_COST: u32 = Params::DEFAULT_P_COST;
+
+/// Derives the wrapping key and the check token from the passphrase in one Argon2id run
+fn derive(
+    passphrase: &str,
+    salt: &[u8; 16],
+    (m_cost, t_cost, p_cost): (u32, u32, u32),
+) -> Result<(Zeroizing<[u8; 32]>, [u8; 32])> {
+    if m_cost > MAX_M_COST {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context("Argon2 memory cost of the share is too high"));
+    }
+    if t_cost > MAX_T_COST || p_cost > MAX_P_COST {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context("Argon2 time cost or parallelism of the share is too high"));
+    }
+    let params = Params::new(m_cost, t_cost, p_cost, Some(64))
+        .map_err(|e| anyhow::anyhow!("Invalid Argon2 parameters: {}", e))?;
+    let mut output = Zeroizing::new([0u8; 64]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut output[..])
+        .map_err(|e| anyhow::anyhow!("Key derivation failed: {}", e))?;
+
+    let mut key = Zeroizing::new([0u8; 32]);
+    key.copy_from_slice(&output[..32]);
+    let check: [u8; 32] = output[32..].try_into().unwrap();
+    Ok((key, check))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PASSPHRASE: &str = "correct horse battery staple";
+
+    /// Wraps with the lowest cost Argon2id allows, so the tests don't wait on the default one
+    fn wrap(passphrase: &str) -> Vec<Vec<u8>> {
+        let params = Params::new(Params::MIN_M_COST, 1, 1, None).unwrap();
+        wrap_shares(b"secret", 2, 3, passphrase, params).unwrap()
+    }
+
+    #[test]
+    fn passphrases_are_checked_against_a_single_share() {
+        let shares = wrap(PASSPHRASE);
+
+        assert!(verify_passphrase(&shares[1], PASSPHRASE));
+        assert!(!verify_passphrase(&shares[1], "Tr0ub4dor&3"));
+        assert!(!verify_passphrase(&shares[1], ""));
+        assert!(!verify_passphrase(b"not a share", PASSPHRASE));
+    }
+
+    #[test]
+    fn wrapped_shares_recover_with_the_passphrase() {
+        let shares = wrap(PASSPHRASE);
+
+        let recovered = from_shares_with_passphrase(shares[1..].to_vec(), PASSPHRASE);
+        assert_eq!(recovered.unwrap(), b"secret");
+        assert!(from_shares_with_passphrase(shares, "wrong").is_err());
+    }
+
+    #[test]
+    fn wrapped_shares_are_told_apart_from_other_bytes() {
+        let shares = wrap(PASSPHRASE);
+        assert!(is_passphrase_share(&shares[0]));
+
+        let mut longer = shares[0].clone();
+        longer.push(0);
+        assert!(!is_passphrase_share(&longer));
+        assert!(!is_passphrase_share(&shares[0][..shares[0].len() - 1]));
+        let native = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        assert!(!is_passphrase_share(&native[0]));
+    }
+
+    #[test]
+    fn shares_asking_for_too_many_passes_are_rejected_before_deriving() {
+        let mut wrapped: PassphraseShare = bincode::deserialize(&wrap(PASSPHRASE)[0]).unwrap();
+        for cost in [
+            (Params::MIN_M_COST, u32::MAX, 1),
+            (Params::MIN_M_COST, 1, Params::MAX_P_COST),
+        ] {
+            wrapped.cost = cost;
+            let crafted = bincode::serialize(&wrapped).unwrap();
+
+            // Argon2id would run for hours on these costs, returning at all shows it never ran
+            let error = unwrap_share(&crafted, PASSPHRASE).unwrap_err();
+            assert_eq!(
+                error.downcast_ref::<ShamirError>(),
+                Some(&ShamirError::UnsupportedParameters)
+            );
+            assert!(!verify_passphrase(&crafted, PASSPHRASE));
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+/// A single PEM block, with everything needed to write it back out byte for byte
+#[derive(Debug, Serialize, Deserialize)]
+struct PemBlock {
+    label: String,
+    line_width: usize,
+    line_ending: String,
+    der: Vec<u8>,
+}
+
+/// Parses a PEM file holding a single unencrypted key, and returns the payload to share
+///
+/// The payload is the DER contents plus the label and layout of the file, so `from_payload` can
+/// recreate the exact same file
+pub fn to_payload(pem: &[u8]) -> Result<Vec<u8>> {
+    let text = std::str::from_utf8(pem).map_err(|_| anyhow::anyhow!("PEM file is not text"))?;
+
+    let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+    let lines: Vec<&str> = text.lines().collect();
+
+    let begins = lines.iter().filter(|l| l.starts_with("-----BEGIN ")).count();
+    if begins == 0 {
+        return Err(anyhow::anyhow!("File does not contain a PEM block"));
+    }
+    if begins > 1 {
+        return Err(anyhow::anyhow!(
+            "File contains {} PEM blocks, split them into separate files first",
+            begins
+        ));
+    }
+
+    let label = lines[0]
+        .strip_prefix("-----BEGIN ")
+        .and_then(|l| l.strip_suffix("-----"))
+        .ok_or_else(|| anyhow::anyhow!("PEM file must start with its BEGIN line"))?;
+
+    if label.contains("ENCRYPTED") || lines.iter().any(|l| l.starts_with("Proc-Type:")) {
+        return Err(anyhow::anyhow!(
+            "Encrypted PEM files are not supported, decrypt the key first (for example with `openssl pkey`)"
+        ));
+    }
+
+    let end = format!("-----END {}-----", label);
+    let body_lines = match lines.iter().position(|l| *l == end) {
+        Some(position) => &lines[1..position],
+        None => return Err(anyhow::anyhow!("PEM block is missing its END line")),
+    };
+
+    let der = STANDARD
+        .decode(body_lines.concat())
+        .map_err(|e| anyhow::anyhow!("PEM block contains invalid base64: {}", e))?;
+
+    let block = PemBlock {
+        label: label.to_string(),
+        line_width: body_lines.first().map(|l| l.len()).unwrap_or(64),
+        line_ending: line_ending.to_string(),
+        der,
+    };
+
+    // Only accept files that can be recreated exactly, anything else would change its checksum
+    if encode(&block) != pem {
+        return Err(anyhow::anyhow!(
+            "PEM file has irregular formatting or extra text, and could not be recreated byte for byte"
+        ));
+    }
+
+    Ok(bincode::serialize(&block)?)
+}
+
+/// Recreates the original PEM file from a recovered payload
+pub fn from_payload(payload: &[u8]) -> Result<Vec<u8>> {
+    let block: PemBlock = bincode::deserialize(payload)
+        .map_err(|_| anyhow::anyhow!("Recovered data is not a PEM payload"))?;
+
+    Ok(encode(&block))
+}
+
+fn encode(block: &PemBlock) -> Vec<u8> {
+    let body = STANDARD.encode(&block.der);
+
+    let mut pem = format!("-----BEGIN {}-----{}", block.label, block.line_ending);
+    for line in body.as_bytes().chunks(block.line_width.max(1)) {
+        pem.push_str(std::str::from_utf8(line).unwrap());
+        pem.push_str(&block.line_ending);
+    }
+    pem.push_str(&format!("-----END {}-----{}", block.label, block.line_ending));
+
+    pem.into_bytes()
+}
@@ -0,0 +1,203 @@
+use anyhow::Result;
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use zeroize::Zeroizing;
+
+/// The Assuan error code pinentry answers with when the dialog is cancelled, `GPG_ERR_CANCELED`
+/// from the pinentry source
+const CANCELLED: &str = "83886179";
+
+/// The pinentry program to ask for passphrases and PINs with, the `pinentry-program` of
+/// gpg-agent.conf when it names one, otherwise `pinentry` when it is on the PATH
+pub fn find_program() -> Option<PathBuf> {
+    let gnupg_home = env::var_os("GNUPGHOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".gnupg")));
+    if let Some(program) = gnupg_home
+        .and_then(|home| fs::read_to_string(home.join("gpg-agent.conf")).ok())
+        .and_then(|conf| program_from_agent_conf(&conf))
+    {
+        return Some(program);
+    }
+
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|folder| folder.join("pinentry"))
+        .find(|program| program.is_file())
+}
+
+/// The `pinentry-program` option of a gpg-agent.conf, the last one counts like for gpg-agent
+fn program_from_agent_conf(conf: &str) -> Option<PathBuf> {
+    conf.lines()
+        .filter_map(|line| line.trim().strip_prefix("pinentry-program"))
+        .filter(|rest| rest.starts_with([' ', '\t']))
+        .map(|rest| PathBuf::from(rest.trim()))
+        .next_back()
+}
+
+/// Asks for a passphrase or PIN with the pinentry program, speaking the Assuan protocol to it
+///
+/// The description is shown above the input field and the prompt in front of it. Pinentry asks on
+/// the terminal of `GPG_TTY` when it is a terminal based one, like gpg-agent tells it to. Returns
+/// `None` when the dialog was cancelled.
+pub fn get_pin(
+    program: &Path,
+    description: &str,
+    prompt: &str,
+) -> Result<Option<Zeroizing<String>>> {
+    let mut child = Command::new(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Failed to start \"{}\": {}", program.display(), e))?;
+    let mut input = child.stdin.take().unwrap();
+    let mut output = BufReader::new(child.stdout.take().unwrap());
+
+    let mut commands = Vec::new();
+    if let Some(tty) = env::var_os("GPG_TTY") {
+        commands.push(format!("OPTION ttyname={}", tty.to_string_lossy()));
+    }
+    commands.push(format!("SETDESC {}", escape(description)));
+    commands.push(format!("SETPROMPT {}", escape(prompt)));
+
+    // Every command, and the greeting before them, is answered with OK or ERR
+    let result = read_response(&mut output).and_then(|_| {
+        for command in commands.iter() {
+            writeln!(input, "{}", command)?;
+            read_response(&mut output)?;
+        }
+        writeln!(input, "GETPIN")?;
+        read_response(&mut output)
+    });
+
+    let _ = writeln!(input, "BYE");
+    drop(input);
+    let _ = child.wait();
+
+    result
+}
+
+/// Reads the lines pinentry answers a command with, returning the data lines joined and unescaped,
+/// or `None` when the dialog was cancelled
+fn read_response(output: &mut impl BufRead) -> Result<Option<Zeroizing<String>>> {
+    let mut data = Zeroizing::new(String::new());
+    loop {
+        let mut line = Zeroizing::new(String::new());
+        if output.read_line(&mut line)? == 0 {
+            return Err(anyhow::anyhow!("Pinentry closed the connection"));
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+
+        if line == "OK" || line.starts_with("OK ") {
+            return Ok(Some(data));
+        }
+        if let Some(error) = line.strip_prefix("ERR ") {
+            return match error.split_once(' ') {
+                Some((CANCELLED, _)) => Ok(None),
+                _ => Err(anyhow::anyhow!("Pinentry failed: {}", error)),
+            };
+        }
+        if let Some(chunk) = line.strip_prefix("D ") {
+            data.push_str(&unescape(chunk)?);
+        }
+        // Status lines and comments carry nothing that is needed
+    }
+}
+
+/// Escapes the characters Assuan doesn't allow in a line, as %XX
+fn escape(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\n', "%0A")
+        .replace('\r', "%0D")
+}
+
+fn unescape(text: &str) -> Result<Zeroizing<String>> {
+    let mut bytes = Zeroizing::new(Vec::with_capacity(text.len()));
+    let mut rest = text.as_bytes();
+    while let Some((&byte, after)) = rest.split_first() {
+        if byte == b'%' && after.len() >= 2 {
+            let hex = std::str::from_utf8(&after[..2])?;
+            bytes.push(u8::from_str_radix(hex, 16)?);
+            rest = &after[2..];
+        } else {
+            bytes.push(byte);
+            rest = after;
+        }
+    }
+
+    Ok(Zeroizing::new(String::from_utf8(bytes.to_vec())?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_pinentry_program_of_the_agent_conf_counts() {
+        let conf = "# pinentry-program /usr/bin/pinentry-tty\n\
+                    pinentry-program /usr/bin/pinentry-gnome3\n\
+                    pinentry-timeout 30\n\
+                    \tpinentry-program  /opt/homebrew/bin/pinentry-mac \n";
+        assert_eq!(
+            program_from_agent_conf(conf),
+            Some(PathBuf::from("/opt/homebrew/bin/pinentry-mac"))
+        );
+        assert_eq!(program_from_agent_conf("pinentry-timeout 30\n"), None);
+    }
+
+    #[cfg(unix)]
+    fn fake_pinentry(name: &str, getpin: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = env::temp_dir().join(format!("shamir-{}-{}", name, std::process::id()));
+        let script = format!(
+            "#!/bin/sh\n\
+             echo 'OK Pleased to meet you'\n\
+             while read -r line; do\n\
+             case \"$line\" in\n\
+             GETPIN) {} ;;\n\
+             BYE) echo 'OK closing connection'; exit 0 ;;\n\
+             *) echo '# seen' ; echo OK ;;\n\
+             esac\n\
+             done\n",
+            getpin
+        );
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o700)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn pins_are_read_from_pinentry() {
+        let program = fake_pinentry(
+            "pinentry",
+            "echo 'S PASSWORD_FROM_CACHE'; echo 'D 100%25 s%0Acret'; echo OK",
+        );
+        let pin = get_pin(
+            &program,
+            "Enter the passphrase\nof the shares",
+            "Passphrase:",
+        );
+        assert_eq!(pin.unwrap().unwrap().as_str(), "100% s\ncret");
+
+        fs::remove_file(program).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn cancelling_pinentry_gives_no_pin() {
+        let program = fake_pinentry(
+            "pinentry-cancel",
+            "echo 'ERR 83886179 Operation cancelled <Pinentry>'",
+        );
+        assert!(get_pin(&program, "Enter the PIN", "PIN:")
+            .unwrap()
+            .is_none());
+
+        fs::remove_file(program).unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::Result;
+use ff::PrimeField;
+
+use crate::error::ShamirError;
+use crate::shamir::secure_rng;
+
+/// Shamir's Secret Sharing over any prime field, where the secret is a single field element
+///
+/// Unlike `shamir::SecretData`, which shares bytes over GF(2^8), this shares field elements as a
+/// whole, so secrets that already are scalars of a curve can be shared without converting them.
+pub struct SecretData<F: PrimeField> {
+    pub coefficients: Vec<F>,
+}
+
+impl<F: PrimeField> SecretData<F> {
+    /// Creates a random polynomial with the secret as its constant term
+    pub fn with_secret(secret: F, threshold: u8) -> Result<SecretData<F>> {
+        if threshold == 0 {
+            return Err(anyhow::anyhow!("Threshold must be at least 1"));
+        }
+
+        let mut rng = secure_rng()?;
+        let mut coefficients: Vec<F> = vec![secret];
+        for _ in 1..threshold {
+            coefficients.push(F::random(&mut rng));
+        }
+
+        Ok(SecretData { coefficients })
+    }
+
+    /// Evaluates the polynomial at `id`, the share starts with `id` as its x coordinate followed by
+    /// the canonical encoding of the field element
+    pub fn get_share(&self, id: u8) -> Result<Vec<u8>> {
+        if id == 0 {
+            return Err(anyhow::anyhow!("Invalid share count"));
+        }
+
+        // Horner's method, starting at the highest coefficient
+        let x = F::from(id as u64);
+        let y = self
+            .coefficients
+            .iter()
+            .rev()
+            .fold(F::ZERO, |acc, c| acc * x + c);
+
+        let mut share = vec![id];
+        share.extend_from_slice(y.to_repr().as_ref());
+        Ok(share)
+    }
+
+    /// Interpolates the secret from shares as created by `get_share`
+    ///
+    /// Like the shares of `shamir::SecretData`, these carry no integrity protection. Too few shares
+    /// or shares of different secrets silently recover the wrong element.
+    pub fn recover_secret(shares: Vec<Vec<u8>>) -> Result<F> {
+        if shares.is_empty() {
+            return Err(ShamirError::NotEnoughShares {
+                have: 0,
+                need: None,
+            }
+            .into());
+        }
+
+        let mut points: Vec<(F, F)> = Vec::new();
+        let mut xs: Vec<u8> = Vec::new();
+        for share in shares.iter() {
+            // Index 0 is where the secret itself is, no share can be there
+            let id = share.first().copied().unwrap_or(0);
+            if id == 0 {
+                return Err(anyhow::anyhow!("Share has no valid index"));
+            }
+
+            if xs.contains(&id) {
+                return Err(anyhow::anyhow!("Multiple shares with the same first byte"));
+            }
+            xs.push(id);
+
+            let mut repr = F::Repr::default();
+            if share.len() - 1 != repr.as_ref().len() {
+                return Err(ShamirError::InconsistentShares.into());
+            }
+            repr.as_mut().copy_from_slice(&share[1..]);
+            let y = Option::<F>::from(F::from_repr(repr))
+                .ok_or_else(|| anyhow::anyhow!("Share is not an element of the field"))?;
+
+            points.push((F::from(id as u64), y));
+        }
+
+        // Lagrange interpolation at x = 0
+        let mut secret = F::ZERO;
+        for (i, (x_i, y_i)) in points.iter().enumerate() {
+            let mut numerator = F::ONE;
+            let mut denominator = F::ONE;
+            for (j, (x_j, _)) in points.iter().enumerate() {
+                if i != j {
+                    numerator *= x_j;
+                    denominator *= *x_j - x_i;
+                }
+            }
+
+            // The x coordinates are distinct and small, so the denominator is never 0
+            secret += *y_i * numerator * denominator.invert().unwrap();
+        }
+
+        Ok(secret)
+    }
+}
+
+/// Splits a BLS12-381 scalar, like the secret key of a BLS signature, into shares of 33 bytes
+pub fn to_shares_scalar(
+    secret: bls12_381::Scalar,
+    threshold: u8,
+    count: u8,
+) -> Result<Vec<Vec<u8>>> {
+    if threshold > count {
+        return Err(anyhow::anyhow!(
+            "Threshold must be between 1 and the number of shares"
+        ));
+    }
+
+    let secret_data = SecretData::with_secret(secret, threshold)?;
+    (1..=count).map(|id| secret_data.get_share(id)).collect()
+}
+
+/// Recovers a BLS12-381 scalar from shares created by `to_shares_scalar`
+pub fn from_shares_scalar(shares: Vec<Vec<u8>>) -> Result<bls12_381::Scalar> {
+    SecretData::recover_secret(shares)
+}
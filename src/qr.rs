@@ -0,0 +1,135 @@
+use anyhow::Result;
+use flate2::{read::ZlibDecoder, write::ZlibEncoder, Compression};
+#[cfg(feature = "qr")]
+use image::{ImageFormat, Luma};
+#[cfg(feature = "qr")]
+use qrcode::{EcLevel, QrCode};
+#[cfg(feature = "qr")]
+use std::io::Cursor;
+use std::io::{Read, Write};
+
+use crate::sskr::{cbor_bytes, cbor_unwrap_bytes};
+use crate::wrapper::is_share;
+
+/// How many bytes of a share go into one frame, little enough for a phone camera to read quickly
+pub const DEFAULT_FRAGMENT_LEN: usize = 200;
+
+/// Splits a share into the parts of a multi-part `ur:bytes`, one for every QR frame
+///
+/// Half again as many parts as there are fragments are created. The extra parts are fountain
+/// coded mixes of several fragments, so the share can still be put together with frames missing.
+pub fn to_parts(share: &[u8], fragment_len: usize) -> Result<Vec<String>> {
+    let message = cbor_bytes(share);
+    let mut encoder = ur::Encoder::bytes(&message, fragment_len)
+        .map_err(|e| anyhow::anyhow!("Cannot encode share as UR: {}", e))?;
+
+    let count = encoder.fragment_count() + encoder.fragment_count().div_ceil(2);
+    let mut parts: Vec<String> = Vec::new();
+    for _ in 0..count {
+        let part = encoder
+            .next_part()
+            .map_err(|e| anyhow::anyhow!("Cannot encode share as UR: {}", e))?;
+        parts.push(part);
+    }
+
+    Ok(parts)
+}
+
+/// Puts a share back together from its UR parts, which may come in any order
+pub fn from_parts(parts: &[String]) -> Result<Vec<u8>> {
+    let mut decoder = ur::Decoder::default();
+    for part in parts {
+        decoder
+            .receive(&part.trim().to_ascii_lowercase())
+            .map_err(|e| anyhow::anyhow!("Invalid UR part \"{}\": {}", part.trim(), e))?;
+        if decoder.complete() {
+            break;
+        }
+    }
+
+    if decoder.ur_type() != Some("bytes") {
+        return Err(anyhow::anyhow!("Unsupported UR type, expected \"bytes\""));
+    }
+
+    match decoder.message() {
+        Ok(Some(message)) => cbor_unwrap_bytes(&message),
+        _ => Err(anyhow::anyhow!(
+            "Not enough UR parts to put the share back together"
+        )),
+    }
+}
+
+/// Shares larger than this are compressed by `to_qr_data`, beyond it QR codes quickly run out of
+/// room
+pub const QR_COMPRESS_THRESHOLD: usize = 200;
+
+/// The first byte of a QR payload, telling how the share after it is stored
+const QR_DATA_RAW: u8 = 0;
+const QR_DATA_ZLIB: u8 = 1;
+
+/// Packs a share into as few bytes as possible, to put into a single binary mode QR code
+///
+/// Shares larger than `QR_COMPRESS_THRESHOLD` are compressed with zlib, but only kept that way if
+/// it helps, most of a share is encrypted data, which does not compress.
+pub fn to_qr_data(share: &[u8]) -> Result<Vec<u8>> {
+    if share.len() > QR_COMPRESS_THRESHOLD {
+        let mut encoder = ZlibEncoder::new(vec![QR_DATA_ZLIB], Compression::best());
+        encoder.write_all(share)?;
+        let compressed = encoder.finish()?;
+        if compressed.len() < share.len() + 1 {
+            return Ok(compressed);
+        }
+    }
+
+    let mut data = vec![QR_DATA_RAW];
+    data.extend_from_slice(share);
+    Ok(data)
+}
+
+/// Reads a share back from the payload written by `to_qr_data`
+pub fn from_qr_data(data: &[u8]) -> Result<Vec<u8>> {
+    let share = match data.split_first() {
+        Some((&QR_DATA_RAW, share)) => share.to_vec(),
+        Some((&QR_DATA_ZLIB, compressed)) => {
+            let mut share: Vec<u8> = Vec::new();
+            ZlibDecoder::new(compressed)
+                .read_to_end(&mut share)
+                .map_err(|e| anyhow::anyhow!("QR code holds a damaged compressed share: {}", e))?;
+            share
+        }
+        _ => return Err(anyhow::anyhow!("QR code does not hold a share")),
+    };
+
+    if !is_share(&share) {
+        return Err(anyhow::anyhow!("QR code does not hold a share"));
+    }
+
+    Ok(share)
+}
+
+/// Renders a UR part as a QR code PNG image
+#[cfg(feature = "qr")]
+pub fn to_png(part: &str) -> Result<Vec<u8>> {
+    // Uppercase URs fit the denser alphanumeric mode of QR codes
+    let code = QrCode::with_error_correction_level(part.to_ascii_uppercase(), EcLevel::L)?;
+    let image = code.render::<Luma<u8>>().module_dimensions(4, 4).build();
+
+    let mut png: Vec<u8> = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageFormat::Png)?;
+    Ok(png)
+}
+
+/// Reads the contents of every QR code found in an image
+#[cfg(feature = "qr")]
+pub fn read_image(image: &[u8]) -> Result<Vec<String>> {
+    let image = image::load_from_memory(image)?.to_luma8();
+    let mut prepared = rqrr::PreparedImage::prepare(image);
+
+    let mut contents: Vec<String> = Vec::new();
+    for grid in prepared.detect_grids() {
+        let (_, content) = grid.decode()?;
+        contents.push(content);
+    }
+
+    Ok(contents)
+}
@@ -1,19 +1,248 @@
 extern crate rand;
 
+use std::ops::RangeInclusive;
+
 use anyhow::Result;
-use rand::{thread_rng, RngCore};
+use rand::{rngs::OsRng, rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+
+use crate::error::ShamirError;
+
+/// Seeds a random number generator from the operating system, returning an error instead of
+/// panicking when the system has no entropy to give
+pub(crate) fn secure_rng() -> Result<StdRng> {
+    StdRng::from_rng(OsRng).map_err(|_| ShamirError::RngUnavailable.into())
+}
+
+/// What a split with `verify_threshold_security` parameters does and does not reveal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThresholdSecurityInfo {
+    pub secret_bits: u64,
+    pub shares_needed: u8,
+    pub shares_available: u8,
+    /// Whether the shares can recover the secret, while fewer than `shares_needed` of them reveal
+    /// nothing at all about it
+    pub information_theoretic: bool,
+    /// Bits of the secret a single share gives away, all of them with a threshold of 1
+    pub min_bits_leaked_per_share: u64,
+}
+
+/// Describes the security of splitting a secret of `secret_len_bytes` with `split_secret`
+///
+/// Any `threshold - 1` shares are points on a random polynomial of degree `threshold - 1`, which
+/// fit every possible secret equally well, so they reveal nothing about it whatever the computing
+/// power of an attacker (A. Shamir, "How to Share a Secret", Communications of the ACM 22(11),
+/// 1979). With a threshold of 1 the polynomial is a constant, and every share is the secret itself.
+/// The shares of `wrapper::to_shares` only split the key this way, the encrypted data in them is
+/// as strong as AES-256.
+pub fn verify_threshold_security(
+    threshold: u8,
+    count: u8,
+    secret_len_bytes: usize,
+) -> ThresholdSecurityInfo {
+    let secret_bits = (secret_len_bytes as u64).saturating_mul(8);
+    let information_theoretic = threshold >= 2 && threshold <= count;
+
+    ThresholdSecurityInfo {
+        secret_bits,
+        shares_needed: threshold,
+        shares_available: count,
+        information_theoretic,
+        min_bits_leaked_per_share: if threshold <= 1 { secret_bits } else { 0 },
+    }
+}
+
+/// The number of shares a secret can be split into, one for every non-zero element of GF(256), as
+/// index 0 would be the secret itself
+pub const MAX_SHARES: u8 = u8::MAX;
+
+/// Checks a number of shares given as any integer, like from a binding or a sum of several splits,
+/// failing with `ShamirError::TooManyShares` when it is above `MAX_SHARES`
+pub fn checked_share_count(count: usize) -> Result<u8> {
+    if count == 0 {
+        return Err(anyhow::anyhow!("Number of shares must be at least 1"));
+    }
+
+    u8::try_from(count).map_err(|_| {
+        anyhow::Error::new(ShamirError::TooManyShares { max: MAX_SHARES })
+            .context(format!("{} shares were asked for", count))
+    })
+}
+
+/// Splits a secret into `count` shares, any `threshold` of which recover it with
+/// `recover_secret`
+pub fn split_secret(secret: &[u8], threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    split_secret_with_rng(secret, threshold, count, &mut secure_rng()?)
+}
+
+/// Like `split_secret`, but takes the coefficients from the given random number generator, which
+/// must be cryptographically secure for the shares to hide the secret
+pub fn split_secret_with_rng(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    rng: &mut (impl RngCore + CryptoRng),
+) -> Result<Vec<Vec<u8>>> {
+    SecretData::with_secret_and_count_with_rng(secret.to_vec(), threshold, count, rng)?
+        .into_iter()
+        .map(|share| share.map(|(_, share)| share))
+        .collect()
+}
+
+/// Interpolates the secret from shares as created by `split_secret` or `SecretData::get_share`
+///
+/// Every share carries its own x coordinate in its first byte, so the shares can be given in any
+/// order, and any indices can be used as long as they are distinct and not 0
+pub fn recover_secret(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let mut xs: Vec<u8> = vec![];
+
+    for share in shares.iter() {
+        // Index 0 is where the secret itself is, no share can be there
+        if share.first().copied().unwrap_or(0) == 0 {
+            return Err(anyhow::anyhow!("Share has no valid index"));
+        }
+
+        if xs.contains(&share[0]) {
+            return Err(anyhow::anyhow!("Multiple shares with the same first byte"));
+        }
+
+        if share.len() != shares[0].len() {
+            return Err(anyhow::anyhow!("Shares have different lengths"));
+        }
+
+        xs.push(share[0].to_owned());
+    }
+    let mut mysecretdata: Vec<u8> = vec![];
+    let rounds = shares[0].len() - 1;
+
+    for byte_to_use in 0..rounds {
+        let mut fxs: Vec<u8> = vec![];
+        for share in shares.clone() {
+            fxs.push(share[1..][byte_to_use]);
+        }
+
+        let resulting_poly = SecretData::full_lagrange(&xs, &fxs)?;
+        mysecretdata.push(resulting_poly[0]);
+    }
+
+    Ok(mysecretdata)
+}
+
+/// Like `recover_secret`, but borrows the shares and writes the secret into `out` instead of
+/// allocating anything, returning the length of the secret, one byte less than a share
+///
+/// Fails with `ShamirError::BufferTooSmall` when `out` is shorter than that, leaving it as it is.
+pub fn recover_into(shares: &[&[u8]], out: &mut [u8]) -> Result<usize> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    for (i, share) in shares.iter().enumerate() {
+        // Index 0 is where the secret itself is, no share can be there
+        if share.first().copied().unwrap_or(0) == 0 {
+            return Err(anyhow::anyhow!("Share has no valid index"));
+        }
+
+        if shares[..i].iter().any(|other| other[0] == share[0]) {
+            return Err(anyhow::anyhow!("Multiple shares with the same first byte"));
+        }
+
+        if share.len() != shares[0].len() {
+            return Err(anyhow::anyhow!("Shares have different lengths"));
+        }
+    }
+
+    let needed = shares[0].len() - 1;
+    if out.len() < needed {
+        return Err(ShamirError::BufferTooSmall {
+            needed,
+            available: out.len(),
+        }
+        .into());
+    }
+
+    // The Lagrange basis polynomials at 0, the same for every byte, there are at most 255 shares
+    let mut weights = [0u8; 256];
+    for (i, share) in shares.iter().enumerate() {
+        let mut weight: u8 = 1;
+        for (j, other) in shares.iter().enumerate() {
+            if i != j {
+                let term = SecretData::gf256_checked_div(other[0], other[0] ^ share[0])
+                    .ok_or_else(|| anyhow::anyhow!("Invalid share"))?;
+                weight = SecretData::gf256_mul(weight, term);
+            }
+        }
+        weights[i] = weight;
+    }
+
+    for (position, byte) in out[..needed].iter_mut().enumerate() {
+        *byte = shares
+            .iter()
+            .zip(weights.iter())
+            .fold(0, |secret, (share, weight)| {
+                secret ^ SecretData::gf256_mul(share[1 + position], *weight)
+            });
+    }
+    Ok(needed)
+}
 
 pub struct SecretData {
     pub secret_data: Option<Vec<u8>>,
     pub coefficients: Vec<Vec<u8>>,
+    /// The highest index `get_share` gives a share for, iterating over the secret yields the
+    /// shares from index 1 up to it
+    pub max_shares: u8,
+    /// Random key the salts of `get_share_with_commitment` are derived from, so they can't be
+    /// worked out from the secret
+    commitment_key: [u8; 32],
 }
 
 impl SecretData {
-    pub fn with_secret(secret: Vec<u8>, threshold: u8) -> SecretData {
+    /// Creates a random polynomial for every byte of the secret
+    ///
+    /// There is no limit on the size of the secret besides memory, but all coefficients are kept in
+    /// memory at once, which takes `threshold` bytes for every byte of the secret.
+    pub fn with_secret(secret: Vec<u8>, threshold: u8) -> Result<SecretData> {
+        SecretData::generate(secret, threshold, &mut secure_rng()?)
+    }
+
+    /// Starts building the polynomials of a secret with the given threshold, which unlike
+    /// `with_secret` can take its own random number generator
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new<'a>(threshold: u8) -> SecretDataBuilder<'a> {
+        SecretDataBuilder {
+            threshold,
+            secret: None,
+            count: None,
+            rng: None,
+        }
+    }
+
+    fn generate(secret: Vec<u8>, threshold: u8, rng: &mut dyn RngCore) -> Result<SecretData> {
+        if secret.is_empty() {
+            return Err(ShamirError::EmptySecret.into());
+        }
+
+        if threshold == 0 {
+            return Err(anyhow::anyhow!("Threshold must be at least 1"));
+        }
+
         let mut coefficients: Vec<Vec<u8>> = vec![];
-        let mut rng = thread_rng();
         let mut rand_container = vec![0u8; (threshold - 1) as usize];
-        for c in secret.to_owned() {
+        for c in secret.iter().copied() {
             rng.fill_bytes(&mut rand_container);
             let mut coef: Vec<u8> = vec![c];
             for r in rand_container.iter() {
@@ -22,16 +251,57 @@ impl SecretData {
             coefficients.push(coef);
         }
 
-        SecretData {
+        let mut commitment_key = [0u8; 32];
+        rng.fill_bytes(&mut commitment_key);
+
+        Ok(SecretData {
             secret_data: Some(secret),
             coefficients,
+            max_shares: MAX_SHARES,
+            commitment_key,
+        })
+    }
+
+    /// Like `with_secret`, but only the `count` shares of the intended split can be taken, instead
+    /// of every share the field allows
+    pub fn with_secret_and_count(secret: Vec<u8>, threshold: u8, count: u8) -> Result<SecretData> {
+        SecretData::with_secret_and_count_with_rng(secret, threshold, count, &mut secure_rng()?)
+    }
+
+    /// Like `with_secret_and_count`, but takes the coefficients from the given random number
+    /// generator, which must be cryptographically secure for the shares to hide the secret
+    pub fn with_secret_and_count_with_rng(
+        secret: Vec<u8>,
+        threshold: u8,
+        count: u8,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<SecretData> {
+        if threshold > count {
+            return Err(anyhow::anyhow!(
+                "Threshold must not be greater than the number of shares"
+            ));
         }
+
+        let mut secret_data = SecretData::generate(secret, threshold, rng)?;
+        secret_data.max_shares = count;
+        Ok(secret_data)
     }
 
+    /// Evaluates the polynomials at `id`, the share starts with `id` as its x coordinate followed by
+    /// one byte for every byte of the secret
+    ///
+    /// Fails with `ShamirError::ShareIndexOutOfRange` when `id` is above `max_shares`.
     pub fn get_share(&self, id: u8) -> Result<Vec<u8>> {
         if id == 0 {
             return Err(anyhow::anyhow!("Invalid share count"));
         }
+        if id > self.max_shares {
+            return Err(ShamirError::ShareIndexOutOfRange {
+                index: id,
+                max: self.max_shares,
+            }
+            .into());
+        }
         let mut share_bytes: Vec<u8> = vec![];
         let coefficients = self.coefficients.clone();
         for coefficient in coefficients {
@@ -43,10 +313,95 @@ impl SecretData {
         Ok(share_bytes)
     }
 
+    /// Like `get_share`, but writes the share into `out` instead of allocating it, returning its
+    /// length, which is one byte more than the secret
+    ///
+    /// Fails with `ShamirError::BufferTooSmall` when `out` is shorter than that, leaving it as it is.
+    pub fn get_share_into(&self, id: u8, out: &mut [u8]) -> Result<usize> {
+        if id == 0 {
+            return Err(anyhow::anyhow!("Invalid share count"));
+        }
+        if id > self.max_shares {
+            return Err(ShamirError::ShareIndexOutOfRange {
+                index: id,
+                max: self.max_shares,
+            }
+            .into());
+        }
+
+        let needed = 1 + self.coefficients.len();
+        if out.len() < needed {
+            return Err(ShamirError::BufferTooSmall {
+                needed,
+                available: out.len(),
+            }
+            .into());
+        }
+
+        out[0] = id;
+        for (byte, coefficients) in out[1..needed].iter_mut().zip(self.coefficients.iter()) {
+            *byte = SecretData::evaluate_polynomial(id, coefficients);
+        }
+        Ok(needed)
+    }
+
+    /// Like `get_share`, but also returns the commitment to the share, to be handed to its holder
+    /// along with the share
+    pub fn get_share_with_commitment(&self, id: u8) -> Result<(Vec<u8>, ShareCommitment)> {
+        let share = self.get_share(id)?;
+
+        let mut hasher = Sha3_256::new();
+        hasher.update(b"share commitment salt");
+        hasher.update(self.commitment_key);
+        hasher.update([id]);
+        let salt: [u8; 32] = hasher.finalize().into();
+
+        let commitment = ShareCommitment {
+            salt,
+            commitment: ShareCommitment::commit(&salt, &share),
+        };
+        Ok((share, commitment))
+    }
+
+    /// The commitments of the shares with index 1 up to `count`, to be published by the dealer
+    pub fn share_commitments(&self, count: u8) -> Result<Vec<[u8; 32]>> {
+        let mut commitments: Vec<[u8; 32]> = Vec::new();
+        for id in 1..=count {
+            let (_, commitment) = self.get_share_with_commitment(id)?;
+            commitments.push(commitment.commitment);
+        }
+        Ok(commitments)
+    }
+
+    /// Interpolates the secret from shares as created by `get_share`, same as the free
+    /// `recover_secret`
     pub fn recover_secret(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
-        let mut xs: Vec<u8> = vec![];
+        recover_secret(shares)
+    }
 
+    /// Like `recover_secret`, but corrects up to `(n - threshold) / 2` of the `n` shares having
+    /// corrupted bytes, using the Berlekamp-Welch algorithm on every byte
+    ///
+    /// Returns the secret and the indices of the shares that had to be corrected. Unlike
+    /// `recover_secret` the threshold must be known, as it sets how many errors can be corrected.
+    pub fn recover_secret_error_correcting(
+        shares: Vec<Vec<u8>>,
+        threshold: u8,
+    ) -> Result<(Vec<u8>, Vec<u8>)> {
+        if threshold == 0 || shares.len() < threshold as usize {
+            return Err(ShamirError::NotEnoughShares {
+                have: shares.len(),
+                need: Some((threshold as usize).max(1)),
+            }
+            .into());
+        }
+
+        let mut xs: Vec<u8> = vec![];
         for share in shares.iter() {
+            if share.first().copied().unwrap_or(0) == 0 {
+                return Err(anyhow::anyhow!("Share has no valid index"));
+            }
+
             if xs.contains(&share[0]) {
                 return Err(anyhow::anyhow!("Multiple shares with the same first byte"));
             }
@@ -55,38 +410,143 @@ impl SecretData {
                 return Err(anyhow::anyhow!("Shares have different lengths"));
             }
 
-            xs.push(share[0].to_owned());
+            xs.push(share[0]);
         }
-        let mut mysecretdata: Vec<u8> = vec![];
-        let rounds = shares[0].len() - 1;
 
-        for byte_to_use in 0..rounds {
-            let mut fxs: Vec<u8> = vec![];
-            for share in shares.clone() {
-                fxs.push(share[1..][byte_to_use]);
+        let errors = (shares.len() - threshold as usize) / 2;
+        let mut secret: Vec<u8> = vec![];
+        let mut corrected: Vec<u8> = vec![];
+        for byte in 1..shares[0].len() {
+            let ys = shares.iter().map(|s| s[byte]).collect::<Vec<u8>>();
+            let polynomial = SecretData::berlekamp_welch(&xs, &ys, threshold as usize, errors)
+                .ok_or_else(|| anyhow::anyhow!("Too many shares are corrupted to correct"))?;
+            secret.push(polynomial[0]);
+
+            for (x, y) in xs.iter().zip(ys.iter()) {
+                let expected = SecretData::accumulate_share_bytes(*x, polynomial.clone())?;
+                if expected != *y && !corrected.contains(x) {
+                    corrected.push(*x);
+                }
+            }
+        }
+
+        corrected.sort_unstable();
+        Ok((secret, corrected))
+    }
+
+    /// Finds the polynomial with fewer than `k` coefficients that goes through all but at most
+    /// `e` of the points
+    ///
+    /// The points off the polynomial are the roots of an error locator `E` of degree `e`, so with
+    /// `Q = P * E`, every point satisfies `Q(x) = y * E(x)`, which is linear in the coefficients of
+    /// `Q` and `E`. `P` is then `Q / E`.
+    fn berlekamp_welch(xs: &[u8], ys: &[u8], k: usize, e: usize) -> Option<Vec<u8>> {
+        // The unknowns are the k + e coefficients of Q, then the e lower coefficients of E, whose
+        // highest coefficient is 1
+        let columns = k + 2 * e;
+        let mut rows: Vec<Vec<u8>> = vec![];
+        for (x, y) in xs.iter().zip(ys.iter()) {
+            let mut row = vec![0u8; columns + 1];
+            let mut power: u8 = 1;
+            for j in 0..k + e {
+                if j == e {
+                    row[columns] = SecretData::gf256_mul(*y, power);
+                }
+                if j < e {
+                    row[k + e + j] = SecretData::gf256_mul(*y, power);
+                }
+                row[j] = power;
+                power = SecretData::gf256_mul(power, *x);
+            }
+            rows.push(row);
+        }
+
+        let solution = SecretData::solve_linear(rows, columns)?;
+        let mut locator = solution[k + e..].to_vec();
+        locator.push(1);
+
+        // Q has to be a multiple of E, otherwise there were more errors than e
+        let mut remainder = solution[..k + e].to_vec();
+        let mut quotient = vec![0u8; k];
+        for i in (0..k).rev() {
+            let c = remainder[i + e];
+            quotient[i] = c;
+            for (j, l) in locator.iter().enumerate() {
+                remainder[i + j] ^= SecretData::gf256_mul(c, *l);
             }
+        }
+
+        if remainder.iter().any(|r| *r != 0) {
+            return None;
+        }
+        Some(quotient)
+    }
+
+    /// Solves a system of linear equations with Gauss-Jordan elimination, every row holds the
+    /// coefficients followed by the right hand side, free variables are set to 0
+    fn solve_linear(mut rows: Vec<Vec<u8>>, columns: usize) -> Option<Vec<u8>> {
+        let mut pivots: Vec<usize> = vec![];
+        for column in 0..columns {
+            let rank = pivots.len();
+            let pivot = match (rank..rows.len()).find(|r| rows[*r][column] != 0) {
+                Some(pivot) => pivot,
+                None => continue,
+            };
+            rows.swap(rank, pivot);
+
+            let inverse = SecretData::gf256_checked_div(1, rows[rank][column])?;
+            for value in rows[rank].iter_mut() {
+                *value = SecretData::gf256_mul(*value, inverse);
+            }
+
+            let pivot_row = rows[rank].clone();
+            for (r, row) in rows.iter_mut().enumerate() {
+                let factor = row[column];
+                if r != rank && factor != 0 {
+                    for (value, p) in row.iter_mut().zip(pivot_row.iter()) {
+                        *value ^= SecretData::gf256_mul(factor, *p);
+                    }
+                }
+            }
+            pivots.push(column);
+        }
 
-            let resulting_poly = SecretData::full_lagrange(&xs, &fxs)?;
-            mysecretdata.push(resulting_poly[0]);
+        // A row without coefficients left must not need a right hand side either
+        if rows[pivots.len()..].iter().any(|row| row[columns] != 0) {
+            return None;
         }
 
-        Ok(mysecretdata)
+        let mut solution = vec![0u8; columns];
+        for (row, column) in pivots.iter().enumerate() {
+            solution[*column] = rows[row][columns];
+        }
+        Some(solution)
     }
 
     fn accumulate_share_bytes(id: u8, coefficient_bytes: Vec<u8>) -> Result<u8> {
         if id == 0 {
             return Err(anyhow::anyhow!("Invalid share count"));
         }
+
+        Ok(SecretData::evaluate_polynomial(id, &coefficient_bytes))
+    }
+
+    /// Evaluates `sum(coefficients[i] * x^i)` in GF(256), the polynomial `get_share` evaluates for
+    /// every byte of the secret, with the secret byte as `coefficients[0]`
+    ///
+    /// For checking shares against a reference implementation, the field is the one of AES, reduced
+    /// by `x^8 + x^4 + x^3 + x + 1`. At `x` 0 this is the secret byte itself.
+    pub fn evaluate_polynomial(x: u8, coefficients: &[u8]) -> u8 {
         let mut accumulator: u8 = 0;
 
         let mut x_i: u8 = 1;
 
-        for c in coefficient_bytes {
+        for c in coefficients.iter().copied() {
             accumulator = SecretData::gf256_add(accumulator, SecretData::gf256_mul(c, x_i));
-            x_i = SecretData::gf256_mul(x_i, id);
+            x_i = SecretData::gf256_mul(x_i, x);
         }
 
-        Ok(accumulator)
+        accumulator
     }
 
     fn full_lagrange(xs: &[u8], fxs: &[u8]) -> Result<Vec<u8>> {
@@ -197,6 +657,38 @@ impl SecretData {
     }
 }
 
+/// A salted hash commitment to a share, which lets its holder check the share against the
+/// commitments the dealer published
+///
+/// It is not a proof that the share was evaluated correctly: GF(2^8) has no group structure to
+/// build Feldman style commitments on, so a dealer that commits to wrong shares is not caught, see
+/// `commitments`. The salt is random, so the published commitments reveal nothing about the
+/// secret, also with a threshold of 1 where every share holds the secret itself. The salt must be
+/// kept along with the share, and not be published.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShareCommitment {
+    /// The random salt of the commitment, only for the holder of the share
+    pub salt: [u8; 32],
+    /// Hash of the salt and the share, to be published
+    pub commitment: [u8; 32],
+}
+
+impl ShareCommitment {
+    /// Checks that the share matches this commitment, and that it is one of the published
+    /// commitments
+    pub fn verify(&self, share: &[u8], commitments: &[[u8; 32]]) -> bool {
+        ShareCommitment::commit(&self.salt, share) == self.commitment
+            && commitments.contains(&self.commitment)
+    }
+
+    fn commit(salt: &[u8; 32], share: &[u8]) -> [u8; 32] {
+        let mut hasher = Sha3_256::new();
+        hasher.update(salt);
+        hasher.update(share);
+        hasher.finalize().into()
+    }
+}
+
 static GF256_EXP: [u8; 256] = [
     0x01, 0x03, 0x05, 0x0f, 0x11, 0x33, 0x55, 0xff, 0x1a, 0x2e, 0x72, 0x96, 0xa1, 0xf8, 0x13, 0x35,
     0x5f, 0xe1, 0x38, 0x48, 0xd8, 0x73, 0x95, 0xa4, 0xf7, 0x02, 0x06, 0x0a, 0x1e, 0x22, 0x66, 0xaa,
@@ -234,3 +726,308 @@ static GF256_LOG: [u8; 256] = [
     0x44, 0x11, 0x92, 0xd9, 0x23, 0x20, 0x2e, 0x89, 0xb4, 0x7c, 0xb8, 0x26, 0x77, 0x99, 0xe3, 0xa5,
     0x67, 0x4a, 0xed, 0xde, 0xc5, 0x31, 0xfe, 0x18, 0x0d, 0x63, 0x8c, 0x80, 0xc0, 0xf7, 0x70, 0x07,
 ];
+
+/// Builds a `SecretData` from `SecretData::new`, nothing is created until `build` is called
+///
+/// The secret is required, the number of shares defaults to every share the field allows and the
+/// random number generator to one seeded from the operating system.
+pub struct SecretDataBuilder<'a> {
+    threshold: u8,
+    secret: Option<Vec<u8>>,
+    count: Option<u8>,
+    rng: Option<&'a mut dyn RngCore>,
+}
+
+impl<'a> SecretDataBuilder<'a> {
+    pub fn secret(mut self, secret: &[u8]) -> SecretDataBuilder<'a> {
+        self.secret = Some(secret.to_vec());
+        self
+    }
+
+    /// Only lets the `count` shares of the intended split be taken, like `with_secret_and_count`
+    pub fn count(mut self, count: u8) -> SecretDataBuilder<'a> {
+        self.count = Some(count);
+        self
+    }
+
+    /// The random number generator for the coefficients, cryptographically secure like for
+    /// `split_secret_with_rng`, as the shares only hide the secret when the coefficients can't be
+    /// guessed
+    pub fn rng<R: RngCore + CryptoRng>(mut self, rng: &'a mut R) -> SecretDataBuilder<'a> {
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Checks the settings and creates the polynomials, failing with every setting that is wrong
+    pub fn build(self) -> Result<SecretData> {
+        let mut problems: Vec<String> = Vec::new();
+        if self.threshold == 0 {
+            problems.push("the threshold must be at least 1".to_string());
+        }
+        match self.secret.as_ref() {
+            None => problems.push("no secret is given".to_string()),
+            Some(secret) if secret.is_empty() => problems.push("the secret is empty".to_string()),
+            Some(_) => {}
+        }
+        if self.count.is_some_and(|count| self.threshold > count) {
+            problems.push("the threshold is greater than the number of shares".to_string());
+        }
+        if !problems.is_empty() {
+            let problems = problems.join(", ");
+            return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+                .context(format!("Cannot build the secret data: {}", problems)));
+        }
+
+        let secret = self.secret.unwrap_or_default();
+        let mut secret_data = match self.rng {
+            Some(rng) => SecretData::generate(secret, self.threshold, rng)?,
+            None => SecretData::generate(secret, self.threshold, &mut secure_rng()?)?,
+        };
+        if let Some(count) = self.count {
+            secret_data.max_shares = count;
+        }
+        Ok(secret_data)
+    }
+}
+
+/// The shares of a secret, from index 1 up to its `max_shares`
+pub struct Shares {
+    secret_data: SecretData,
+    ids: RangeInclusive<u8>,
+}
+
+impl Iterator for Shares {
+    type Item = Result<(u8, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let id = self.ids.next()?;
+        Some(self.secret_data.get_share(id).map(|share| (id, share)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.ids.size_hint()
+    }
+}
+
+/// Yields the shares from index 1 up to `max_shares`
+///
+/// That is the count of `with_secret_and_count`, or of the builder when it was given one. Without
+/// a count, as after `with_secret`, `max_shares` is `MAX_SHARES`, so iterating yields all 255
+/// shares, take as many as are needed instead of collecting them.
+impl IntoIterator for SecretData {
+    type Item = Result<(u8, Vec<u8>)>;
+    type IntoIter = Shares;
+
+    fn into_iter(self) -> Shares {
+        let ids = 1..=self.max_shares;
+        Shares {
+            secret_data: self,
+            ids,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_secret_with_rng_is_reproducible() {
+        let split = |seed: u64| {
+            split_secret_with_rng(b"secret", 2, 3, &mut StdRng::seed_from_u64(seed)).unwrap()
+        };
+        let shares = split(7);
+        assert_eq!(shares.len(), 3);
+        assert_eq!(shares, split(7));
+        assert_ne!(shares, split(8));
+        assert_eq!(recover_secret(shares[1..].to_vec()).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn with_secret_and_count_limits_the_shares() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let secret_data =
+            SecretData::with_secret_and_count_with_rng(b"secret".to_vec(), 2, 5, &mut rng).unwrap();
+        assert!(secret_data.get_share(5).is_ok());
+        assert_eq!(
+            secret_data
+                .get_share(6)
+                .err()
+                .unwrap()
+                .downcast_ref::<ShamirError>(),
+            Some(&ShamirError::ShareIndexOutOfRange { index: 6, max: 5 })
+        );
+
+        let mut rng = StdRng::seed_from_u64(7);
+        let again =
+            SecretData::with_secret_and_count_with_rng(b"secret".to_vec(), 2, 5, &mut rng).unwrap();
+        assert_eq!(
+            again.get_share(1).unwrap(),
+            secret_data.get_share(1).unwrap()
+        );
+    }
+
+    #[test]
+    fn share_commitments_verify_only_their_share() {
+        let secret_data = SecretData::with_secret_and_count(b"secret".to_vec(), 1, 3).unwrap();
+        let commitments = secret_data.share_commitments(3).unwrap();
+
+        let (share, commitment) = secret_data.get_share_with_commitment(2).unwrap();
+        assert_eq!(commitments[1], commitment.commitment);
+        assert!(commitment.verify(&share, &commitments));
+
+        let mut changed = share.clone();
+        changed[1] ^= 1;
+        assert!(!commitment.verify(&changed, &commitments));
+        assert!(!commitment.verify(&share, &commitments[..1]));
+
+        // With a threshold of 1 the share holds the secret, the commitment still hides it
+        let mut guess = Sha3_256::new();
+        guess.update(&share);
+        assert!(!commitments.contains(&guess.finalize().into()));
+    }
+
+    #[test]
+    fn builder_builds_recoverable_secret_data() {
+        let secret_data = SecretData::new(2)
+            .secret(b"secret")
+            .count(3)
+            .build()
+            .unwrap();
+        let shares = vec![
+            secret_data.get_share(1).unwrap(),
+            secret_data.get_share(3).unwrap(),
+        ];
+        assert_eq!(recover_secret(shares).unwrap(), b"secret");
+        assert!(secret_data.get_share(4).is_err());
+    }
+
+    #[test]
+    fn builder_takes_its_own_rng() {
+        let share = |seed: u64| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let secret_data = SecretData::new(3)
+                .secret(b"secret")
+                .rng(&mut rng)
+                .build()
+                .unwrap();
+            secret_data.get_share(1).unwrap()
+        };
+        assert_eq!(share(7), share(7));
+        assert_ne!(share(7), share(8));
+    }
+
+    #[test]
+    fn builder_reports_every_problem() {
+        let error = SecretData::new(0).build().err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::UnsupportedParameters)
+        );
+        let message = error.to_string();
+        assert!(message.contains("the threshold must be at least 1"));
+        assert!(message.contains("no secret is given"));
+
+        let error = SecretData::new(3)
+            .secret(b"")
+            .count(2)
+            .build()
+            .err()
+            .unwrap();
+        let message = error.to_string();
+        assert!(message.contains("the secret is empty"));
+        assert!(message.contains("the threshold is greater than the number of shares"));
+    }
+
+    #[test]
+    fn empty_secrets_are_rejected() {
+        let error = SecretData::with_secret(vec![], 2).err().unwrap();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::EmptySecret)
+        );
+    }
+
+    #[test]
+    fn evaluate_polynomial_matches_the_aes_field() {
+        let vectors: [(u8, &[u8], u8); 7] = [
+            // Products from FIPS 197, section 4.2
+            (0x83, &[0x00, 0x57], 0xc1),
+            (0x13, &[0x00, 0x57], 0xfe),
+            // 0x53 and 0xca are each other's inverse
+            (0xca, &[0x01, 0x53], 0x00),
+            (0x57, &[0x00, 0x00, 0x01], 0xa5),
+            (0x02, &[0x01, 0x01, 0x01], 0x07),
+            (0x00, &[0x2a, 0x57, 0x83], 0x2a),
+            (0x05, &[], 0x00),
+        ];
+        for (x, coefficients, expected) in vectors {
+            assert_eq!(SecretData::evaluate_polynomial(x, coefficients), expected);
+        }
+    }
+
+    #[test]
+    fn every_index_of_the_field_can_be_a_share() {
+        let count = checked_share_count(255).unwrap();
+        assert_eq!(count, MAX_SHARES);
+        let shares = split_secret(b"secret", 3, count).unwrap();
+        assert_eq!(shares.len(), 255);
+        assert_eq!(shares[254][0], 255);
+        let last = shares[252..].to_vec();
+        assert_eq!(recover_secret(last).unwrap(), b"secret");
+
+        let error = checked_share_count(256).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::TooManyShares { max: 255 })
+        );
+        assert!(checked_share_count(0).is_err());
+    }
+
+    #[test]
+    fn shares_round_trip_through_stack_buffers() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let secret_data =
+            SecretData::with_secret_and_count_with_rng(b"secret".to_vec(), 2, 3, &mut rng).unwrap();
+
+        let mut first = [0u8; 16];
+        let mut third = [0u8; 16];
+        let length = secret_data.get_share_into(1, &mut first).unwrap();
+        assert_eq!(length, 7);
+        assert_eq!(secret_data.get_share_into(3, &mut third).unwrap(), length);
+        assert_eq!(first[..length], secret_data.get_share(1).unwrap()[..]);
+
+        let mut out = [0u8; 16];
+        let shares: [&[u8]; 2] = [&first[..length], &third[..length]];
+        let written = recover_into(&shares, &mut out).unwrap();
+        assert_eq!(&out[..written], b"secret");
+    }
+
+    #[test]
+    fn too_small_buffers_are_left_as_they_are() {
+        let secret_data = SecretData::with_secret_and_count(b"secret".to_vec(), 2, 3).unwrap();
+        let mut short = [0xaa; 6];
+        let error = secret_data.get_share_into(1, &mut short).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::BufferTooSmall {
+                needed: 7,
+                available: 6
+            })
+        );
+        assert_eq!(short, [0xaa; 6]);
+
+        let first = secret_data.get_share(1).unwrap();
+        let second = secret_data.get_share(2).unwrap();
+        let mut out = [0xaa; 5];
+        let error = recover_into(&[&first, &second], &mut out).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::BufferTooSmall {
+                needed: 6,
+                available: 5
+            })
+        );
+        assert_eq!(out, [0xaa; 5]);
+    }
+}
@@ -0,0 +1,212 @@
+use ::sskr::{GroupSpec, Secret, Spec};
+use anyhow::Result;
+use ur::bytewords::{self, Style};
+
+use crate::encoding;
+
+pub use ::sskr::{MAX_SECRET_LEN, MIN_SECRET_LEN};
+
+/// The UR type used by Gordian Seed Tool and friends for a single SSKR share
+pub const UR_TYPE: &str = "crypto-sskr";
+
+/// CBOR tag 309, registered for `crypto-sskr`
+const CBOR_TAG: [u8; 3] = [0xd9, 0x01, 0x35];
+
+/// The UR type current Gordian tools, like `seedtool`, write SSKR shares as
+const CURRENT_UR_TYPE: &str = "sskr";
+
+/// CBOR tag 40309, registered for `sskr`
+const CURRENT_CBOR_TAG: [u8; 3] = [0xd9, 0x9d, 0x75];
+
+/// Splits a secret into SSKR shares, grouped by the given group specifications
+///
+/// The secret must be between 16 and 32 bytes long and of even length, as required by SSKR
+pub fn to_shares(
+    input: &[u8],
+    group_threshold: usize,
+    groups: &[(usize, usize)],
+) -> Result<Vec<Vec<Vec<u8>>>> {
+    let secret = Secret::new(input)?;
+
+    let mut group_specs: Vec<GroupSpec> = Vec::new();
+    for (threshold, count) in groups.iter() {
+        group_specs.push(GroupSpec::new(*threshold, *count)?);
+    }
+
+    let spec = Spec::new(group_threshold, group_specs)?;
+    Ok(::sskr::sskr_generate(&spec, &secret)?)
+}
+
+/// Recovers a secret from raw SSKR shares
+pub fn from_shares(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let secret = ::sskr::sskr_combine(&input)?;
+    Ok(secret.data().to_vec())
+}
+
+/// Encodes a raw SSKR share as ByteWords, the way Gordian Seed Tool displays them
+pub fn to_bytewords(share: &[u8]) -> String {
+    let mut tagged = CBOR_TAG.to_vec();
+    tagged.append(&mut cbor_bytes(share));
+    bytewords::encode(&tagged, Style::Standard)
+}
+
+/// Encodes a raw SSKR share as a `ur:crypto-sskr/...` URI
+pub fn to_ur(share: &[u8]) -> String {
+    ur::ur::encode(&cbor_bytes(share), &ur::Type::Custom(UR_TYPE))
+}
+
+/// Parses a single share in either ByteWords or UR form back into a raw SSKR share
+///
+/// Both the `crypto-sskr` shares written here and the `sskr` shares of current Gordian tools are
+/// read, with ByteWords either as whole words or as the two letter minimal form.
+pub fn parse_share(text: &str) -> Result<Vec<u8>> {
+    let text = encoding::strip_share_text(text);
+    let lower = text.to_ascii_lowercase();
+
+    if [UR_TYPE, CURRENT_UR_TYPE]
+        .iter()
+        .any(|kind| lower.starts_with(&format!("ur:{}/", kind)))
+    {
+        let (kind, payload) = ur::ur::decode(&encoding::remove_noise(text))
+            .map_err(|e| anyhow::anyhow!("Invalid SSKR UR: {}", e))?;
+        if kind != ur::ur::Kind::SinglePart {
+            return Err(anyhow::anyhow!("Multi-part SSKR URs are not supported"));
+        }
+        return cbor_unwrap_bytes(&payload);
+    }
+
+    if lower.starts_with("ur:") {
+        return Err(anyhow::anyhow!(
+            "Unsupported UR type, expected \"{}\" or \"{}\"",
+            UR_TYPE,
+            CURRENT_UR_TYPE
+        ));
+    }
+
+    let words = encoding::words(&lower);
+    let decoded = match words.as_slice() {
+        [minimal] => bytewords::decode(minimal, Style::Minimal),
+        words => bytewords::decode(&words.join(" "), Style::Standard),
+    }
+    .map_err(|e| anyhow::anyhow!("Invalid SSKR ByteWords: {}", e))?;
+
+    match [CBOR_TAG, CURRENT_CBOR_TAG]
+        .iter()
+        .find_map(|tag| decoded.strip_prefix(tag))
+    {
+        Some(rest) => cbor_unwrap_bytes(rest),
+        None => Err(anyhow::anyhow!("ByteWords do not contain an SSKR share")),
+    }
+}
+
+/// Wraps the data in a CBOR byte string, as the payload of a UR has to be CBOR
+pub(crate) fn cbor_bytes(data: &[u8]) -> Vec<u8> {
+    let mut encoded: Vec<u8> = match data.len() {
+        len if len < 24 => vec![0x40 | len as u8],
+        len if len < 256 => vec![0x58, len as u8],
+        len if len < 65536 => vec![0x59, (len >> 8) as u8, len as u8],
+        len => {
+            let mut header = vec![0x5a];
+            header.extend_from_slice(&(len as u32).to_be_bytes());
+            header
+        }
+    };
+    encoded.extend_from_slice(data);
+    encoded
+}
+
+/// Reads the data back out of a CBOR byte string
+pub(crate) fn cbor_unwrap_bytes(data: &[u8]) -> Result<Vec<u8>> {
+    let (len, header) = match data.first() {
+        Some(b) if (0x40..0x58).contains(b) => ((b & 0x1f) as usize, 1),
+        Some(0x58) if data.len() >= 2 => (data[1] as usize, 2),
+        Some(0x59) if data.len() >= 3 => (((data[1] as usize) << 8) | data[2] as usize, 3),
+        Some(0x5a) if data.len() >= 5 => {
+            (u32::from_be_bytes([data[1], data[2], data[3], data[4]]) as usize, 5)
+        }
+        _ => return Err(anyhow::anyhow!("Share is not a CBOR byte string")),
+    };
+
+    if data.len() != header + len {
+        return Err(anyhow::anyhow!("Share has an invalid length"));
+    }
+
+    Ok(data[header..].to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Shares written by Blockchain Commons' `seedtool`, taken from the examples in its manual
+
+    /// The default seed of the manual, `59f2293a5bce7d4de59e71b4207ac5d2`
+    const DEFAULT_SEED: [u8; 16] = [
+        0x59, 0xf2, 0x29, 0x3a, 0x5b, 0xce, 0x7d, 0x4d, 0xe5, 0x9e, 0x71, 0xb4, 0x20, 0x7a, 0xc5,
+        0xd2,
+    ];
+
+    /// Two of the shares of `seedtool -o sskr -g 2-of-3 -s btw`
+    const BYTEWORDS: [&str; 2] = [
+        "tuna next keep gyro acid yawn able acid able leaf idle mild legs play ugly atom liar slot \
+         scar film redo tent poem wasp maze calm scar need toil",
+        "tuna next keep gyro acid yawn able acid acid holy keep when luau cook jazz yank rock grim \
+         toil stub dice keys very ruby work crux peck down iron",
+    ];
+
+    /// `seedtool -i hex 59f2293a5bce7d4de59e71b4207ac5d2 -o sskr -g 2-of-3 -s btwm`
+    const MINIMAL_BYTEWORDS: [&str; 3] = [
+        "tantkpgowkftaeadaehhmnrkdrlybzwpdlyaaededllbckcnrtdnqznduy",
+        "tantkpgowkftaeadaddydttnwnfpsoptzopewnglprgylfahcnjoroctve",
+        "tantkpgowkftaeadaolruykkltcyrpiynshfytvebacnfsjlcawpkpbgne",
+    ];
+
+    /// Two shares of the first group and one of the last of
+    /// `seedtool -o sskr -g 2-of-3 3-of-5 1-of-2 -t 2 -s btwm`, which recover
+    /// `7042842963c788571776c4adfa4ed8df`
+    const GROUPED: [&str; 3] = [
+        "tantkpgohllabgadaeptlkndfzrdwfihtdmtrpeovtsofnfxfzvwtypkot",
+        "tantkpgohllabgadadyllamsenpklgcfceoxdycyjtvwfsjetbjtspuetl",
+        "tantkpgohllabgcxadntdrecrfcxledmsklujndwmnmyurhkmnzswnzcvd",
+    ];
+
+    fn recover(texts: &[&str]) -> Result<Vec<u8>> {
+        let shares = texts
+            .iter()
+            .map(|text| parse_share(text))
+            .collect::<Result<Vec<Vec<u8>>>>()?;
+        from_shares(shares)
+    }
+
+    #[test]
+    fn seedtool_bytewords_are_recovered() {
+        assert_eq!(recover(&BYTEWORDS).unwrap(), DEFAULT_SEED);
+    }
+
+    #[test]
+    fn seedtool_minimal_bytewords_are_recovered() {
+        assert_eq!(recover(&MINIMAL_BYTEWORDS[..2]).unwrap(), DEFAULT_SEED);
+        assert_eq!(recover(&MINIMAL_BYTEWORDS[1..]).unwrap(), DEFAULT_SEED);
+        assert!(recover(&MINIMAL_BYTEWORDS[..1]).is_err());
+    }
+
+    #[test]
+    fn seedtool_group_shares_are_recovered() {
+        let secret = [
+            0x70, 0x42, 0x84, 0x29, 0x63, 0xc7, 0x88, 0x57, 0x17, 0x76, 0xc4, 0xad, 0xfa, 0x4e,
+            0xd8, 0xdf,
+        ];
+        assert_eq!(recover(&GROUPED).unwrap(), secret);
+        assert!(recover(&GROUPED[..2]).is_err());
+    }
+
+    #[test]
+    fn seedtool_shares_read_the_same_in_every_form() {
+        let share = parse_share(MINIMAL_BYTEWORDS[0]).unwrap();
+        assert_eq!(parse_share(&to_bytewords(&share)).unwrap(), share);
+        assert_eq!(parse_share(&to_ur(&share)).unwrap(), share);
+
+        let current = ur::ur::encode(&cbor_bytes(&share), &ur::Type::Custom(CURRENT_UR_TYPE));
+        assert_eq!(parse_share(&current).unwrap(), share);
+    }
+}
@@ -0,0 +1,497 @@
+use anyhow::Result;
+use rand::RngCore;
+
+use crate::encoding;
+use crate::shamir::secure_rng;
+
+/// The largest secret ssss can handle, it works in GF(2^1024) at most
+pub const MAX_SECRET_LEN: usize = 128;
+
+/// The smallest field ssss will apply its diffusion layer to
+const MIN_DIFFUSION_DEGREE: usize = 64;
+
+/// Splits a secret into shares in the textual `[token-]index-hexdata` format of `ssss-split`
+///
+/// Like `ssss-split`, the field size is chosen to match the secret length, and the diffusion layer
+/// is only applied to secrets of at least 8 bytes
+pub fn to_shares(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    token: Option<&str>,
+    diffusion: bool,
+) -> Result<Vec<String>> {
+    if input.is_empty() || input.len() > MAX_SECRET_LEN {
+        return Err(anyhow::anyhow!(
+            "ssss only supports secrets of 1 to {} bytes",
+            MAX_SECRET_LEN
+        ));
+    }
+
+    // ssss treats the secret as a number, so it silently drops leading null bytes on combine
+    if input[0] == 0 {
+        return Err(anyhow::anyhow!(
+            "ssss cannot represent secrets starting with a null byte"
+        ));
+    }
+
+    if threshold < 2 || count < threshold {
+        return Err(anyhow::anyhow!(
+            "ssss requires a threshold of at least 2 and no more than the number of shares"
+        ));
+    }
+
+    if let Some(token) = token {
+        if token.is_empty() || token.contains('-') || token.len() > 128 {
+            return Err(anyhow::anyhow!(
+                "Invalid ssss token \"{}\", it must be 1 to 128 characters without dashes",
+                token
+            ));
+        }
+    }
+
+    let field = Field::new(input.len() * 8)?;
+
+    let mut secret = input.to_vec();
+    if diffusion && field.degree >= MIN_DIFFUSION_DEGREE {
+        encode_diffusion(&mut secret, false);
+    }
+
+    let mut coefficients: Vec<Vec<u64>> = vec![field.element_from_bytes(&secret)];
+    let mut rng = secure_rng()?;
+    let mut rand_container = vec![0u8; input.len()];
+    for _ in 1..threshold {
+        rng.fill_bytes(&mut rand_container);
+        coefficients.push(field.element_from_bytes(&rand_container));
+    }
+
+    let index_width = count.to_string().len();
+    let mut shares: Vec<String> = Vec::new();
+    for i in 1..=count {
+        let y = field.horner(&field.element_from_index(i), &coefficients);
+
+        let mut share = String::new();
+        if let Some(token) = token {
+            share.push_str(token);
+            share.push('-');
+        }
+        share.push_str(&format!("{:0width$}-", i, width = index_width));
+        for b in field.element_to_bytes(&y) {
+            share.push_str(&format!("{:02x}", b));
+        }
+        shares.push(share);
+    }
+
+    Ok(shares)
+}
+
+/// Recovers a secret from shares created by `ssss-split`, or by `to_shares`
+///
+/// Just like `ssss-combine`, only the first `threshold` shares are used, and the diffusion layer
+/// setting has to match the one used when splitting
+pub fn from_shares(input: &[String], threshold: u8, diffusion: bool) -> Result<Vec<u8>> {
+    if threshold < 2 {
+        return Err(anyhow::anyhow!("ssss requires a threshold of at least 2"));
+    }
+
+    if input.len() < threshold as usize {
+        return Err(anyhow::anyhow!(
+            "Not enough shares, {} are needed but only {} were given",
+            threshold,
+            input.len()
+        ));
+    }
+
+    let mut token: Option<&str> = None;
+    let mut xs: Vec<u8> = vec![];
+    let mut ys: Vec<Vec<u8>> = vec![];
+    for (i, share) in input.iter().take(threshold as usize).enumerate() {
+        let (share_token, index, data) = parse_share(share)?;
+
+        if i == 0 {
+            token = share_token;
+        } else if share_token != token {
+            return Err(anyhow::anyhow!("Shares have different tokens"));
+        }
+
+        if xs.contains(&index) {
+            return Err(anyhow::anyhow!("Multiple shares with the same index"));
+        }
+
+        if !ys.is_empty() && data.len() != ys[0].len() {
+            return Err(anyhow::anyhow!("Shares have different security levels"));
+        }
+
+        xs.push(index);
+        ys.push(data);
+    }
+
+    let field = Field::new(ys[0].len() * 8)?;
+
+    // ssss evaluates a monic polynomial, so remove the x^threshold term before interpolating
+    let mut points: Vec<(Vec<u64>, Vec<u64>)> = vec![];
+    for (x, y) in xs.iter().zip(ys.iter()) {
+        let x = field.element_from_index(*x);
+        let x_t = field.pow(&x, threshold as usize);
+        let y = field.add(&field.element_from_bytes(y), &x_t);
+        points.push((x, y));
+    }
+
+    let mut secret = field.element_to_bytes(&field.interpolate_at_zero(&points)?);
+    if diffusion && field.degree >= MIN_DIFFUSION_DEGREE {
+        encode_diffusion(&mut secret, true);
+    }
+
+    // Like ssss-combine, drop the null padding added by larger security levels
+    let start = secret.iter().position(|b| *b != 0).unwrap_or(secret.len());
+    Ok(secret[start..].to_vec())
+}
+
+/// Splits a secret into the shares `ssss-split -t <threshold> -n <count>` writes, without a token
+/// and with the diffusion layer ssss applies by default
+///
+/// ssss doesn't work byte by byte in GF(2^8), it takes the whole secret as one element of a field
+/// as large as the secret, so these shares only combine with ssss shares, not with the other
+/// shares of this crate.
+pub fn to_shares_ssss_compat(input: &[u8], threshold: u8, count: u8) -> Result<Vec<String>> {
+    to_shares(input, threshold, count, None, true)
+}
+
+/// Recovers a secret from shares of `ssss-split` with the default diffusion layer, like
+/// `ssss-combine -t <threshold>`
+///
+/// ssss shares don't record the threshold, and without it the shares recover a wrong secret, so
+/// unlike `from_shares` of the other modules it has to be given.
+pub fn from_shares_ssss_compat(shares: &[&str], threshold: u8) -> Result<Vec<u8>> {
+    let shares = shares
+        .iter()
+        .map(|share| share.to_string())
+        .collect::<Vec<String>>();
+    from_shares(&shares, threshold, true)
+}
+
+/// Splits a share line into its optional token, index, and data
+fn parse_share(share: &str) -> Result<(Option<&str>, u8, Vec<u8>)> {
+    let share = encoding::strip_share_text(share);
+    let parts: Vec<&str> = share.split('-').collect();
+    let (token, index, hex) = match parts.as_slice() {
+        [index, hex] => (None, *index, *hex),
+        [token, index, hex] => (Some(*token), *index, *hex),
+        _ => return Err(anyhow::anyhow!("Invalid ssss share \"{}\"", share)),
+    };
+
+    let index: u8 = match index.parse() {
+        Ok(index) if index != 0 => index,
+        _ => return Err(anyhow::anyhow!("Invalid ssss share index \"{}\"", index)),
+    };
+
+    if hex.is_empty() || hex.len() % 2 != 0 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(anyhow::anyhow!("Invalid ssss share data \"{}\"", hex));
+    }
+
+    let data = (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect::<Vec<u8>>();
+
+    Ok((token, index, data))
+}
+
+/// Applies, or with `decode` removes, the XTEA based diffusion layer of ssss
+///
+/// ssss runs this on the secret as exported by GMP, 16 bit words with the least significant word
+/// first, which for field sizes that are not a multiple of 16 moves the top byte around
+fn encode_diffusion(secret: &mut [u8], decode: bool) {
+    let len = secret.len();
+    let words = len.div_ceil(2);
+    let mut v = vec![0u8; words * 2];
+    for (i, b) in secret.iter().rev().enumerate() {
+        v[(i / 2) * 2 + 1 - (i % 2)] = *b;
+    }
+    if len % 2 == 1 {
+        v[len - 1] = v[len];
+    }
+
+    if decode {
+        for i in (0..40 * len).step_by(2).rev() {
+            encode_slice(&mut v[..len], i, decipher_block);
+        }
+    } else {
+        for i in (0..40 * len).step_by(2) {
+            encode_slice(&mut v[..len], i, encipher_block);
+        }
+    }
+
+    if len % 2 == 1 {
+        v[len] = v[len - 1];
+        v[len - 1] = 0;
+    }
+    for (i, b) in secret.iter_mut().rev().enumerate() {
+        *b = v[(i / 2) * 2 + 1 - (i % 2)];
+    }
+}
+
+fn encode_slice(data: &mut [u8], idx: usize, process_block: fn(&mut [u32; 2])) {
+    let len = data.len();
+    let mut v = [0u32; 2];
+    for (i, word) in v.iter_mut().enumerate() {
+        for j in 0..4 {
+            *word = (*word << 8) | data[(idx + 4 * i + j) % len] as u32;
+        }
+    }
+
+    process_block(&mut v);
+
+    for (i, word) in v.iter().enumerate() {
+        for j in 0..4 {
+            data[(idx + 4 * i + j) % len] = (word >> (24 - 8 * j)) as u8;
+        }
+    }
+}
+
+fn encipher_block(v: &mut [u32; 2]) {
+    let mut sum: u32 = 0;
+    for _ in 0..32 {
+        v[0] = v[0].wrapping_add((((v[1] << 4) ^ (v[1] >> 5)).wrapping_add(v[1])) ^ sum);
+        sum = sum.wrapping_add(XTEA_DELTA);
+        v[1] = v[1].wrapping_add((((v[0] << 4) ^ (v[0] >> 5)).wrapping_add(v[0])) ^ sum);
+    }
+}
+
+fn decipher_block(v: &mut [u32; 2]) {
+    let mut sum: u32 = XTEA_DELTA.wrapping_mul(32);
+    for _ in 0..32 {
+        v[1] = v[1].wrapping_sub((((v[0] << 4) ^ (v[0] >> 5)).wrapping_add(v[0])) ^ sum);
+        sum = sum.wrapping_sub(XTEA_DELTA);
+        v[0] = v[0].wrapping_sub((((v[1] << 4) ^ (v[1] >> 5)).wrapping_add(v[1])) ^ sum);
+    }
+}
+
+const XTEA_DELTA: u32 = 0x9e37_79b9;
+
+/// GF(2^degree), with elements stored as little endian 64 bit limbs
+struct Field {
+    degree: usize,
+    limbs: usize,
+    modulus: Vec<u64>,
+}
+
+impl Field {
+    fn new(degree: usize) -> Result<Field> {
+        if !(8..=MAX_SECRET_LEN * 8).contains(&degree) || !degree.is_multiple_of(8) {
+            return Err(anyhow::anyhow!("Invalid ssss security level {}", degree));
+        }
+
+        let limbs = degree / 64 + 1;
+        let mut modulus = vec![0u64; limbs];
+        let coefficients = &IRREDUCIBLE_COEFFICIENTS[(degree / 8 - 1) * 3..][..3];
+        let mut bits = vec![degree, 0];
+        bits.extend(coefficients.iter().map(|c| *c as usize));
+        for bit in bits {
+            modulus[bit / 64] |= 1 << (bit % 64);
+        }
+
+        Ok(Field {
+            degree,
+            limbs,
+            modulus,
+        })
+    }
+
+    fn element_from_bytes(&self, bytes: &[u8]) -> Vec<u64> {
+        let mut element = vec![0u64; self.limbs];
+        for (i, b) in bytes.iter().rev().enumerate() {
+            element[i / 8] |= (*b as u64) << ((i % 8) * 8);
+        }
+        element
+    }
+
+    fn element_to_bytes(&self, element: &[u64]) -> Vec<u8> {
+        (0..self.degree / 8)
+            .rev()
+            .map(|i| (element[i / 8] >> ((i % 8) * 8)) as u8)
+            .collect()
+    }
+
+    fn element_from_index(&self, index: u8) -> Vec<u64> {
+        let mut element = vec![0u64; self.limbs];
+        element[0] = index as u64;
+        element
+    }
+
+    fn add(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        a.iter().zip(b.iter()).map(|(a, b)| a ^ b).collect()
+    }
+
+    fn mul(&self, a: &[u64], b: &[u64]) -> Vec<u64> {
+        let mut result = vec![0u64; self.limbs];
+        for bit in (0..self.degree).rev() {
+            // Multiply the accumulator by x, reducing when it overflows the field
+            let mut carry = 0;
+            for limb in result.iter_mut() {
+                let next = *limb >> 63;
+                *limb = (*limb << 1) | carry;
+                carry = next;
+            }
+            if (result[self.degree / 64] >> (self.degree % 64)) & 1 == 1 {
+                result = self.add(&result, &self.modulus);
+            }
+
+            if (b[bit / 64] >> (bit % 64)) & 1 == 1 {
+                result = self.add(&result, a);
+            }
+        }
+        result
+    }
+
+    fn pow(&self, a: &[u64], exponent: usize) -> Vec<u64> {
+        let mut result = self.element_from_index(1);
+        for _ in 0..exponent {
+            result = self.mul(&result, a);
+        }
+        result
+    }
+
+    fn invert(&self, a: &[u64]) -> Option<Vec<u64>> {
+        if a.iter().all(|l| *l == 0) {
+            return None;
+        }
+
+        // a^(2^degree - 2), which is the product of a^(2^i) for i in 1..degree
+        let mut result = self.element_from_index(1);
+        let mut square = a.to_vec();
+        for _ in 1..self.degree {
+            square = self.mul(&square, &square);
+            result = self.mul(&result, &square);
+        }
+        Some(result)
+    }
+
+    fn horner(&self, x: &[u64], coefficients: &[Vec<u64>]) -> Vec<u64> {
+        let mut y = x.to_vec();
+        for coefficient in coefficients.iter().skip(1).rev() {
+            y = self.mul(&self.add(&y, coefficient), x);
+        }
+        self.add(&y, &coefficients[0])
+    }
+
+    fn interpolate_at_zero(&self, points: &[(Vec<u64>, Vec<u64>)]) -> Result<Vec<u64>> {
+        let mut result = vec![0u64; self.limbs];
+        for (i, (x_i, y_i)) in points.iter().enumerate() {
+            let mut term = y_i.to_vec();
+            for (j, (x_j, _)) in points.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+
+                let denominator = self
+                    .invert(&self.add(x_j, x_i))
+                    .ok_or_else(|| anyhow::anyhow!("Invalid share"))?;
+                term = self.mul(&self.mul(&term, x_j), &denominator);
+            }
+            result = self.add(&result, &term);
+        }
+        Ok(result)
+    }
+}
+
+/// The middle coefficients of the irreducible pentanomials ssss uses for every field size
+static IRREDUCIBLE_COEFFICIENTS: [u8; 384] = [
+    4, 3, 1, 5, 3, 1, 4, 3, 1, 7, 3, 2, 5, 4, 3, 5, 3, 2, 7, 4, 2, 4, 3, 1, 10, 9, 3, 9, 4, 2, 7,
+    6, 2, 10, 9, 6, 4, 3, 1, 5, 4, 3, 4, 3, 1, 7, 2, 1, 5, 3, 2, 7, 4, 2, 6, 3, 2, 5, 3, 2, 15, 3,
+    2, 11, 3, 2, 9, 8, 7, 7, 2, 1, 5, 3, 2, 9, 3, 1, 7, 3, 1, 9, 8, 3, 9, 4, 2, 8, 5, 3, 15, 14,
+    10, 10, 5, 2, 9, 6, 2, 9, 3, 2, 9, 5, 2, 11, 10, 1, 7, 3, 2, 11, 2, 1, 9, 7, 4, 4, 3, 1, 8, 3,
+    1, 7, 4, 1, 7, 2, 1, 13, 11, 6, 5, 3, 2, 7, 3, 2, 8, 7, 5, 12, 3, 2, 13, 10, 6, 5, 3, 2, 5, 3,
+    2, 9, 5, 2, 9, 7, 2, 13, 4, 3, 4, 3, 1, 11, 6, 4, 18, 9, 6, 19, 18, 13, 11, 3, 2, 15, 9, 6, 4,
+    3, 1, 16, 5, 2, 15, 14, 6, 8, 5, 2, 15, 11, 2, 11, 6, 2, 7, 5, 3, 8, 3, 1, 19, 16, 9, 11, 9,
+    6, 15, 7, 6, 13, 4, 3, 14, 13, 3, 13, 6, 3, 9, 5, 2, 19, 13, 6, 19, 10, 3, 11, 6, 5, 9, 2, 1,
+    14, 3, 2, 13, 3, 1, 7, 5, 4, 11, 9, 8, 11, 6, 5, 23, 16, 9, 19, 14, 6, 23, 10, 2, 8, 3, 2, 5,
+    4, 3, 9, 6, 4, 4, 3, 2, 13, 8, 6, 13, 11, 1, 13, 10, 3, 11, 6, 5, 19, 17, 4, 15, 14, 7, 13, 9,
+    6, 9, 7, 3, 9, 7, 1, 14, 3, 2, 11, 8, 2, 11, 6, 4, 13, 5, 2, 11, 5, 1, 11, 4, 1, 19, 10, 3, 21,
+    10, 6, 13, 3, 1, 15, 7, 5, 19, 18, 10, 7, 5, 3, 12, 7, 2, 7, 5, 1, 14, 9, 6, 10, 3, 2, 15, 13,
+    12, 12, 11, 9, 16, 9, 7, 12, 9, 3, 9, 5, 2, 17, 10, 6, 24, 9, 3, 17, 15, 13, 5, 4, 3, 19, 17,
+    8, 15, 6, 3, 19, 6, 1,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example on the ssss website, `ssss-split -t 3 -n 5` of "my secret root password", with
+    /// the diffusion layer it applies by default
+    const WEBSITE_SHARES: [&str; 5] = [
+        "1-1c41ef496eccfbeba439714085df8437236298da8dd824",
+        "2-fbc74a03a50e14ab406c225afb5f45c40ae11976d2b665",
+        "3-fa1c3a9c6df8af0779c36de6c33f6e36e989d0e0b91309",
+        "4-468de7d6eb36674c9cf008c8e8fc8c566537ad6301eb9e",
+        "5-4756974923c0dce0a55f4774d09ca7a4865f64f56a4ee0",
+    ];
+
+    fn shares(positions: &[usize]) -> Vec<String> {
+        positions
+            .iter()
+            .map(|&i| WEBSITE_SHARES[i].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn ssss_split_shares_are_recovered_with_diffusion() {
+        for positions in [[0, 1, 2], [0, 2, 4], [1, 3, 4], [4, 3, 2]] {
+            let secret = from_shares(&shares(&positions), 3, true).unwrap();
+            assert_eq!(secret, b"my secret root password");
+        }
+
+        let tokened: Vec<String> = shares(&[0, 1, 2])
+            .iter()
+            .map(|share| format!("foo-{}", share))
+            .collect();
+        assert_eq!(
+            from_shares(&tokened, 3, true).unwrap(),
+            b"my secret root password"
+        );
+    }
+
+    #[test]
+    fn compat_shares_round_trip_with_ssss_split_ones() {
+        let picked = [WEBSITE_SHARES[4], WEBSITE_SHARES[0], WEBSITE_SHARES[2]];
+        assert_eq!(
+            from_shares_ssss_compat(&picked, 3).unwrap(),
+            b"my secret root password"
+        );
+
+        let shares = to_shares_ssss_compat(b"my secret root password", 3, 5).unwrap();
+        assert!(shares.iter().enumerate().all(|(i, share)| {
+            share.starts_with(&format!("{}-", i + 1)) && share.len() == WEBSITE_SHARES[i].len()
+        }));
+        let lines = shares.iter().map(String::as_str).collect::<Vec<&str>>();
+        assert_eq!(
+            from_shares_ssss_compat(&lines[2..], 3).unwrap(),
+            b"my secret root password"
+        );
+    }
+
+    #[test]
+    fn ssss_split_shares_are_recovered_without_diffusion() {
+        // Without the diffusion layer the shares of ssss are plain shares of the diffused secret,
+        // the same as `ssss-split -D` gives for it
+        let mut diffused = from_shares(&shares(&[0, 1, 2]), 3, false).unwrap();
+        assert_ne!(diffused, b"my secret root password");
+        assert_eq!(
+            from_shares(&shares(&[1, 3, 4]), 3, false).unwrap(),
+            diffused
+        );
+
+        encode_diffusion(&mut diffused, true);
+        assert_eq!(diffused, b"my secret root password");
+    }
+
+    #[test]
+    fn shares_round_trip_with_and_without_diffusion() {
+        for diffusion in [true, false] {
+            for secret in [&b"pin"[..], b"my secret root password"] {
+                let shares = to_shares(secret, 2, 3, Some("foo"), diffusion).unwrap();
+                assert_eq!(from_shares(&shares[1..], 2, diffusion).unwrap(), secret);
+            }
+        }
+    }
+}
@@ -0,0 +1,108 @@
+use anyhow::Result;
+use image::{DynamicImage, RgbaImage};
+
+/// Marks an image as holding a share, so images without one are told apart from damaged ones
+const MAGIC: &[u8; 4] = b"SHST";
+
+/// The magic marker followed by the length of the share, as a big endian u32
+const HEADER_LEN: usize = MAGIC.len() + 4;
+
+/// The number of bytes an image can hide, one bit in each of the red, green and blue channels of
+/// every pixel
+pub fn capacity(image: &DynamicImage) -> usize {
+    (image.width() as usize * image.height() as usize * 3 / 8).saturating_sub(HEADER_LEN)
+}
+
+/// Hides a share in the least significant bits of the cover image, which looks the same afterwards
+///
+/// The image has to be saved losslessly, like as a PNG, as any other format destroys the hidden
+/// bits. The alpha channel is left alone, so transparent pixels stay transparent.
+pub fn embed_share_in_png(share: &[u8], cover: &DynamicImage) -> Result<DynamicImage> {
+    if share.is_empty() {
+        return Err(anyhow::anyhow!("Share is empty"));
+    }
+
+    let available = capacity(cover);
+    if share.len() > available || share.len() > u32::MAX as usize {
+        return Err(anyhow::anyhow!(
+            "Image of {}x{} pixels can hide {} bytes, but the share is {} bytes",
+            cover.width(),
+            cover.height(),
+            available,
+            share.len()
+        ));
+    }
+
+    let mut payload = Vec::with_capacity(HEADER_LEN + share.len());
+    payload.extend_from_slice(MAGIC);
+    payload.extend_from_slice(&(share.len() as u32).to_be_bytes());
+    payload.extend_from_slice(share);
+
+    let mut image = cover.to_rgba8();
+    let bits = payload
+        .iter()
+        .flat_map(|byte| (0..8).rev().map(move |i| (byte >> i) & 1));
+    for (channel, bit) in color_channels(&mut image).zip(bits) {
+        *channel = (*channel & !1) | bit;
+    }
+
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+/// Reads back a share hidden by `embed_share_in_png`
+pub fn extract_share_from_png(image: &DynamicImage) -> Result<Vec<u8>> {
+    let mut image = image.to_rgba8();
+    let mut bytes = color_channels(&mut image)
+        .map(|channel| *channel & 1)
+        .collect::<Vec<u8>>()
+        .chunks_exact(8)
+        .map(|bits| bits.iter().fold(0, |byte, bit| (byte << 1) | bit))
+        .collect::<Vec<u8>>();
+
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(anyhow::anyhow!("Image does not hide a share"));
+    }
+
+    let length = u32::from_be_bytes(bytes[MAGIC.len()..HEADER_LEN].try_into().unwrap()) as usize;
+    if length == 0 || length > bytes.len() - HEADER_LEN {
+        return Err(anyhow::anyhow!(
+            "Image claims to hide a share of {} bytes, but can only hold {}",
+            length,
+            bytes.len() - HEADER_LEN
+        ));
+    }
+
+    bytes.truncate(HEADER_LEN + length);
+    Ok(bytes.split_off(HEADER_LEN))
+}
+
+/// The red, green and blue channels of every pixel, row by row
+fn color_channels(image: &mut RgbaImage) -> impl Iterator<Item = &mut u8> {
+    image
+        .pixels_mut()
+        .flat_map(|pixel| pixel.0.iter_mut().take(3))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wrapper;
+    use image::Rgba;
+
+    #[test]
+    fn shares_hidden_in_an_image_are_extracted() {
+        let share = wrapper::to_shares(b"secret".to_vec(), 2, 3)
+            .unwrap()
+            .remove(0);
+        let cover = DynamicImage::ImageRgba8(RgbaImage::from_fn(64, 64, |x, y| {
+            Rgba([(x * 4) as u8, (y * 4) as u8, (x ^ y) as u8, 255])
+        }));
+
+        let image = embed_share_in_png(&share, &cover).unwrap();
+        assert_eq!(extract_share_from_png(&image).unwrap(), share);
+        assert!(extract_share_from_png(&cover).is_err());
+
+        let small = DynamicImage::ImageRgba8(RgbaImage::new(8, 8));
+        assert!(embed_share_in_png(&share, &small).is_err());
+    }
+}
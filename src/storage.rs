@@ -0,0 +1,123 @@
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::error::ShamirError;
+use crate::wrapper;
+
+/// Somewhere shares are written to one at a time, like a bucket of an S3 compatible object store
+pub trait ShareSink {
+    /// Stores the share with the given index, replacing any share stored under it before
+    fn put(&self, index: u8, bytes: Vec<u8>) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Somewhere shares are read back from, the counterpart of `ShareSink`
+pub trait ShareSource {
+    /// The indices of the shares that are stored
+    fn list(&self) -> impl Future<Output = Result<Vec<u8>>> + Send;
+
+    /// Reads the share with the given index
+    fn get(&self, index: u8) -> impl Future<Output = Result<Vec<u8>>> + Send;
+}
+
+/// Splits the input like `wrapper::to_shares`, writing every share to the sink as soon as it is
+/// created, instead of returning them all
+///
+/// When writing a share fails, the shares written before it are left in the sink.
+pub async fn split_to_sink(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+    sink: &impl ShareSink,
+) -> Result<()> {
+    for (index, share) in wrapper::to_shares_lazily(input, threshold, count)? {
+        sink.put(index, share)
+            .await
+            .map_err(|e| e.context(format!("Failed to store share {}", index)))?;
+    }
+
+    Ok(())
+}
+
+/// Recovers the input like `wrapper::from_shares`, from every share stored in the source
+pub async fn combine_from_source(source: &impl ShareSource) -> Result<Vec<u8>> {
+    let indices = source.list().await?;
+    if indices.is_empty() {
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })
+        .context("The source holds no shares"));
+    }
+
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for index in indices {
+        let share = source
+            .get(index)
+            .await
+            .map_err(|e| e.context(format!("Failed to read share {}", index)))?;
+        shares.push(share);
+    }
+
+    wrapper::from_shares(shares)
+}
+
+/// Keeps shares in memory, as a reference sink and source, and to stand in for a real object store
+/// in tests
+#[derive(Debug, Default)]
+pub struct MemoryShareStore {
+    shares: Mutex<BTreeMap<u8, Vec<u8>>>,
+}
+
+impl MemoryShareStore {
+    pub fn new() -> MemoryShareStore {
+        MemoryShareStore::default()
+    }
+
+    /// Takes out the share with the given index, like a custodian losing it
+    pub fn remove(&self, index: u8) -> Option<Vec<u8>> {
+        self.shares.lock().unwrap().remove(&index)
+    }
+}
+
+impl ShareSink for MemoryShareStore {
+    async fn put(&self, index: u8, bytes: Vec<u8>) -> Result<()> {
+        self.shares.lock().unwrap().insert(index, bytes);
+        Ok(())
+    }
+}
+
+impl ShareSource for MemoryShareStore {
+    async fn list(&self) -> Result<Vec<u8>> {
+        Ok(self.shares.lock().unwrap().keys().copied().collect())
+    }
+
+    async fn get(&self, index: u8) -> Result<Vec<u8>> {
+        self.shares
+            .lock()
+            .unwrap()
+            .get(&index)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("Share {} is not stored", index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn secrets_round_trip_through_the_memory_store() {
+        let store = MemoryShareStore::new();
+        split_to_sink(b"secret", 2, 3, &store).await.unwrap();
+        assert_eq!(store.list().await.unwrap(), vec![1, 2, 3]);
+
+        store.remove(2);
+        assert_eq!(combine_from_source(&store).await.unwrap(), b"secret");
+
+        store.remove(1);
+        store.remove(3);
+        assert!(combine_from_source(&store).await.is_err());
+    }
+}
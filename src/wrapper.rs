@@ -1,119 +1,3150 @@
 use aes_gcm::{
-  aead::{Aead, AeadCore, KeyInit},
+  aead::{Aead, AeadCore, KeyInit, Payload},
   Aes256Gcm, Key,
 };
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Sha3_256};
+use std::str::FromStr;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
-use crate::shamir::SecretData;
+use crate::additive;
+use crate::error::ShamirError;
+use crate::shamir::{secure_rng, SecretData, MAX_SHARES};
+
+/// Version of the share format `to_shares` writes
+///
+/// Version 0 is the original layout of just the share info and the encrypted data, version 1 added
+/// the sharing scheme and the hash of the encrypted data, version 2 the quorum, version 3 the
+/// optional expiry, and version 4 the length of the encrypted data
+pub const FORMAT_VERSION: u8 = 4;
+
+/// Oldest share format version `from_shares` can read
+pub const MIN_FORMAT_VERSION: u8 = 0;
+
+/// Marks the expiry that follows the scheme of an expiring share, see `to_shares_with_expiry`
+const EXPIRY_MARKER: [u8; 4] = *b"EXPY";
+
+/// How long after their expiry shares are still recovered, as the clocks of the holders may be off
+/// and the expiry date may have been meant in another time zone
+pub const EXPIRY_GRACE_SECS: u64 = 24 * 60 * 60;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ShareInfo {
     length: usize,
     shares: u8,
     hash: [u8; 32],
+    data_hash: [u8; 32],
+    key: [u8; 32],
+    nonce: [u8; 12],
+    /// How many shares must be given to `from_shares_with_quorum`, 0 when there is no quorum
+    min_participants: u8,
+    /// Length of the encrypted data, to tell truncated data apart from other damage, 0 for info of
+    /// version 3 and before
+    data_length: u64,
+}
+
+/// Length of the serialized `ShareInfo`, as bincode gives every field a fixed size: 8 for the
+/// length, 1 for the number of shares, 32 for each hash and the key, 12 for the nonce, 1 for the
+/// quorum and 8 for the length of the encrypted data
+const SHARE_INFO_LEN: usize = 126;
+
+// A field added to `ShareInfo` changes the layout of the info of every new share, which takes a
+// new format version and a fallback in `ShareInfo::parse`, so adding one by accident fails to
+// build, and fails `share_info_size_is_stable`. Serialized the info takes 126 bytes, in memory
+// the same fields take 128 bytes on 64 bit targets, as they are padded to the alignment of u64.
+#[cfg(target_pointer_width = "64")]
+const _: () = assert!(std::mem::size_of::<ShareInfo>() == 128);
+
+/// The share info of versions 2 and 3, before the length of the encrypted data was added
+#[derive(Debug, Deserialize)]
+struct ShareInfoV2 {
+    length: usize,
+    shares: u8,
+    hash: [u8; 32],
+    data_hash: [u8; 32],
+    key: [u8; 32],
+    nonce: [u8; 12],
+    min_participants: u8,
+}
+
+/// The share info of version 1, before the quorum was added
+#[derive(Debug, Deserialize)]
+struct ShareInfoV1 {
+    length: usize,
+    shares: u8,
+    hash: [u8; 32],
+    data_hash: [u8; 32],
     key: [u8; 32],
     nonce: [u8; 12],
 }
 
+impl ShareInfo {
+    /// Reads the share info of version 1 and later, version 1 info has no quorum, and info before
+    /// version 4 no length of the encrypted data
+    fn parse(bytes: &[u8]) -> Result<ShareInfo> {
+        if let Ok(info) = bincode::deserialize::<ShareInfo>(bytes) {
+            return Ok(info);
+        }
+
+        if let Ok(info) = bincode::deserialize::<ShareInfoV2>(bytes) {
+            return Ok(ShareInfo {
+                length: info.length,
+                shares: info.shares,
+                hash: info.hash,
+                data_hash: info.data_hash,
+                key: info.key,
+                nonce: info.nonce,
+                min_participants: info.min_participants,
+                data_length: 0,
+            });
+        }
+
+        let info: ShareInfoV1 = bincode::deserialize(bytes)?;
+        Ok(ShareInfo {
+            length: info.length,
+            shares: info.shares,
+            hash: info.hash,
+            data_hash: info.data_hash,
+            key: info.key,
+            nonce: info.nonce,
+            min_participants: 0,
+            data_length: 0,
+        })
+    }
+}
+
+/// How the share info is split between the shares
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Scheme {
+    /// Shamir's Secret Sharing, any `threshold` of the shares recover the secret
+    Shamir,
+    /// XOR based sharing, every share is needed, which is simpler and faster when that is the case
+    Additive,
+    /// Shamir's Secret Sharing, with a tag binding every share's index to its info fragment
+    ///
+    /// A share that was given another index is reported as tampered before interpolating, instead
+    /// of recovering random info that fails to decrypt. The tag is a hash anyone can recompute, so
+    /// it catches relabeling by mistake or by tools unaware of it, not a deliberate forgery, which
+    /// still only fails decryption.
+    IndexedShamir,
+}
+
+impl Scheme {
+    /// The scheme `to_shares` uses, additive sharing whenever all shares are required
+    pub fn for_threshold(threshold: u8, count: u8) -> Scheme {
+        if threshold == count && count >= 2 {
+            Scheme::Additive
+        } else {
+            Scheme::Shamir
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct Share {
     info: Vec<u8>,
     data: Vec<u8>,
+    scheme: Scheme,
+}
+
+/// A `Share` that borrows its encrypted data from the serialized share instead of copying it
+#[derive(Debug, Deserialize)]
+struct ShareView<'a> {
+    info: Vec<u8>,
+    #[serde(borrow)]
+    data: &'a [u8],
+    scheme: Scheme,
 }
 
-fn create_raw_shares(input: Vec<u8>, threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
-    let secret_data = SecretData::with_secret(input, threshold);
+fn create_raw_shares(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    scheme: Scheme,
+) -> Result<Vec<Vec<u8>>> {
+    if scheme == Scheme::Additive {
+        if threshold != count {
+            return Err(anyhow::anyhow!(
+                "Additive sharing requires the threshold to equal the number of shares"
+            ));
+        }
+        return additive::split_secret(&input, count);
+    }
+
+    let secret_data = SecretData::with_secret_and_count(input, threshold, count)?;
     let mut shares: Vec<Vec<u8>> = Vec::new();
-    for i in 1..=count {
-        let share = secret_data.get_share(i)?;
+    for share in secret_data {
+        let (_, mut share) = share?;
+        if scheme == Scheme::IndexedShamir {
+            let tag = index_tag(&share);
+            share.extend_from_slice(&tag);
+        }
         shares.push(share);
     }
     Ok(shares)
 }
 
-pub fn to_shares(input: Vec<u8>, threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
-    let mut rng = rand::thread_rng();
+/// The tag of an `IndexedShamir` fragment, which starts with the index of the share
+fn index_tag(fragment: &[u8]) -> [u8; 32] {
+    Sha3_256::digest(fragment).into()
+}
+
+/// Whether the info of an `IndexedShamir` share still matches its tag
+fn index_tag_matches(info: &[u8]) -> bool {
+    match info.split_at_checked(info.len().saturating_sub(32)) {
+        Some((fragment, tag)) if !fragment.is_empty() => index_tag(fragment) == tag,
+        _ => false,
+    }
+}
+
+/// Puts the split share info back together from the info fragments of the shares
+fn combine_fragments(scheme: Scheme, fragments: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    match scheme {
+        Scheme::Shamir => SecretData::recover_secret(fragments),
+        Scheme::Additive => additive::recover_secret(fragments),
+        Scheme::IndexedShamir => {
+            let mut untagged: Vec<Vec<u8>> = Vec::new();
+            for fragment in fragments.iter() {
+                let (fragment, tag) = fragment
+                    .split_at_checked(fragment.len().saturating_sub(32))
+                    .filter(|(fragment, _)| !fragment.is_empty())
+                    .ok_or(ShamirError::InconsistentShares)?;
+                if index_tag(fragment) != tag {
+                    return Err(anyhow::Error::new(ShamirError::ShareTampered)
+                        .context(format!("Share {} does not match its index", fragment[0])));
+                }
+                untagged.push(fragment.to_vec());
+            }
+            SecretData::recover_secret(untagged)
+        }
+    }
+}
+
+/// Splits the input with Shamir's Secret Sharing only, without encrypting it first
+///
+/// Raw shares carry no encryption and no integrity protection. Fewer than `threshold` of them still
+/// reveal nothing about the input, but damaged or mismatched shares are not detected, and silently
+/// recover the wrong data. Use them when the input is a key that is protected some other way.
+pub fn to_raw_shares(input: &[u8], threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    if threshold == 0 || threshold > count {
+        return Err(anyhow::anyhow!(
+            "Threshold must be between 1 and the number of shares"
+        ));
+    }
+
+    create_raw_shares(input.to_vec(), threshold, count, Scheme::Shamir)
+}
+
+/// Recovers the input from raw shares created by `to_raw_shares`
+pub fn from_raw_shares(shares: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    SecretData::recover_secret(shares)
+}
+
+/// Encrypts the input, returning the serialized share info that is to be split, and the ciphertext
+///
+/// Inputs shorter than `min_length` are padded with zeros before encrypting, the share info keeps
+/// the true length to trim them back to
+fn encrypt_input(
+    input: &[u8],
+    count: u8,
+    min_length: usize,
+    min_participants: u8,
+    expires_at: Option<u64>,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut rng = secure_rng()?;
 
     let key = Aes256Gcm::generate_key(&mut rng);
     let nonce = Aes256Gcm::generate_nonce(&mut rng);
 
+    let mut padded = input.to_vec();
+    if padded.len() < min_length {
+        padded.resize(min_length, 0);
+    }
+
+    // Encrypt input with aes-gcm crate
+    let cipher = Aes256Gcm::new(&key);
+    let payload = Payload {
+        msg: padded.as_slice(),
+        aad: &expiry_framing(expires_at),
+    };
+    let ciphertext = cipher
+        .encrypt(&nonce, payload)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    // The data hash is split along with the key, binding the ciphertext to this set of shares
     let info = ShareInfo {
         length: input.len(),
         shares: count,
-        hash: Sha3_256::digest(&input).into(),
+        hash: Sha3_256::digest(input).into(),
+        data_hash: Sha3_256::digest(&ciphertext).into(),
         key: key.into(),
         nonce: nonce.into(),
+        min_participants,
+        data_length: ciphertext.len() as u64,
     };
 
     let info_serialized = bincode::serialize(&info).unwrap();
+    debug_assert_eq!(info_serialized.len(), SHARE_INFO_LEN);
 
-    let unverifyable_shares = create_raw_shares(info_serialized, threshold, count)?;
+    Ok((info_serialized, ciphertext))
+}
 
-    // Encrypt input with aes-gcm crate
-    let cipher = Aes256Gcm::new(&key);
-    let ciphertext = cipher
-        .encrypt(&nonce, input.as_slice())
-        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+/// `ShamirError::TruncatedCiphertext` when one copy of the encrypted data is the start of the other
+fn truncated_copy(data: &[u8], other: &[u8]) -> Option<ShamirError> {
+    let (shorter, longer) = match data.len() < other.len() {
+        true => (data, other),
+        false => (other, data),
+    };
+
+    (shorter.len() < longer.len() && longer.starts_with(shorter)).then_some(
+        ShamirError::TruncatedCiphertext {
+            expected: longer.len(),
+            got: shorter.len(),
+        },
+    )
+}
+
+/// The bytes that follow the scheme of a share expiring at `expires_at`, which are also the
+/// associated data its encrypted data is bound to, empty for shares that do not expire
+fn expiry_framing(expires_at: Option<u64>) -> Vec<u8> {
+    match expires_at {
+        Some(expires_at) => [EXPIRY_MARKER.as_slice(), &expires_at.to_le_bytes()].concat(),
+        None => Vec::new(),
+    }
+}
+
+/// Fails with `ShamirError::ShareExpired` once shares expiring at `expires_at` are past their
+/// expiry and the grace period after it
+fn check_expiry(expires_at: Option<u64>) -> Result<()> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|since| since.as_secs())
+        .unwrap_or_default();
+    match expires_at {
+        Some(expires_at) if now > expires_at.saturating_add(EXPIRY_GRACE_SECS) => {
+            Err(ShamirError::ShareExpired { expires_at }.into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The number of shares needed to recover a secret, either as a number or as a share of the count
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Threshold {
+    Absolute(u8),
+    /// A percentage of the shares, rounded up to at least 2 shares
+    Percent(u8),
+}
+
+impl Threshold {
+    /// The number of shares the threshold needs out of `count` shares
+    pub fn resolve(self, count: u8) -> Result<u8> {
+        match self {
+            Threshold::Absolute(threshold) => Ok(threshold),
+            Threshold::Percent(percent) => {
+                if percent == 0 || percent > 100 {
+                    return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+                        .context("Threshold percentage must be between 1% and 100%"));
+                }
+
+                let threshold = (count as u32 * percent as u32).div_ceil(100).max(2);
+                if threshold > count as u32 {
+                    return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+                        .context("A threshold percentage needs at least 2 shares"));
+                }
+
+                Ok(threshold as u8)
+            }
+        }
+    }
+}
+
+impl From<u8> for Threshold {
+    fn from(threshold: u8) -> Threshold {
+        Threshold::Absolute(threshold)
+    }
+}
+
+impl FromStr for Threshold {
+    type Err = anyhow::Error;
+
+    /// Reads a threshold as a number like "3", or a percentage like "60%"
+    fn from_str(text: &str) -> Result<Threshold> {
+        let text = text.trim();
+        match text.strip_suffix('%') {
+            Some(percent) => percent
+                .trim()
+                .parse()
+                .map(Threshold::Percent)
+                .map_err(|_| anyhow::anyhow!("Invalid threshold percentage \"{}\"", text)),
+            None => text
+                .parse()
+                .map(Threshold::Absolute)
+                .map_err(|_| anyhow::anyhow!("Invalid threshold \"{}\"", text)),
+        }
+    }
+}
+
+/// Encrypts the input and splits the key between `count` shares, any `threshold` of which recover
+/// the input with `from_shares`
+///
+/// The shares are returned in ascending order of their index, the share at position `i` always has
+/// index `i + 1` (see `share_index`), so shares can be handed out by position. All functions that
+/// return the shares of a split, like `to_shares_with_scheme`, keep to this order.
+pub fn to_shares(input: Vec<u8>, threshold: u8, count: u8) -> Result<Vec<Vec<u8>>> {
+    to_shares_with_scheme(input, threshold, count, Scheme::for_threshold(threshold, count))
+}
+
+/// Like `to_shares`, but also returns the SHA3-256 hash of the input, to be published separately
+///
+/// Anyone can then check a recovered secret against the published hash. Only publish it for
+/// secrets that can't be guessed, as the hash lets anyone test guesses offline.
+pub fn to_shares_with_commitment(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+) -> Result<(Vec<Vec<u8>>, [u8; 32])> {
+    let commitment: [u8; 32] = Sha3_256::digest(input).into();
+    let shares = to_shares(input.to_vec(), threshold, count)?;
+    Ok((shares, commitment))
+}
+
+/// Like `to_shares`, but with an explicitly chosen sharing scheme
+pub fn to_shares_with_scheme(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    scheme: Scheme,
+) -> Result<Vec<Vec<u8>>> {
+    create_shares(input, threshold, count, scheme, 0, 0, None)
+}
+
+/// Like `to_shares_with_scheme`, but the shares expire at `expires_at`, in seconds since the Unix
+/// epoch, after which `from_shares` refuses to recover them
+///
+/// The expiry is written in the clear after the scheme, so `share_expiry` reads it from any single
+/// share, and the encrypted data is bound to it as associated data, so changing it makes
+/// decryption fail. It is a policy for honest holders, not a cryptographic limit: anyone holding
+/// enough shares can still recover the secret with software that ignores the expiry, or with
+/// `from_shares_ignoring_expiry`. Shares are recovered for `EXPIRY_GRACE_SECS` after their expiry.
+pub fn to_shares_with_expiry(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    scheme: Scheme,
+    expires_at: u64,
+) -> Result<Vec<Vec<u8>>> {
+    create_shares(input, threshold, count, scheme, 0, 0, Some(expires_at))
+}
+
+/// Like `to_shares`, but pads the encrypted data of small secrets to at least `min_share_bytes`
+///
+/// Shares otherwise give away the length of the secret. With the same `min_share_bytes`, the shares
+/// of all secrets up to that length are the same size.
+pub fn to_shares_padded(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    min_share_bytes: usize,
+) -> Result<Vec<Vec<u8>>> {
+    let scheme = Scheme::for_threshold(threshold, count);
+    create_shares(input, threshold, count, scheme, min_share_bytes, 0, None)
+}
+
+/// Like `to_shares`, but also records a quorum of shares that `from_shares_with_quorum` requires,
+/// which may be more than the threshold needed to recover the secret
+///
+/// The quorum is a governance rule, not a cryptographic one. Any `threshold` of the shares still
+/// recover the secret with `from_shares`, or with any software that ignores the quorum.
+pub fn to_shares_with_quorum(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    min_participants: u8,
+) -> Result<Vec<Vec<u8>>> {
+    if min_participants < threshold || min_participants > count {
+        return Err(anyhow::anyhow!(
+            "Quorum must be between the threshold and the number of shares"
+        ));
+    }
+
+    let scheme = Scheme::for_threshold(threshold, count);
+    create_shares(input, threshold, count, scheme, 0, min_participants, None)
+}
+
+fn create_shares(
+    input: Vec<u8>,
+    threshold: u8,
+    count: u8,
+    scheme: Scheme,
+    min_length: usize,
+    min_participants: u8,
+    expires_at: Option<u64>,
+) -> Result<Vec<Vec<u8>>> {
+    let (info_serialized, ciphertext) =
+        encrypt_input(&input, count, min_length, min_participants, expires_at)?;
+
+    let unverifyable_shares = create_raw_shares(info_serialized, threshold, count, scheme)?;
 
     let mut shares: Vec<Vec<u8>> = Vec::new();
-    for i in 0..count as usize {
+    for unverifyable_share in unverifyable_shares.iter() {
         let share = Share {
-            info: unverifyable_shares[i].to_vec(),
+            info: unverifyable_share.to_vec(),
             data: ciphertext.to_vec(),
+            scheme,
         };
 
-        let share_serialized = bincode::serialize(&share).unwrap();
+        let mut share_serialized = bincode::serialize(&share).unwrap();
+        share_serialized.extend_from_slice(&expiry_framing(expires_at));
         shares.push(share_serialized);
     }
 
+    // Callers rely on the order of the shares, see `to_shares`
+    debug_assert!(shares
+        .iter()
+        .enumerate()
+        .all(|(i, share)| share_index(share) == Some(i as u8 + 1)));
+
     Ok(shares)
 }
 
+/// Like `to_shares`, but serializes every share only once the iterator gets to it, along with its
+/// index, so the shares are never all in memory at once
+#[cfg(feature = "object_storage")]
+pub(crate) fn to_shares_lazily(
+    input: &[u8],
+    threshold: u8,
+    count: u8,
+) -> Result<impl Iterator<Item = (u8, Vec<u8>)>> {
+    let scheme = Scheme::for_threshold(threshold, count);
+    let (info_serialized, ciphertext) = encrypt_input(input, count, 0, 0, None)?;
+    let fragments = create_raw_shares(info_serialized, threshold, count, scheme)?;
+
+    Ok(fragments.into_iter().map(move |info| {
+        let index = info[0];
+        let share = Share {
+            info,
+            data: ciphertext.to_vec(),
+            scheme,
+        };
+        (index, bincode::serialize(&share).unwrap())
+    }))
+}
+
+/// Mints shares on demand, for when the number of custodians is not known at split time
+///
+/// The factory holds the whole polynomial, and with it the encryption key. Anyone who gets hold of
+/// it can recover the secret without any shares, or mint new shares at will. Only keep it around in
+/// memory for as long as shares still need to be handed out, and never persist it anywhere the
+/// secret itself couldn't be stored.
+pub struct ShareFactory {
+    secret_data: SecretData,
+    ciphertext: Vec<u8>,
+}
+
+impl ShareFactory {
+    /// Creates the share at the given index, any index from 1 to 255 can be used once
+    pub fn share(&self, index: u8) -> Result<Vec<u8>> {
+        let share = Share {
+            info: self.secret_data.get_share(index)?,
+            data: self.ciphertext.to_vec(),
+            scheme: Scheme::Shamir,
+        };
+
+        Ok(bincode::serialize(&share)?)
+    }
+}
+
+/// Like `to_shares`, but only fixes the threshold and returns a factory to create shares later
+pub fn to_share_factory(input: Vec<u8>, threshold: u8) -> Result<ShareFactory> {
+    // The number of shares is unknown, so it is recorded as 0
+    let (info_serialized, ciphertext) = encrypt_input(&input, 0, 0, 0, None)?;
+
+    Ok(ShareFactory {
+        secret_data: SecretData::with_secret(info_serialized, threshold)?,
+        ciphertext,
+    })
+}
+
+/// `to_shares` for `bytes::Bytes`, as used by tokio, hyper, and friends
+#[cfg(feature = "bytes")]
+pub fn to_shares_bytes(
+    input: bytes::Bytes,
+    threshold: u8,
+    count: u8,
+) -> Result<Vec<bytes::Bytes>> {
+    let shares = to_shares(input.into(), threshold, count)?;
+    Ok(shares.into_iter().map(bytes::Bytes::from).collect())
+}
+
+/// `from_shares` for `bytes::Bytes`, as used by tokio, hyper, and friends
+#[cfg(feature = "bytes")]
+pub fn from_shares_bytes(shares: Vec<bytes::Bytes>) -> Result<bytes::Bytes> {
+    let plaintext = from_shares(shares.into_iter().map(Vec::from).collect())?;
+    Ok(bytes::Bytes::from(plaintext))
+}
+
+/// Time spent in each phase of a recovery, in microseconds
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RecoveryTimings {
+    pub deserialization_us: u64,
+    pub interpolation_us: u64,
+    pub aes_decryption_us: u64,
+    pub hash_verification_us: u64,
+}
+
+/// The largest secret `from_shares` recovers, 100 MB
+pub const DEFAULT_MAX_SECRET_BYTES: usize = 100 * 1024 * 1024;
+
+/// Recovers the secret, as long as the shares claim it is no larger than `DEFAULT_MAX_SECRET_BYTES`
+///
+/// Giving no shares at all fails with `ShamirError::NotEnoughShares`, while the shares of an empty
+/// secret recover to an empty vec.
 pub fn from_shares(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
-    // Return if no shares are given
-    if input.len() == 0 {
-        return Ok(vec![]);
+    from_shares_guarded(input, DEFAULT_MAX_SECRET_BYTES)
+}
+
+/// Same as `from_shares`, but with its own limit on the size of the secret
+///
+/// The shares record the length of the secret, so crafted shares could claim a huge one. That claim
+/// is checked against `max_bytes` before anything is decrypted, failing with
+/// `ShamirError::SecretTooLarge`.
+pub fn from_shares_guarded(input: Vec<Vec<u8>>, max_bytes: usize) -> Result<Vec<u8>> {
+    let (plaintext, _, _, _) = recover(input, max_bytes, false, &mut |_| {})?;
+    Ok(plaintext)
+}
+
+/// Like `from_shares_guarded`, but also recovers shares past their expiry, for a holder who chooses
+/// to override the expiry of `to_shares_with_expiry`
+pub fn from_shares_ignoring_expiry(input: Vec<Vec<u8>>, max_bytes: usize) -> Result<Vec<u8>> {
+    let (plaintext, _, _, _) = recover(input, max_bytes, true, &mut |_| {})?;
+    Ok(plaintext)
+}
+
+/// Same as `from_shares`, but also reports how long each phase of the recovery took
+pub fn from_shares_timed(input: Vec<Vec<u8>>) -> Result<(Vec<u8>, RecoveryTimings)> {
+    let (plaintext, timings, _, _) = recover(input, DEFAULT_MAX_SECRET_BYTES, false, &mut |_| {})?;
+    Ok((plaintext, timings))
+}
+
+/// Same as `from_shares`, but also fails with `ShamirError::QuorumNotMet` when fewer shares are
+/// given than the quorum recorded by `to_shares_with_quorum`
+///
+/// Only distinct shares count towards the quorum, giving one share several times counts it once.
+pub fn from_shares_with_quorum(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let (mut plaintext, _, min_participants, given) =
+        recover(input, DEFAULT_MAX_SECRET_BYTES, false, &mut |_| {})?;
+
+    if given < min_participants as usize {
+        plaintext.zeroize();
+        return Err(ShamirError::QuorumNotMet {
+            given,
+            required: min_participants,
+        }
+        .into());
     }
 
-    let mut shares: Vec<Share> = Vec::new();
-    for share in input {
-        let share: Share = bincode::deserialize(&share)?;
-        shares.push(share);
+    Ok(plaintext)
+}
+
+/// What a recovery used and produced, for an auditor to check against the same shares without
+/// trusting the tool that recovered the secret
+///
+/// It is for transparency, not confidentiality. The hash of the secret lets anyone holding the
+/// proof test guesses of the secret offline, so only hand it out for secrets that can't be guessed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecoveryProof {
+    /// Indices of the shares the secret was recovered from, in the order they were given
+    pub indices: Vec<u8>,
+    /// SHA3-256 hash of every share used, in the same order
+    pub share_hashes: Vec<[u8; 32]>,
+    /// SHA3-256 hash of the encrypted data of the shares, which identifies the split
+    pub data_hash: [u8; 32],
+    /// SHA3-256 hash of the recovered secret
+    pub secret_hash: [u8; 32],
+}
+
+impl RecoveryProof {
+    /// Recovers the secret from the shares on its own, and checks it is the recovery the proof
+    /// describes, from exactly these shares
+    pub fn verify(&self, shares: Vec<Vec<u8>>) -> Result<()> {
+        let (mut secret, proof) = from_shares_with_proof(shares)?;
+        secret.zeroize();
+
+        if proof.indices != self.indices || proof.share_hashes != self.share_hashes {
+            return Err(anyhow::anyhow!(
+                "The shares are not the ones the proof was made with"
+            ));
+        }
+        if proof.data_hash != self.data_hash {
+            return Err(anyhow::anyhow!(
+                "The shares are of another split than the proof"
+            ));
+        }
+        if proof.secret_hash != self.secret_hash {
+            return Err(anyhow::anyhow!(
+                "The shares recover another secret than the proof"
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Same as `from_shares`, but also returns a `RecoveryProof` of the recovery
+///
+/// Exact copies of a share are left out of the proof, like they are left out of the recovery.
+pub fn from_shares_with_proof(input: Vec<Vec<u8>>) -> Result<(Vec<u8>, RecoveryProof)> {
+    let mut indices: Vec<u8> = Vec::new();
+    let mut share_hashes: Vec<[u8; 32]> = Vec::new();
+    for share in input.iter() {
+        let index = share_index(share).ok_or(ShamirError::InconsistentShares)?;
+        let hash: [u8; 32] = Sha3_256::digest(share).into();
+        if share_hashes.contains(&hash) {
+            continue;
+        }
+        indices.push(index);
+        share_hashes.push(hash);
+    }
+    let data_hash = input.first().and_then(|share| share_set_id(share)).ok_or(
+        ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        },
+    )?;
+
+    let plaintext = from_shares(input)?;
+    let proof = RecoveryProof {
+        indices,
+        share_hashes,
+        data_hash,
+        secret_hash: Sha3_256::digest(&plaintext).into(),
+    };
+
+    Ok((plaintext, proof))
+}
+
+/// Number of hex characters in the short form of a split fingerprint
+pub const SHORT_FINGERPRINT_LEN: usize = 8;
+
+/// The fingerprint of the split a share belongs to, to write down when splitting and check the
+/// shares against when recovering
+///
+/// It is the set id in hex, its first `SHORT_FINGERPRINT_LEN` characters are the short form.
+pub fn split_fingerprint(share: &[u8]) -> Option<String> {
+    share_set_id(share).map(|set_id| set_id.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Same as `from_shares`, but also fails when the shares are not of the split with the given
+/// fingerprint, in its short or its full form
+///
+/// This catches recovering a secret from the wrong pile of shares, which succeeds as long as they
+/// are all of the same split.
+pub fn from_shares_expecting_fingerprint(input: Vec<Vec<u8>>, expected: &str) -> Result<Vec<u8>> {
+    check_fingerprint_format(expected)?;
+    if input.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let share = input[0].clone();
+    let plaintext = from_shares(input)?;
+    check_fingerprint(&share, expected)?;
+
+    Ok(plaintext)
+}
+
+/// Fails unless the share is of the split with the given fingerprint, in its short or its full form
+pub fn check_fingerprint(share: &[u8], expected: &str) -> Result<()> {
+    let expected = check_fingerprint_format(expected)?;
+
+    let fingerprint = split_fingerprint(share).unwrap_or_default();
+    if !fingerprint.starts_with(&expected) {
+        return Err(anyhow::anyhow!(
+            "Shares are of the split with fingerprint {}, not {}",
+            &fingerprint[..SHORT_FINGERPRINT_LEN.min(fingerprint.len())],
+            expected
+        ));
+    }
+
+    Ok(())
+}
+
+/// The fingerprint in lowercase, if it is as long as a short or a full one and only hex
+fn check_fingerprint_format(expected: &str) -> Result<String> {
+    let expected = expected.trim().to_ascii_lowercase();
+    if !(expected.len() == SHORT_FINGERPRINT_LEN || expected.len() == 64)
+        || !expected.chars().all(|c| c.is_ascii_hexdigit())
+    {
+        return Err(anyhow::anyhow!(
+            "Fingerprint \"{}\" is not {} or 64 hex characters",
+            expected,
+            SHORT_FINGERPRINT_LEN
+        ));
+    }
+
+    Ok(expected)
+}
+
+/// Same as `from_shares`, but emits a `tracing` event for every step of the recovery, so a failed
+/// recovery ceremony leaves an audit trail of which shares were accepted and where it went wrong
+#[cfg(feature = "tracing")]
+pub fn from_shares_with_logging(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    use tracing::Level;
+
+    let (plaintext, _, _, _) = recover(
+        input,
+        DEFAULT_MAX_SECRET_BYTES,
+        false,
+        &mut |event| match event {
+            RecoveryEvent::ShareReceived { index, size } => {
+                tracing::event!(Level::INFO, ?index, size, "share_received")
+            }
+            RecoveryEvent::ShareRejected { index, reason } => {
+                tracing::event!(Level::WARN, ?index, reason, "share_rejected")
+            }
+            RecoveryEvent::ShareValidated { index, set_id } => {
+                let set_id = set_id[..8]
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>();
+                tracing::event!(Level::INFO, index, set_id, "share_validated")
+            }
+            RecoveryEvent::SharesConsistent { count } => {
+                tracing::event!(Level::INFO, count, "shares_consistent")
+            }
+            RecoveryEvent::InterpolationComplete { secret_len } => {
+                tracing::event!(Level::INFO, secret_len, "interpolation_complete")
+            }
+            RecoveryEvent::DataHashVerified => {
+                tracing::event!(Level::INFO, "data_hash_verified")
+            }
+            RecoveryEvent::DecryptionComplete => {
+                tracing::event!(Level::INFO, "decryption_complete")
+            }
+            RecoveryEvent::HashVerified { ok } => {
+                if ok {
+                    tracing::event!(Level::INFO, ok, "hash_verified")
+                } else {
+                    tracing::event!(Level::ERROR, ok, "hash_verified")
+                }
+            }
+        },
+    )?;
+
+    Ok(plaintext)
+}
+
+/// Steps of a recovery, reported while `recover` runs
+#[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+enum RecoveryEvent {
+    ShareReceived { index: Option<u8>, size: usize },
+    ShareRejected { index: Option<u8>, reason: &'static str },
+    ShareValidated { index: u8, set_id: [u8; 32] },
+    SharesConsistent { count: usize },
+    InterpolationComplete { secret_len: usize },
+    DataHashVerified,
+    DecryptionComplete,
+    HashVerified { ok: bool },
+}
+
+/// Recovers the secret, along with the timings, the recorded quorum and the number of distinct
+/// shares it was recovered from, which leaves out exact copies
+fn recover(
+    input: Vec<Vec<u8>>,
+    max_bytes: usize,
+    ignore_expiry: bool,
+    on_event: &mut dyn FnMut(RecoveryEvent),
+) -> Result<(Vec<u8>, RecoveryTimings, u8, usize)> {
+    let mut timings = RecoveryTimings::default();
+
+    // No shares is not the same as the shares of an empty secret, which recover to an empty vec
+    if input.is_empty() {
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })
+        .context("No shares were given"));
+    }
+
+    // Framed shares are checked against their checksum, and read like any other share after that
+    let input = unframe_shares(input)?;
+
+    // Version 0 shares are recovered on their own, their share info has another layout
+    let v0 = input.iter().filter(|s| parse_v0(s).is_some()).count();
+    if v0 == input.len() {
+        let (plaintext, _, _) = recover_v0(&input, max_bytes, on_event)?;
+        return Ok((plaintext, timings, 0, input.len()));
+    }
+    if v0 > 0 {
+        return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+            .context("Shares of version 0 can't be combined with shares of later versions"));
+    }
+
+    let start = Instant::now();
+    let mut shares: Vec<ShareView> = Vec::new();
+    for (position, share) in input.iter().enumerate() {
+        let index = share_index(share);
+        on_event(RecoveryEvent::ShareReceived {
+            index,
+            size: share.len(),
+        });
+
+        let parsed = validate_share(share).and_then(|_| {
+            bincode::deserialize::<ShareView>(share)
+                .map_err(|_| ShamirError::UnsupportedVersion.into())
+        });
+        match parsed {
+            Ok(share) => shares.push(share),
+            Err(e) => {
+                on_event(RecoveryEvent::ShareRejected {
+                    index,
+                    reason: "malformed",
+                });
+                return Err(e.context(format!("Share {} is malformed", position + 1)));
+            }
+        }
+    }
+
+    // Exact copies of a share add nothing, but two different shares with the same index can't both
+    // be genuine, and would break interpolation
+    let mut unique: Vec<ShareView> = Vec::new();
+    let mut positions: Vec<usize> = Vec::new();
+    for (position, share) in shares.into_iter().enumerate() {
+        let index = share.info.first().copied();
+        let kept = unique
+            .iter()
+            .position(|u| index.is_some() && u.info.first().copied() == index);
+        match kept.map(|k| positions[k]) {
+            Some(other) if input[other] == input[position] => continue,
+            Some(other) => {
+                return Err(
+                    anyhow::Error::new(ShamirError::InconsistentShares).context(format!(
+                        "Shares {} and {} both have index {}, but are different, compare them \
+                         with `verify`",
+                        other + 1,
+                        position + 1,
+                        index.unwrap()
+                    )),
+                );
+            }
+            None => {
+                unique.push(share);
+                positions.push(position);
+            }
+        }
     }
+    let shares = unique;
 
-    // Check if all shares have the same encrypted data
-    let encrypted_data: Vec<u8> = shares[0].data.to_vec();
-    for share in shares.iter_mut() {
-        if share.data != encrypted_data {
+    // Check if all shares have the same encrypted data, comparing in place without any copies, and
+    // in constant time, so crafted shares can't find out where they differ by timing it
+    let encrypted_data: &[u8] = shares[0].data;
+    let data_hash: [u8; 32] = Sha3_256::digest(encrypted_data).into();
+    for share in shares.iter() {
+        let index = share.info.first().copied();
+        if !bool::from(share.data.ct_eq(encrypted_data)) {
+            on_event(RecoveryEvent::ShareRejected {
+                index,
+                reason: "different encrypted data",
+            });
+            if let Some(truncated) = truncated_copy(share.data, encrypted_data) {
+                return Err(anyhow::Error::new(truncated)
+                    .context("One of the shares has a truncated copy of the encrypted data"));
+            }
             return Err(anyhow::anyhow!("Shares do not match"));
         }
-        share.data.clear(); // Not needed anymore
+
+        if let Some(index) = index {
+            on_event(RecoveryEvent::ShareValidated {
+                index,
+                set_id: data_hash,
+            });
+        }
+    }
+
+    // The shares of an expiring split all carry the expiry their encrypted data is bound to
+    let expires_at = share_expiry(&input[0]);
+    if input.iter().any(|share| share_expiry(share) != expires_at) {
+        return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+            .context("Shares do not have the same expiry"));
+    }
+    if !ignore_expiry {
+        check_expiry(expires_at)?;
     }
 
+    // All info fragments of one split have the same length, anything else can't be interpolated
+    let info_length = shares[0].info.len();
+    if info_length == 0 || shares.iter().any(|s| s.info.len() != info_length) {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+    timings.deserialization_us = elapsed_us(start);
+
+    // All shares of one split use the same scheme
+    let scheme = shares[0].scheme;
+    if shares.iter().any(|s| s.scheme != scheme) {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+    on_event(RecoveryEvent::SharesConsistent {
+        count: shares.len(),
+    });
+
     // Decrypt share info
-    let decrypted = SecretData::recover_secret(
-        shares
-            .iter()
-            .map(|s| s.info.to_vec())
-            .collect::<Vec<Vec<u8>>>(),
-    )?;
+    let start = Instant::now();
+    let raw_shares = shares
+        .iter()
+        .map(|s| s.info.to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let mut decrypted = combine_fragments(scheme, raw_shares)?;
+
+    // The serialized info holds the key, only keep the parsed copy of it around
+    let info = ShareInfo::parse(&decrypted);
+    decrypted.zeroize();
+    let info = info?;
+    timings.interpolation_us = elapsed_us(start);
+    on_event(RecoveryEvent::InterpolationComplete {
+        secret_len: info.length,
+    });
 
-    let info: ShareInfo = bincode::deserialize(&decrypted)?;
+    // Check if the encrypted data belongs to these shares, data cut short gets an error of its own.
+    // Too few shares interpolate to random share info, which claims a length far beyond any limit,
+    // so only info that could be real is trusted to tell the data is cut short.
+    let start = Instant::now();
+    if encrypted_data.len() < info.data_length as usize && info.length <= max_bytes {
+        return Err(ShamirError::TruncatedCiphertext {
+            expected: info.data_length as usize,
+            got: encrypted_data.len(),
+        }
+        .into());
+    }
+    if info.data_hash != data_hash {
+        // Too few shares and damaged ones look the same here, as the threshold isn't recorded
+        return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+            .context("Encrypted data does not belong to these shares"));
+    }
+    on_event(RecoveryEvent::DataHashVerified);
+    if info.length > max_bytes {
+        return Err(ShamirError::SecretTooLarge {
+            claimed: info.length,
+            limit: max_bytes,
+        }
+        .into());
+    }
 
     // Decrypt data
     let key = Key::<Aes256Gcm>::from_slice(&info.key);
     let cipher = Aes256Gcm::new(key);
+    let payload = Payload {
+        msg: encrypted_data,
+        aad: &expiry_framing(expires_at),
+    };
     let plaintext = cipher
-        .decrypt(&info.nonce.into(), encrypted_data.as_ref())
+        .decrypt(&info.nonce.into(), payload)
         .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
+    timings.aes_decryption_us = elapsed_us(start);
+    on_event(RecoveryEvent::DecryptionComplete);
+
+    // The recorded length covers the plaintext, the tag is not part of it, and anything after it
+    // is padding
+    let mut plaintext = plaintext;
+    if plaintext.len() < info.length {
+        plaintext.zeroize();
+        return Err(anyhow::anyhow!("Decrypted data does not have the recorded length"));
+    }
+    plaintext.truncate(info.length);
 
     // Check if hash matches
+    let start = Instant::now();
     let hash: [u8; 32] = Sha3_256::digest(&plaintext).into();
+    on_event(RecoveryEvent::HashVerified {
+        ok: info.hash == hash,
+    });
     if info.hash != hash {
+        plaintext.zeroize();
         return Err(anyhow::anyhow!("Hashes do not match"));
     }
+    timings.hash_verification_us = elapsed_us(start);
 
-    Ok(plaintext)
+    Ok((plaintext, timings, info.min_participants, shares.len()))
+}
+
+/// The stage a recovery failed at, see `recovery_failure_stage`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureStage {
+    /// The shares can't be read, or do not belong to the same split
+    Grouping,
+    /// The shares belong together, but do not recover the share info of their encrypted data, as
+    /// too few of them are given or some are damaged
+    Quorum,
+    /// The share info was recovered, but its key does not decrypt the encrypted data
+    Decryption,
+    /// The encrypted data was decrypted, but not to the recorded length and hash
+    Integrity,
+}
+
+/// Recovers the secret only to tell at which stage that fails, `None` when it does not fail
+///
+/// The secret is wiped from memory right away when recovering succeeds.
+pub fn recovery_failure_stage(input: Vec<Vec<u8>>, max_bytes: usize) -> Option<FailureStage> {
+    // Expired shares are recovered all the same, the stages are about the shares themselves
+    let mut stage = FailureStage::Grouping;
+    let recovered = recover(input, max_bytes, true, &mut |event| match event {
+        RecoveryEvent::SharesConsistent { .. } => stage = FailureStage::Quorum,
+        RecoveryEvent::DataHashVerified => stage = FailureStage::Decryption,
+        RecoveryEvent::DecryptionComplete => stage = FailureStage::Integrity,
+        _ => {}
+    });
+
+    match recovered {
+        Ok((mut plaintext, _, _, _)) => {
+            plaintext.zeroize();
+            None
+        }
+        Err(_) => Some(stage),
+    }
+}
+
+fn elapsed_us(start: Instant) -> u64 {
+    start.elapsed().as_micros() as u64
+}
+
+/// Like `from_shares`, but corrects shares with corrupted bytes in their split share info, as long
+/// as no more than `(n - threshold) / 2` of the `n` given shares are corrupted
+///
+/// Returns the secret and the indices of the corrupted shares. Shares that can't be read, or whose
+/// encrypted data differs from most of the others, are left out and reported too. Additive shares
+/// have no redundancy, and can't be corrected.
+pub fn from_shares_error_correcting(input: Vec<Vec<u8>>) -> Result<(Vec<u8>, Vec<u8>)> {
+    let majority = majority_shares(&input).ok_or(ShamirError::NotEnoughShares {
+        have: 0,
+        need: None,
+    })?;
+    if majority.scheme == Scheme::Additive {
+        return Err(anyhow::Error::new(ShamirError::UnsupportedParameters)
+            .context("Additive shares have no redundancy to correct errors with"));
+    }
+
+    check_expiry(majority.expires_at)?;
+
+    let data_hash: [u8; 32] = Sha3_256::digest(majority.data).into();
+    let (info, corrected) = correct_info(&majority.fragments, &data_hash).ok_or_else(|| {
+        anyhow::Error::new(ShamirError::InconsistentShares)
+            .context("Too many shares are corrupted to correct")
+    })?;
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&info.key));
+    let payload = Payload {
+        msg: majority.data,
+        aad: &expiry_framing(majority.expires_at),
+    };
+    let mut plaintext = cipher
+        .decrypt(&info.nonce.into(), payload)
+        .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
+
+    // Padded secrets are trimmed back to their recorded length
+    if plaintext.len() < info.length {
+        return Err(anyhow::anyhow!("Decrypted data does not have the recorded length"));
+    }
+    plaintext.truncate(info.length);
+
+    let hash: [u8; 32] = Sha3_256::digest(&plaintext).into();
+    if info.hash != hash {
+        return Err(anyhow::anyhow!("Hashes do not match"));
+    }
+
+    let mut corrupted = majority
+        .outliers
+        .iter()
+        .filter_map(|(position, _)| share_index(&input[*position]))
+        .collect::<Vec<u8>>();
+    corrupted.extend(corrected);
+    corrupted.sort_unstable();
+    corrupted.dedup();
+    Ok((plaintext, corrupted))
+}
+
+/// A share left out of a recovery, and why
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExcludedShare {
+    /// The position of the share in the input
+    pub position: usize,
+    pub reason: &'static str,
+}
+
+/// Splits off the shares that fail the checks possible before recovering
+///
+/// A share is left out when it can't be read, has a damaged frame, belongs to another split than
+/// most of the shares, has another layout or scheme, has an invalid index, or fails its
+/// `IndexedShamir` tag. Exact copies of a share are kept once, different shares claiming the same
+/// index are all left out. Returns the healthy shares, and the position of every share left out
+/// with the reason why.
+pub fn exclude_damaged_shares(input: Vec<Vec<u8>>) -> (Vec<Vec<u8>>, Vec<ExcludedShare>) {
+    const CONFLICT: &str = "claims the same index as another share";
+
+    let views = input
+        .iter()
+        .map(|raw| match raw.starts_with(&FRAME_MAGIC) {
+            true => unframe_share(raw).ok().and_then(parse_any_version),
+            false => parse_any_version(raw),
+        })
+        .collect::<Vec<Option<ShareView>>>();
+    let readable = views.iter().flatten().collect::<Vec<&ShareView>>();
+    let data = most_common(&readable.iter().map(|v| v.data).collect::<Vec<&[u8]>>());
+    let info_length = most_common(&readable.iter().map(|v| v.info.len()).collect::<Vec<usize>>());
+    let scheme = most_common(&readable.iter().map(|v| v.scheme).collect::<Vec<Scheme>>());
+
+    let mut reasons: Vec<Option<&'static str>> = Vec::new();
+    for (raw, view) in input.iter().zip(views.iter()) {
+        reasons.push(match view {
+            None if raw.starts_with(&FRAME_MAGIC) => Some("has a damaged frame"),
+            None => Some("can't be read"),
+            Some(v) if Some(v.data) != data => Some("belongs to another split"),
+            Some(v) if Some(v.info.len()) != info_length || Some(v.scheme) != scheme => {
+                Some("has a different layout")
+            }
+            Some(v) if v.info[0] == 0 => Some("has an invalid index"),
+            Some(v) if v.scheme == Scheme::IndexedShamir && !index_tag_matches(&v.info) => {
+                Some("fails its index checksum")
+            }
+            Some(_) => None,
+        });
+    }
+
+    // Which of two different shares with the same index is genuine can't be told
+    for i in 0..input.len() {
+        let index = match (&views[i], reasons[i]) {
+            (Some(view), None) => view.info[0],
+            _ => continue,
+        };
+
+        for j in 0..i {
+            let same_index = views[j].as_ref().is_some_and(|v| v.info[0] == index);
+            if !same_index || !matches!(reasons[j], None | Some(CONFLICT)) {
+                continue;
+            }
+
+            if reasons[j].is_none() && input[j] == input[i] {
+                reasons[i] = Some("is a copy of another share");
+                break;
+            }
+            reasons[i] = Some(CONFLICT);
+            reasons[j] = Some(CONFLICT);
+        }
+    }
+    drop(views);
+
+    let mut healthy: Vec<Vec<u8>> = Vec::new();
+    let mut excluded: Vec<ExcludedShare> = Vec::new();
+    for (position, (share, reason)) in input.into_iter().zip(reasons).enumerate() {
+        match reason {
+            Some(reason) => excluded.push(ExcludedShare { position, reason }),
+            None => healthy.push(share),
+        }
+    }
+
+    (healthy, excluded)
+}
+
+/// Like `from_shares`, but leaves out the shares `exclude_damaged_shares` finds, and recovers from
+/// the rest if enough of them remain
+///
+/// Returns the secret along with the shares left out, which are also listed when recovery fails.
+pub fn from_shares_excluding_damaged(input: Vec<Vec<u8>>) -> Result<(Vec<u8>, Vec<ExcludedShare>)> {
+    let (healthy, excluded) = exclude_damaged_shares(input);
+
+    match from_shares(healthy) {
+        Ok(plaintext) => Ok((plaintext, excluded)),
+        Err(e) if excluded.is_empty() => Err(e),
+        Err(e) => {
+            let list = excluded
+                .iter()
+                .map(|e| format!("share {} {}", e.position + 1, e.reason))
+                .collect::<Vec<String>>();
+            Err(e.context(format!(
+                "{} share(s) were left out: {}",
+                excluded.len(),
+                list.join(", ")
+            )))
+        }
+    }
+}
+
+/// The most shares `from_shares_best_effort` leaves out, as the number of subsets to try grows
+/// exponentially with it
+pub const MAX_BEST_EFFORT_EXCLUDED: usize = 3;
+
+/// The outcome of `from_shares_best_effort`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BestEffortRecovery {
+    pub secret: Vec<u8>,
+    /// Indices of the shares the secret was recovered from
+    pub used_indices: Vec<u8>,
+    /// Indices of the shares that had to be left out
+    pub excluded_indices: Vec<u8>,
+}
+
+/// Like `from_shares`, but when the shares do not recover the secret together, tries the subsets
+/// that leave out one of them, then two, up to `MAX_BEST_EFFORT_EXCLUDED`
+///
+/// The first subset that decrypts to the recorded hash wins, so `t + 1` shares of a split with
+/// threshold `t` recover the secret even when one of them is corrupted, after trying at most
+/// `t + 1` subsets. Shares that can't be read have no index, and are left out without being listed.
+pub fn from_shares_best_effort(input: Vec<Vec<u8>>) -> Result<BestEffortRecovery> {
+    let shares = input
+        .into_iter()
+        .filter_map(|share| share_index(&share).map(|index| (index, share)))
+        .collect::<Vec<(u8, Vec<u8>)>>();
+    if shares.is_empty() {
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })
+        .context("None of the shares can be read"));
+    }
+
+    let max_excluded = MAX_BEST_EFFORT_EXCLUDED.min(shares.len() - 1);
+    for excluded in 0..=max_excluded {
+        let mut combination = (0..excluded).collect::<Vec<usize>>();
+        loop {
+            let (left_out, used): (Vec<_>, Vec<_>) = shares
+                .iter()
+                .enumerate()
+                .partition(|(position, _)| combination.contains(position));
+
+            let subset = used.iter().map(|(_, (_, share))| share.clone()).collect();
+            if let Ok(secret) = from_shares(subset) {
+                return Ok(BestEffortRecovery {
+                    secret,
+                    used_indices: used.iter().map(|(_, (index, _))| *index).collect(),
+                    excluded_indices: left_out.iter().map(|(_, (index, _))| *index).collect(),
+                });
+            }
+
+            if !next_combination(&mut combination, shares.len()) {
+                break;
+            }
+        }
+    }
+
+    Err(
+        anyhow::Error::new(ShamirError::InconsistentShares).context(format!(
+            "No subset of the shares recovers the secret, leaving out at most {} of them",
+            max_excluded
+        )),
+    )
+}
+
+/// The shares that agree with most of the others on their encrypted data, layout and scheme
+struct MajorityShares<'a> {
+    data: &'a [u8],
+    scheme: Scheme,
+    expires_at: Option<u64>,
+    /// The info fragments of the agreeing shares, without the tags of `IndexedShamir` fragments
+    fragments: Vec<Vec<u8>>,
+    /// The position of every share left out, and why
+    outliers: Vec<(usize, &'static str)>,
+}
+
+fn majority_shares(input: &[Vec<u8>]) -> Option<MajorityShares<'_>> {
+    let mut outliers: Vec<(usize, &'static str)> = Vec::new();
+    let mut shares: Vec<(usize, ShareView)> = Vec::new();
+    for (position, raw) in input.iter().enumerate() {
+        match bincode::deserialize::<ShareView>(raw) {
+            Ok(share) if !share.info.is_empty() => shares.push((position, share)),
+            _ => outliers.push((position, "can't be read")),
+        }
+    }
+
+    let data = most_common(&shares.iter().map(|(_, s)| s.data).collect::<Vec<&[u8]>>())?;
+    let info_length =
+        most_common(&shares.iter().map(|(_, s)| s.info.len()).collect::<Vec<usize>>())?;
+    let scheme = most_common(&shares.iter().map(|(_, s)| s.scheme).collect::<Vec<Scheme>>())?;
+    let expiries = shares
+        .iter()
+        .map(|(position, _)| share_expiry(&input[*position]))
+        .collect::<Vec<Option<u64>>>();
+    let expires_at = most_common(&expiries)?;
+
+    let mut fragments: Vec<Vec<u8>> = Vec::new();
+    for ((position, share), expiry) in shares.iter().zip(expiries) {
+        let index = share.info[0];
+        let reason = if share.data != data {
+            "has different encrypted data"
+        } else if share.info.len() != info_length || share.scheme != scheme {
+            "has a different layout"
+        } else if expiry != expires_at {
+            "has a different expiry"
+        } else if index == 0 || fragments.iter().any(|f| f[0] == index) {
+            "has an invalid or duplicate index"
+        } else {
+            ""
+        };
+
+        if !reason.is_empty() {
+            outliers.push((*position, reason));
+            continue;
+        }
+
+        // A corrupted tag is corrected along with the rest, so it is not checked
+        let mut fragment = share.info.to_vec();
+        if scheme == Scheme::IndexedShamir {
+            fragment.truncate(fragment.len().saturating_sub(32).max(1));
+        }
+        fragments.push(fragment);
+    }
+
+    Some(MajorityShares {
+        data,
+        scheme,
+        expires_at,
+        fragments,
+        outliers,
+    })
+}
+
+/// Recovers the share info from Shamir fragments with corrupted bytes, along with the indices of
+/// the corrected fragments
+///
+/// The threshold is not recorded in the shares, so every threshold is tried, smallest first, until
+/// the info matches the hash of the encrypted data.
+fn correct_info(fragments: &[Vec<u8>], data_hash: &[u8; 32]) -> Option<(ShareInfo, Vec<u8>)> {
+    for threshold in 1..=fragments.len().min(u8::MAX as usize) as u8 {
+        let (decrypted, corrected) =
+            match SecretData::recover_secret_error_correcting(fragments.to_vec(), threshold) {
+                Ok(recovered) => recovered,
+                Err(_) => continue,
+            };
+
+        match ShareInfo::parse(&decrypted) {
+            Ok(info) if info.data_hash == *data_hash => return Some((info, corrected)),
+            _ => continue,
+        }
+    }
+
+    None
+}
+
+/// Everything recorded about a split besides the key, as far as it could be recovered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareMetadata {
+    /// Length of the secret
+    pub length: usize,
+    /// Number of shares in the split, 0 for shares minted by a `ShareFactory`
+    pub count: u8,
+    /// SHA3-256 hash of the secret
+    pub hash: [u8; 32],
+    /// SHA3-256 hash of the encrypted data, which identifies the split
+    pub data_hash: [u8; 32],
+    /// The quorum recorded by `to_shares_with_quorum`, 0 when there is none
+    pub min_participants: u8,
+    pub scheme: Scheme,
+}
+
+/// Everything `try_recover_best_effort` could salvage
+#[derive(Debug, Clone, Default)]
+pub struct RecoveryOutcome {
+    pub metadata: Option<ShareMetadata>,
+    pub plaintext: Option<Vec<u8>>,
+    /// Everything that went wrong along the way, empty when nothing did
+    pub warnings: Vec<String>,
+}
+
+/// Recovers as much as possible from damaged shares, for when getting anything back beats failing
+///
+/// Shares that can't be read or disagree with most of the others are left out, corrupted Shamir
+/// fragments are corrected when there are enough spare shares, and the metadata is returned even
+/// when the encrypted data is too damaged to decrypt. Metadata that could not be checked against
+/// the encrypted data comes with a warning, and may be wrong.
+pub fn try_recover_best_effort(shares: &[Vec<u8>]) -> RecoveryOutcome {
+    let mut outcome = RecoveryOutcome::default();
+
+    let majority = match majority_shares(shares) {
+        Some(majority) => majority,
+        None => {
+            outcome.warnings.push("None of the shares can be read".to_string());
+            return outcome;
+        }
+    };
+    for (position, reason) in majority.outliers.iter() {
+        outcome
+            .warnings
+            .push(format!("Share {} {}, it was left out", position + 1, reason));
+    }
+    if check_expiry(majority.expires_at).is_err() {
+        outcome
+            .warnings
+            .push("The shares are past their expiry, they are recovered anyway".to_string());
+    }
+
+    // Info that decrypts the data to the recorded hash is right in every byte
+    let aad = expiry_framing(majority.expires_at);
+    let decrypt = |info: &ShareInfo| -> Option<Vec<u8>> {
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&info.key));
+        let payload = Payload {
+            msg: majority.data,
+            aad: &aad,
+        };
+        let mut plaintext = cipher.decrypt(&info.nonce.into(), payload).ok()?;
+        plaintext.truncate(info.length);
+        let hash: [u8; 32] = Sha3_256::digest(&plaintext).into();
+        (plaintext.len() == info.length && info.hash == hash).then_some(plaintext)
+    };
+
+    // The fragments of `IndexedShamir` shares come without their tags
+    let fragments = majority.fragments.clone();
+    let combined = match majority.scheme {
+        Scheme::Additive => additive::recover_secret(fragments),
+        Scheme::Shamir | Scheme::IndexedShamir => SecretData::recover_secret(fragments),
+    }
+    .ok()
+    .and_then(|decrypted| ShareInfo::parse(&decrypted).ok());
+
+    if let Some(plaintext) = combined.as_ref().and_then(decrypt) {
+        outcome.metadata = combined.map(|info| metadata(&info, majority.scheme));
+        outcome.plaintext = Some(plaintext);
+        return outcome;
+    }
+
+    // Some of the fragments may be damaged, which spare shares can correct
+    let data_hash: [u8; 32] = Sha3_256::digest(majority.data).into();
+    let corrected = match majority.scheme {
+        Scheme::Additive => None,
+        Scheme::Shamir | Scheme::IndexedShamir => correct_info(&majority.fragments, &data_hash),
+    };
+    if let Some((info, corrected)) = corrected {
+        outcome.metadata = Some(metadata(&info, majority.scheme));
+        if !corrected.is_empty() {
+            outcome
+                .warnings
+                .push(format!("Corrected the damaged share(s) {:?}", corrected));
+        }
+
+        match decrypt(&info) {
+            Some(plaintext) => outcome.plaintext = Some(plaintext),
+            None => outcome
+                .warnings
+                .push("Decryption failed, the encrypted data is damaged".to_string()),
+        }
+        return outcome;
+    }
+
+    // Info that can't be checked against the encrypted data is only kept when it fits its length
+    match combined.filter(|info| info.length < majority.data.len()) {
+        Some(info) => {
+            outcome.metadata = Some(metadata(&info, majority.scheme));
+            outcome.warnings.push(
+                "Share info can't be checked, the encrypted data is damaged or too few shares \
+                 were given"
+                    .to_string(),
+            );
+        }
+        None => outcome.warnings.push(
+            "Share info can't be recovered, too few shares were given or too many are damaged"
+                .to_string(),
+        ),
+    }
+
+    outcome
+}
+
+fn metadata(info: &ShareInfo, scheme: Scheme) -> ShareMetadata {
+    ShareMetadata {
+        length: info.length,
+        count: info.shares,
+        hash: info.hash,
+        data_hash: info.data_hash,
+        min_participants: info.min_participants,
+        scheme,
+    }
+}
+
+/// The value that occurs most often, any of them on a tie
+fn most_common<T: PartialEq + Copy>(values: &[T]) -> Option<T> {
+    values
+        .iter()
+        .max_by_key(|v| values.iter().filter(|o| o == v).count())
+        .copied()
+}
+
+/// Checks that the full set of shares is given, exactly one share for every index of the split
+///
+/// The number of shares is recorded in the split share info, so a threshold of the shares is
+/// needed to read it. Shares minted by a `ShareFactory` don't record it, and always fail the check.
+pub fn verify_share_count_matches_info(shares: &[Vec<u8>]) -> Result<()> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let mut views: Vec<ShareView> = Vec::new();
+    for share in shares.iter() {
+        views.push(bincode::deserialize(share)?);
+    }
+
+    let first = &views[0];
+    if first.info.is_empty()
+        || views.iter().any(|v| {
+            v.data != first.data || v.scheme != first.scheme || v.info.len() != first.info.len()
+        })
+    {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    let mut indices: Vec<u8> = Vec::new();
+    for view in views.iter() {
+        if indices.contains(&view.info[0]) {
+            return Err(anyhow::anyhow!("Share {} is given more than once", view.info[0]));
+        }
+        indices.push(view.info[0]);
+    }
+
+    let raw_shares = views
+        .iter()
+        .map(|v| v.info.to_vec())
+        .collect::<Vec<Vec<u8>>>();
+    let decrypted = combine_fragments(first.scheme, raw_shares)?;
+
+    // Too few shares interpolate to random info, which the data hash won't match
+    let data_hash: [u8; 32] = Sha3_256::digest(first.data).into();
+    let info = match ShareInfo::parse(&decrypted) {
+        Ok(info) if info.data_hash == data_hash => info,
+        _ => {
+            return Err(anyhow::Error::new(ShamirError::InconsistentShares)
+                .context("Too few shares are given to read the share info, or some are damaged"))
+        }
+    };
+
+    if info.shares == 0 {
+        return Err(anyhow::anyhow!("Shares do not record how many shares were created"));
+    }
+
+    if let Some(index) = indices.iter().find(|i| **i == 0 || **i > info.shares) {
+        return Err(anyhow::anyhow!(
+            "Share {} is not one of the {} shares that were created",
+            index,
+            info.shares
+        ));
+    }
+
+    if indices.len() != info.shares as usize {
+        let missing = (1..=info.shares)
+            .filter(|i| !indices.contains(i))
+            .map(|i| i.to_string())
+            .collect::<Vec<String>>();
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: indices.len(),
+            need: Some(info.shares as usize),
+        })
+        .context(format!(
+            "{} of the {} shares are given, missing share(s) {}",
+            indices.len(),
+            info.shares,
+            missing.join(", ")
+        )));
+    }
+
+    Ok(())
+}
+
+/// A share as written by version 0, before the sharing scheme was added
+#[derive(Debug, Deserialize)]
+struct ShareV0<'a> {
+    info: Vec<u8>,
+    #[serde(borrow)]
+    data: &'a [u8],
+}
+
+/// Reads a version 0 share, which has no version header, but ends right after the data where later
+/// versions have more fields
+fn parse_v0(share: &[u8]) -> Option<ShareV0<'_>> {
+    let parsed: ShareV0 = bincode::deserialize(share).ok()?;
+    (!parsed.info.is_empty() && share.len() == 16 + parsed.info.len() + parsed.data.len())
+        .then_some(parsed)
+}
+
+/// Reads a share of any version, version 0 shares always used Shamir's Secret Sharing
+fn parse_any_version(share: &[u8]) -> Option<ShareView<'_>> {
+    match bincode::deserialize::<ShareView>(share) {
+        Ok(view) if !view.info.is_empty() => Some(view),
+        Ok(_) => None,
+        Err(_) => parse_v0(share).map(|v0| ShareView {
+            info: v0.info,
+            data: v0.data,
+            scheme: Scheme::Shamir,
+        }),
+    }
+}
+
+/// The share info of version 0, before the hash of the encrypted data was added
+#[derive(Debug, Deserialize)]
+struct ShareInfoV0 {
+    length: usize,
+    shares: u8,
+    hash: [u8; 32],
+    key: [u8; 32],
+    nonce: [u8; 12],
+}
+
+/// Recovers the secret from version 0 shares, along with the threshold and number of shares
+fn recover_v0(
+    input: &[Vec<u8>],
+    max_bytes: usize,
+    on_event: &mut dyn FnMut(RecoveryEvent),
+) -> Result<(Vec<u8>, u8, u8)> {
+    let mut shares: Vec<ShareV0> = Vec::new();
+    for (position, share) in input.iter().enumerate() {
+        validate_share(share)
+            .map_err(|e| e.context(format!("Share {} is malformed", position + 1)))?;
+        let parsed =
+            parse_v0(share).ok_or_else(|| anyhow::anyhow!("Share is not a version 0 share"))?;
+        shares.push(parsed);
+    }
+
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let encrypted_data = shares[0].data;
+    if shares
+        .iter()
+        .any(|s| !bool::from(s.data.ct_eq(encrypted_data)))
+    {
+        return Err(anyhow::anyhow!("Shares do not match"));
+    }
+    on_event(RecoveryEvent::SharesConsistent {
+        count: shares.len(),
+    });
+
+    // Version 0 does not record the threshold, but fewer shares than that interpolate to a wrong
+    // key that fails to decrypt, so the smallest number of shares that decrypts is the threshold
+    for threshold in 1..=shares.len() {
+        let raw_shares = shares[..threshold]
+            .iter()
+            .map(|s| s.info.to_vec())
+            .collect::<Vec<Vec<u8>>>();
+        let info: ShareInfoV0 = match bincode::deserialize(&SecretData::recover_secret(raw_shares)?) {
+            Ok(info) => info,
+            Err(_) => continue,
+        };
+
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&info.key));
+        let plaintext = match cipher.decrypt(&info.nonce.into(), encrypted_data) {
+            Ok(plaintext) => plaintext,
+            Err(_) => continue,
+        };
+        on_event(RecoveryEvent::DecryptionComplete);
+
+        // Info interpolated from too few shares is random, its length only counts once it decrypts
+        if info.length > max_bytes {
+            return Err(ShamirError::SecretTooLarge {
+                claimed: info.length,
+                limit: max_bytes,
+            }
+            .into());
+        }
+
+        let hash: [u8; 32] = Sha3_256::digest(&plaintext).into();
+        if info.hash != hash || plaintext.len() != info.length {
+            return Err(anyhow::anyhow!("Hashes do not match"));
+        }
+
+        return Ok((plaintext, threshold as u8, info.shares));
+    }
+
+    Err(anyhow::Error::new(ShamirError::InconsistentShares)
+        .context("No number of the shares decrypts, too few are given or some are damaged"))
+}
+
+/// Recovers the secret from version 0 shares and splits it again into shares of the current
+/// version, with the same threshold and number of shares
+///
+/// A threshold of the old shares is needed, as the secret has to be recovered. The new shares use
+/// a new key, so they can't be mixed with the old ones, and the old ones should be destroyed.
+pub fn migrate_shares_v0_to_v1(shares: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let (secret, threshold, count) = recover_v0(&shares, usize::MAX, &mut |_| {})?;
+    if count < threshold {
+        return Err(ShamirError::InconsistentShares.into());
+    }
+
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        threshold,
+        count,
+        "Migrating shares re-randomizes the encryption key, the version 0 shares are no longer valid"
+    );
+
+    to_shares(secret, threshold, count)
+}
+
+/// The size of one entry of the interleaved index table, the share index, offset and length
+const INTERLEAVED_ENTRY_LEN: usize = 9;
+
+/// Concatenates shares into one blob, behind a table of where each share starts and ends
+///
+/// The blob is meant to be stored whole, so it can be recovered on its own. It only helps when the
+/// shares are about noticing tampering rather than keeping the secret from whoever holds the blob.
+/// The table is the number of shares, followed by the index, offset, and length of every share,
+/// the offsets and lengths as little endian u32.
+pub fn to_interleaved(shares: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let count = u8::try_from(shares.len()).map_err(|_| anyhow::anyhow!("Too many shares"))?;
+
+    let mut blob: Vec<u8> = vec![count];
+    let mut offset = 1 + shares.len() * INTERLEAVED_ENTRY_LEN;
+    for share in shares.iter() {
+        let index = share_index(share).ok_or_else(|| anyhow::anyhow!("Invalid share"))?;
+        let start = u32::try_from(offset).map_err(|_| anyhow::anyhow!("Shares are too large"))?;
+        let length =
+            u32::try_from(share.len()).map_err(|_| anyhow::anyhow!("Shares are too large"))?;
+
+        blob.push(index);
+        blob.extend_from_slice(&start.to_le_bytes());
+        blob.extend_from_slice(&length.to_le_bytes());
+        offset += share.len();
+    }
+
+    for share in shares.iter() {
+        blob.extend_from_slice(share);
+    }
+
+    Ok(blob)
+}
+
+/// Recovers the input from a blob created by `to_interleaved`
+pub fn from_interleaved(blob: &[u8]) -> Result<Vec<u8>> {
+    let count = *blob.first().ok_or(ShamirError::NotEnoughShares {
+        have: 0,
+        need: None,
+    })? as usize;
+
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for entry in 0..count {
+        let start = 1 + entry * INTERLEAVED_ENTRY_LEN;
+        let table = blob
+            .get(start..start + INTERLEAVED_ENTRY_LEN)
+            .ok_or_else(|| anyhow::anyhow!("Interleaved index table is cut short"))?;
+
+        let offset = u32::from_le_bytes(table[1..5].try_into().unwrap()) as usize;
+        let length = u32::from_le_bytes(table[5..9].try_into().unwrap()) as usize;
+        let share = offset
+            .checked_add(length)
+            .and_then(|end| blob.get(offset..end))
+            .ok_or_else(|| anyhow::anyhow!("Interleaved share {} is out of bounds", table[0]))?;
+
+        // The table is not covered by the shares' integrity checks, so it must agree with them
+        if share_index(share) != Some(table[0]) {
+            return Err(ShamirError::InconsistentShares.into());
+        }
+
+        shares.push(share.to_vec());
+    }
+
+    from_shares(shares)
+}
+
+/// A comparison of two shares, each field is `None` when it could not be read from either share
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareDiff {
+    pub same_set_id: Option<bool>,
+    pub same_index: Option<bool>,
+    pub data_length_match: Option<bool>,
+    pub info_length_match: Option<bool>,
+}
+
+/// The parts of a serialized share that could be read, even if the share is cut short
+struct ShareFields<'a> {
+    info_length: Option<u64>,
+    index: Option<u8>,
+    data_length: Option<u64>,
+    data: Option<&'a [u8]>,
+}
+
+impl<'a> ShareFields<'a> {
+    /// Reads the share the way bincode lays it out, a length prefixed `info` and `data`
+    fn parse(share: &'a [u8]) -> ShareFields<'a> {
+        let read_length = |offset: usize| -> Option<u64> {
+            let bytes = share.get(offset..offset.checked_add(8)?)?;
+            Some(u64::from_le_bytes(bytes.try_into().ok()?))
+        };
+
+        let info_length = read_length(0);
+        let index = info_length.filter(|l| *l > 0).and(share.get(8).copied());
+        let data_offset = info_length.and_then(|l| usize::try_from(l).ok()?.checked_add(8));
+        let data_length = data_offset.and_then(read_length);
+        let data = data_offset.zip(data_length).and_then(|(offset, length)| {
+            let start = offset.checked_add(8)?;
+            share.get(start..start.checked_add(usize::try_from(length).ok()?)?)
+        });
+
+        ShareFields {
+            info_length,
+            index,
+            data_length,
+            data,
+        }
+    }
+
+    /// All shares from one split carry the same encrypted data, so its hash identifies the split
+    fn set_id(&self) -> Option<[u8; 32]> {
+        self.data.map(|data| Sha3_256::digest(data).into())
+    }
+}
+
+/// What the header of a share says, as far as it can be read, without anything of the share info
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ShareHeader {
+    pub index: Option<u8>,
+    pub scheme: Option<Scheme>,
+    /// Length of the share info fragment
+    pub info_length: Option<usize>,
+    /// Length of the encrypted data
+    pub data_length: Option<usize>,
+    /// Whether the index checksum of an `IndexedShamir` share matches, `None` for other schemes
+    pub checksum_ok: Option<bool>,
+    /// See `split_fingerprint`
+    pub fingerprint: Option<String>,
+    /// See `share_expiry`
+    pub expires_at: Option<u64>,
+}
+
+/// Reads the header of a share, also of a damaged one
+pub fn share_header(share: &[u8]) -> ShareHeader {
+    let fields = ShareFields::parse(share);
+    let view = parse_any_version(share);
+    let scheme = view.as_ref().map(|v| v.scheme);
+
+    ShareHeader {
+        index: fields.index,
+        scheme,
+        info_length: fields.info_length.and_then(|l| usize::try_from(l).ok()),
+        data_length: fields.data_length.and_then(|l| usize::try_from(l).ok()),
+        checksum_ok: view
+            .filter(|v| v.scheme == Scheme::IndexedShamir)
+            .map(|v| index_tag_matches(&v.info)),
+        fingerprint: split_fingerprint(share),
+        expires_at: share_expiry(share),
+    }
+}
+
+/// Reads the index of a share, without deserializing all of it
+pub fn share_index(share: &[u8]) -> Option<u8> {
+    ShareFields::parse(share).index
+}
+
+/// Identifies the split a share belongs to, shares of the same split have the same set id
+pub fn share_set_id(share: &[u8]) -> Option<[u8; 32]> {
+    ShareFields::parse(share).set_id()
+}
+
+/// When the share expires, in seconds since the Unix epoch, `None` when it does not expire
+pub fn share_expiry(share: &[u8]) -> Option<u64> {
+    // The data is only found when the lengths before it fit in the share, so they can't overflow
+    let fields = ShareFields::parse(share);
+    let data = fields.data?;
+    let end = 16 + fields.info_length? as usize + data.len();
+    match share.get(end + 4..)?.split_first_chunk::<4>() {
+        Some((marker, expires_at)) if *marker == EXPIRY_MARKER => {
+            Some(u64::from_le_bytes(expires_at.try_into().ok()?))
+        }
+        _ => None,
+    }
+}
+
+/// Whether the bytes can be read as a share, to tell shares apart from other files
+pub fn is_share(share: &[u8]) -> bool {
+    parse_any_version(share).is_some()
+}
+
+/// The length of the share the bytes start with, by the lengths in its header, to read shares
+/// written back to back
+///
+/// A version 0 share has nothing after its data, so it is only found at the end of the bytes.
+pub fn share_len(bytes: &[u8]) -> Option<usize> {
+    let fields = ShareFields::parse(bytes);
+    fields.info_length.filter(|l| *l > 0)?;
+    let end = 16 + fields.info_length? as usize + fields.data?.len();
+    match bytes.get(end..end + 4) {
+        Some(scheme) if bincode::deserialize::<Scheme>(scheme).is_ok() => {
+            match bytes.get(end + 4..end + 16) {
+                Some(expiry) if expiry.starts_with(&EXPIRY_MARKER) => Some(end + 16),
+                _ => Some(end + 4),
+            }
+        }
+        _ if bytes.len() == end => Some(end),
+        _ => None,
+    }
+}
+
+/// Checks how a share is laid out before anything else of it is read, so a malformed share fails
+/// with one of a few errors instead of whatever the deserializer runs into
+///
+/// Fails with `ShamirError::TruncatedHeader` when the share ends in a length, the scheme or the
+/// expiry, `LengthOutOfBounds` when the info is empty or a length runs past the end,
+/// `UnsupportedVersion` for an unknown scheme or bytes after it, `ShareIndexOutOfRange` for index
+/// 0, and `ShareTampered` when an `IndexedShamir` share fails its tag. A framed share, see
+/// `frame_share`, also fails with the errors of `unframe_share`. Unframed shares have no magic
+/// bytes or checksum, so random bytes can still pass, and only fail recovery.
+pub fn validate_share(share: &[u8]) -> Result<()> {
+    if share.starts_with(&FRAME_MAGIC) {
+        return validate_unframed(unframe_share(share)?);
+    }
+    validate_unframed(share)
+}
+
+/// `validate_share` for a share that is not framed, a frame inside a frame is not unwrapped
+fn validate_unframed(share: &[u8]) -> Result<()> {
+    let fields = ShareFields::parse(share);
+    let info_length = fields.info_length.ok_or(ShamirError::TruncatedHeader)?;
+    let info = usize::try_from(info_length)
+        .ok()
+        .filter(|l| *l > 0)
+        .and_then(|l| share.get(8..l.checked_add(8)?))
+        .ok_or(ShamirError::LengthOutOfBounds)?;
+    fields.data_length.ok_or(ShamirError::TruncatedHeader)?;
+    let data = fields.data.ok_or(ShamirError::LengthOutOfBounds)?;
+
+    // Version 0 shares end right after the data, later versions have the scheme after it, which
+    // the expiry of an expiring share follows
+    let trailing = &share[16 + info.len() + data.len()..];
+    let scheme = match trailing.len() {
+        0 => Scheme::Shamir,
+        1..=3 => return Err(ShamirError::TruncatedHeader.into()),
+        4 => bincode::deserialize(trailing).map_err(|_| ShamirError::UnsupportedVersion)?,
+        16 if trailing[4..8] == EXPIRY_MARKER => bincode::deserialize(&trailing[..4])
+            .map_err(|_| ShamirError::UnsupportedVersion)?,
+        5..=15 if EXPIRY_MARKER.starts_with(&trailing[4..trailing.len().min(8)]) => {
+            return Err(ShamirError::TruncatedHeader.into())
+        }
+        _ => return Err(ShamirError::UnsupportedVersion.into()),
+    };
+
+    if info[0] == 0 {
+        return Err(ShamirError::ShareIndexOutOfRange {
+            index: 0,
+            max: MAX_SHARES,
+        }
+        .into());
+    }
+    if scheme == Scheme::IndexedShamir && !index_tag_matches(info) {
+        return Err(ShamirError::ShareTampered.into());
+    }
+
+    Ok(())
+}
+
+/// The bytes every framed share starts with
+pub const FRAME_MAGIC: [u8; 4] = *b"SHMR";
+
+/// The version of the frame `frame_share` writes
+pub const FRAME_VERSION: u8 = 1;
+
+/// Length of the frame before the share, the magic bytes, the version and the length of the share
+const FRAME_HEADER_LEN: usize = 13;
+
+/// Length of the checksum after the share
+const FRAME_CHECKSUM_LEN: usize = 4;
+
+/// Puts a share in a frame, to store it or send it where it may get damaged or mixed up with
+/// other files
+///
+/// The frame is `FRAME_MAGIC`, `FRAME_VERSION`, the length of the share as a little endian u64,
+/// the share, and the first 4 bytes of the SHA3-256 hash of the share as a checksum. An unframed
+/// share can't start with the magic bytes, as it starts with the length of its info fragment.
+/// `unframe_share` takes the share out again, `validate_share` and `from_shares` take both.
+pub fn frame_share(share: &[u8]) -> Vec<u8> {
+    let mut framed = Vec::with_capacity(FRAME_HEADER_LEN + share.len() + FRAME_CHECKSUM_LEN);
+    framed.extend_from_slice(&FRAME_MAGIC);
+    framed.push(FRAME_VERSION);
+    framed.extend_from_slice(&(share.len() as u64).to_le_bytes());
+    framed.extend_from_slice(share);
+    framed.extend_from_slice(&Sha3_256::digest(share)[..FRAME_CHECKSUM_LEN]);
+    framed
+}
+
+/// Takes the share out of a frame written by `frame_share`
+///
+/// Fails with `ShamirError::BadMagic` when the bytes don't start with `FRAME_MAGIC`,
+/// `UnsupportedVersion` for another version of the frame, `TruncatedHeader` when the frame ends
+/// before the length of the share, `LengthOutOfBounds` when that length doesn't match the frame,
+/// and `ChecksumMismatch` when the share was damaged. The share itself is not checked, see
+/// `validate_share`.
+pub fn unframe_share(framed: &[u8]) -> Result<&[u8]> {
+    match framed.get(..FRAME_MAGIC.len()) {
+        Some(magic) if magic == FRAME_MAGIC => {}
+        None if FRAME_MAGIC.starts_with(framed) && !framed.is_empty() => {
+            return Err(ShamirError::TruncatedHeader.into())
+        }
+        _ => return Err(ShamirError::BadMagic.into()),
+    }
+    let header = framed
+        .get(..FRAME_HEADER_LEN)
+        .ok_or(ShamirError::TruncatedHeader)?;
+    if header[4] != FRAME_VERSION {
+        return Err(ShamirError::UnsupportedVersion.into());
+    }
+
+    let length = u64::from_le_bytes(header[5..].try_into().unwrap());
+    let length = usize::try_from(length)
+        .ok()
+        .filter(|l| framed.len() - FRAME_HEADER_LEN == l.saturating_add(FRAME_CHECKSUM_LEN))
+        .ok_or(ShamirError::LengthOutOfBounds)?;
+
+    let (share, checksum) = framed[FRAME_HEADER_LEN..].split_at(length);
+    if !bool::from(Sha3_256::digest(share)[..FRAME_CHECKSUM_LEN].ct_eq(checksum)) {
+        return Err(ShamirError::ChecksumMismatch.into());
+    }
+
+    Ok(share)
+}
+
+/// Takes the framed shares among the input out of their frames, leaving the others as they are
+fn unframe_shares(input: Vec<Vec<u8>>) -> Result<Vec<Vec<u8>>> {
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for (position, share) in input.into_iter().enumerate() {
+        if !share.starts_with(&FRAME_MAGIC) {
+            shares.push(share);
+            continue;
+        }
+        match unframe_share(&share) {
+            Ok(unframed) => shares.push(unframed.to_vec()),
+            Err(e) => return Err(e.context(format!("Share {} is malformed", position + 1))),
+        }
+    }
+
+    Ok(shares)
+}
+
+/// Compares two shares without fully deserializing them, so malformed shares can still be compared
+pub fn share_diff(share_a: &[u8], share_b: &[u8]) -> Result<ShareDiff> {
+    if share_a.is_empty() || share_b.is_empty() {
+        return Err(anyhow::anyhow!("Cannot compare an empty share"));
+    }
+
+    let a = ShareFields::parse(share_a);
+    let b = ShareFields::parse(share_b);
+
+    Ok(ShareDiff {
+        same_set_id: compare(a.set_id(), b.set_id()),
+        same_index: compare(a.index, b.index),
+        data_length_match: compare(a.data_length, b.data_length),
+        info_length_match: compare(a.info_length, b.info_length),
+    })
+}
+
+fn compare<T: PartialEq>(a: Option<T>, b: Option<T>) -> Option<bool> {
+    a.zip(b).map(|(a, b)| a == b)
+}
+
+/// What a custodian can tell about one secret from the shares they hold of it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SecretAudit {
+    pub set_id: [u8; 32],
+    /// Indices of the held shares, in the order they were given
+    pub indices: Vec<u8>,
+    /// `None` when none of the held shares could be deserialized
+    pub scheme: Option<Scheme>,
+    /// Number of shares in the split, only additive shares carry it
+    pub count: Option<u8>,
+    /// Number of shares needed to recover the secret, only known for additive shares
+    pub threshold: Option<u8>,
+    /// Whether all held shares are well formed and agree with each other
+    pub valid: bool,
+}
+
+/// Groups the shares of many secrets by the secret they belong to, and reports on each of them
+///
+/// Shamir shares do not reveal the threshold or the number of shares, that is only known after
+/// recovery. Shares that are too damaged to tell which secret they belong to are left out.
+pub fn audit_shares(shares: &[Vec<u8>]) -> Vec<SecretAudit> {
+    let mut audits: Vec<SecretAudit> = Vec::new();
+    let mut info_lengths: Vec<usize> = Vec::new();
+
+    for (i, raw) in shares.iter().enumerate() {
+        // An exact copy of a share tells nothing new
+        if shares[..i].contains(raw) {
+            continue;
+        }
+
+        let fields = ShareFields::parse(raw);
+        let set_id = match fields.set_id() {
+            Some(set_id) => set_id,
+            None => continue,
+        };
+
+        let position = match audits.iter().position(|a| a.set_id == set_id) {
+            Some(position) => position,
+            None => {
+                audits.push(SecretAudit {
+                    set_id,
+                    indices: vec![],
+                    scheme: None,
+                    count: None,
+                    threshold: None,
+                    valid: true,
+                });
+                info_lengths.push(0);
+                audits.len() - 1
+            }
+        };
+        let audit = &mut audits[position];
+
+        if let Some(index) = fields.index {
+            audit.indices.push(index);
+        }
+
+        let share = match bincode::deserialize::<Share>(raw) {
+            Ok(share) if !share.info.is_empty() => share,
+            _ => {
+                audit.valid = false;
+                continue;
+            }
+        };
+
+        // Every share of one secret has the same scheme and info length
+        if audit.scheme.is_some_and(|s| s != share.scheme)
+            || (info_lengths[position] != 0 && info_lengths[position] != share.info.len())
+        {
+            audit.valid = false;
+        }
+        audit.scheme = Some(share.scheme);
+        info_lengths[position] = share.info.len();
+
+        // Additive shares start with their index and the number of shares
+        if share.scheme == Scheme::Additive {
+            let count = share.info.get(1).copied();
+            if count.is_none() || (audit.count.is_some() && audit.count != count) {
+                audit.valid = false;
+            }
+            audit.count = count;
+            audit.threshold = count;
+        }
+    }
+
+    // Two different shares with the same index can't both be genuine
+    for audit in audits.iter_mut() {
+        let mut indices = audit.indices.clone();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.len() != audit.indices.len() {
+            audit.valid = false;
+        }
+    }
+
+    audits
+}
+
+/// What can be told about the shares of one secret from their headers alone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShareSetReport {
+    /// The split most of the shares belong to, `None` when none of them could be read
+    pub set_id: Option<[u8; 32]>,
+    pub scheme: Option<Scheme>,
+    /// Number of shares needed to recover the secret, only known for additive shares
+    pub threshold: Option<u8>,
+    /// Number of shares in the split, only known for additive shares
+    pub count: Option<u8>,
+    /// Indices of the usable shares, sorted
+    pub present: Vec<u8>,
+    /// Indices without a usable share, up to the number of shares or else the highest index given
+    pub missing: Vec<u8>,
+    /// Indices given more than once as exact copies of the same share
+    pub duplicates: Vec<u8>,
+    /// Indices claimed by different shares, none of which can be trusted
+    pub conflicting: Vec<u8>,
+    /// Positions of the shares that can't be used, with the reason why
+    pub failed: Vec<(usize, String)>,
+    /// Whether the usable shares recover the secret, `None` when that can't be known without
+    /// recovering, as Shamir shares do not reveal their threshold
+    pub recoverable: Option<bool>,
+}
+
+/// Reports on a set of shares of one secret, without recovering anything from them
+///
+/// Only the headers of the shares are read, the info fragments holding the key are never
+/// combined. Shares of other splits than the one most shares belong to are reported as failed.
+pub fn analyze_shares(shares: &[impl AsRef<[u8]>]) -> Result<ShareSetReport> {
+    if shares.is_empty() {
+        return Err(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        }
+        .into());
+    }
+
+    let mut views: Vec<Option<ShareView>> = Vec::new();
+    let mut failed: Vec<(usize, String)> = Vec::new();
+    for (position, share) in shares.iter().enumerate() {
+        match bincode::deserialize::<ShareView>(share.as_ref()) {
+            Ok(view) if !view.info.is_empty() => views.push(Some(view)),
+            _ => {
+                failed.push((position, "is not a share, or is damaged".to_string()));
+                views.push(None);
+            }
+        }
+    }
+
+    let set_ids = views
+        .iter()
+        .flatten()
+        .map(|v| Sha3_256::digest(v.data).into())
+        .collect::<Vec<[u8; 32]>>();
+    let set_id = most_common(&set_ids);
+
+    // The first share of the split decides what the others have to agree with
+    let mut first: Option<(Scheme, usize)> = None;
+    let mut count: Option<u8> = None;
+    let mut usable: Vec<(usize, u8)> = Vec::new();
+    for (position, view) in views.iter().enumerate() {
+        let view = match view {
+            Some(view) => view,
+            None => continue,
+        };
+
+        // Additive shares also carry the number of shares, which has to agree as well
+        let agrees = first.is_none_or(|f| f == (view.scheme, view.info.len()))
+            && (view.scheme != Scheme::Additive
+                || view.info.get(1).is_some_and(|c| count.is_none_or(|count| count == *c)));
+        let reason = if set_id != Some(Sha3_256::digest(view.data).into()) {
+            Some("belongs to another split")
+        } else if !agrees {
+            Some("does not match the other shares")
+        } else {
+            None
+        };
+        if let Some(reason) = reason {
+            failed.push((position, reason.to_string()));
+            continue;
+        }
+
+        first = Some((view.scheme, view.info.len()));
+        if view.scheme == Scheme::Additive {
+            count = view.info.get(1).copied();
+        }
+        usable.push((position, view.info[0]));
+    }
+    failed.sort();
+
+    let mut present: Vec<u8> = Vec::new();
+    let mut duplicates: Vec<u8> = Vec::new();
+    let mut conflicting: Vec<u8> = Vec::new();
+    for (i, (position, index)) in usable.iter().enumerate() {
+        match usable[..i].iter().find(|(_, other)| other == index) {
+            Some((other, _)) if shares[*other].as_ref() == shares[*position].as_ref() => {
+                if !duplicates.contains(index) {
+                    duplicates.push(*index);
+                }
+            }
+            Some(_) => {
+                if !conflicting.contains(index) {
+                    conflicting.push(*index);
+                }
+            }
+            None => present.push(*index),
+        }
+    }
+    present.retain(|i| !conflicting.contains(i));
+    present.sort_unstable();
+    duplicates.sort_unstable();
+    conflicting.sort_unstable();
+
+    let highest = count.or(present.last().copied()).unwrap_or(0);
+    let missing = (1..=highest)
+        .filter(|i| !present.contains(i))
+        .collect::<Vec<u8>>();
+
+    let scheme = first.map(|(scheme, _)| scheme);
+    let recoverable = if present.is_empty() || !conflicting.is_empty() {
+        Some(false)
+    } else {
+        count.map(|c| present.len() == c as usize)
+    };
+
+    Ok(ShareSetReport {
+        set_id,
+        scheme,
+        threshold: count,
+        count,
+        present,
+        missing,
+        duplicates,
+        conflicting,
+        failed,
+        recoverable,
+    })
+}
+
+/// The outcome of `preview_quorum`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuorumPreview {
+    /// Positions of the smallest subset of the shares found to recover the secret
+    pub subset: Option<Vec<usize>>,
+    /// Number of shares in the split, as recorded in the share info the subset recovers
+    pub count: Option<u8>,
+    /// Positions of the other shares of the split that do not agree with the subset, either they
+    /// or a share of the subset is damaged
+    pub disagreeing: Vec<usize>,
+    /// Number of subsets tried
+    pub tried: usize,
+    /// Whether the search stopped at `max_subsets` before every subset was tried
+    pub truncated: bool,
+}
+
+/// Looks for a subset of the shares that recovers the secret, trying the smaller subsets first
+///
+/// Only the split share info is recovered, and a subset counts when the info it recovers belongs to
+/// the encrypted data, the secret itself is never decrypted. This tells whether enough good shares
+/// are at hand when their headers can't be trusted, as Shamir shares do not reveal their threshold.
+/// At most `max_subsets` subsets are tried, as there are exponentially many of them.
+///
+/// Damage to the key in a share of the subset only shows when decrypting, unless other shares are
+/// given to check the subset against, which are listed when they disagree with it.
+pub fn preview_quorum(shares: &[impl AsRef<[u8]>], max_subsets: usize) -> QuorumPreview {
+    let mut preview = QuorumPreview {
+        subset: None,
+        count: None,
+        disagreeing: Vec::new(),
+        tried: 0,
+        truncated: false,
+    };
+
+    // Only shares of the same split, layout and scheme can be combined, the largest group first
+    let views = shares
+        .iter()
+        .map(|s| {
+            bincode::deserialize::<ShareView>(s.as_ref())
+                .ok()
+                .filter(|v| !v.info.is_empty())
+        })
+        .collect::<Vec<Option<ShareView>>>();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (position, view) in views.iter().enumerate() {
+        let view = match view {
+            Some(view) => view,
+            None => continue,
+        };
+        let same = |other: &usize| {
+            let other = views[*other].as_ref().unwrap();
+            other.data == view.data
+                && other.info.len() == view.info.len()
+                && other.scheme == view.scheme
+        };
+        match groups.iter_mut().find(|g| same(&g[0])) {
+            Some(group) => group.push(position),
+            None => groups.push(vec![position]),
+        }
+    }
+    groups.sort_by_key(|g| std::cmp::Reverse(g.len()));
+
+    for group in groups.iter() {
+        let first = views[group[0]].as_ref().unwrap();
+        let data_hash: [u8; 32] = Sha3_256::digest(first.data).into();
+
+        for size in 1..=group.len() {
+            let mut combination = (0..size).collect::<Vec<usize>>();
+            loop {
+                let subset = combination
+                    .iter()
+                    .map(|c| group[*c])
+                    .collect::<Vec<usize>>();
+                let fragments = subset
+                    .iter()
+                    .map(|p| views[*p].as_ref().unwrap().info.clone())
+                    .collect::<Vec<Vec<u8>>>();
+
+                // Two shares with the same index can't both be part of a working subset
+                let mut indices = fragments.iter().map(|f| f[0]).collect::<Vec<u8>>();
+                indices.sort_unstable();
+                indices.dedup();
+
+                if indices.len() == size {
+                    if preview.tried == max_subsets {
+                        preview.truncated = true;
+                        return preview;
+                    }
+                    preview.tried += 1;
+
+                    let mut combined =
+                        combine_fragments(first.scheme, fragments.clone()).unwrap_or_default();
+                    let info = ShareInfo::parse(&combined)
+                        .ok()
+                        .filter(|i| i.data_hash == data_hash && i.length <= first.data.len());
+
+                    // The data hash is only one field of the info, damage to the others only shows
+                    // when the other shares do not interpolate to the same info
+                    if let Some(info) = info {
+                        let mut disagreeing: Vec<usize> = Vec::new();
+                        for other in group.iter().filter(|p| !subset.contains(p)) {
+                            let fragment = &views[*other].as_ref().unwrap().info;
+                            if indices.contains(&fragment[0]) {
+                                continue;
+                            }
+
+                            let mut swapped = fragments[1..].to_vec();
+                            swapped.push(fragment.clone());
+                            let mut other_combined =
+                                combine_fragments(first.scheme, swapped).unwrap_or_default();
+                            if other_combined != combined {
+                                disagreeing.push(*other);
+                            }
+                            other_combined.zeroize();
+                        }
+
+                        // A damaged share in the subset makes the others disagree, so the subset
+                        // the fewest shares disagree with is kept
+                        let fewer = disagreeing.len() < preview.disagreeing.len();
+                        if preview.subset.is_none() || fewer {
+                            preview.subset = Some(subset);
+                            preview.count = Some(info.shares);
+                            preview.disagreeing = disagreeing;
+                        }
+                    }
+                    combined.zeroize();
+
+                    if preview.subset.is_some() && preview.disagreeing.is_empty() {
+                        return preview;
+                    }
+                }
+
+                if !next_combination(&mut combination, group.len()) {
+                    break;
+                }
+            }
+
+            // Larger subsets only add shares to the ones found
+            if preview.subset.is_some() {
+                return preview;
+            }
+        }
+    }
+
+    preview
+}
+
+/// Moves to the next combination of the same size out of `n`, in lexicographic order, returning
+/// false after the last one
+fn next_combination(combination: &mut [usize], n: usize) -> bool {
+    let size = combination.len();
+    let i = match (0..size).rev().find(|i| combination[*i] < n - size + i) {
+        Some(i) => i,
+        None => return false,
+    };
+
+    combination[i] += 1;
+    for j in i + 1..size {
+        combination[j] = combination[j - 1] + 1;
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shamir_error<T>(result: Result<T>) -> Option<ShamirError> {
+        result.err()?.downcast_ref::<ShamirError>().copied()
+    }
+
+    /// Size of the serialized `ShareInfo` of the current format version, 126 bytes, while it takes
+    /// 128 bytes in memory on 64 bit targets
+    const EXPECTED_SIZE: usize = 126;
+
+    #[test]
+    fn share_info_size_is_stable() {
+        let info = ShareInfo {
+            length: 6,
+            shares: 3,
+            hash: [1; 32],
+            data_hash: [2; 32],
+            key: [3; 32],
+            nonce: [4; 12],
+            min_participants: 2,
+            data_length: 22,
+        };
+        let serialized = bincode::serialize(&info).unwrap();
+        assert_eq!(serialized.len(), EXPECTED_SIZE);
+        assert_eq!(SHARE_INFO_LEN, EXPECTED_SIZE);
+        #[cfg(target_pointer_width = "64")]
+        assert_eq!(std::mem::size_of::<ShareInfo>(), 128);
+    }
+
+    #[test]
+    fn quorum_is_met_by_enough_distinct_shares() {
+        let shares = to_shares_with_quorum(b"secret".to_vec(), 3, 5, 5).unwrap();
+        assert_eq!(from_shares_with_quorum(shares).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn quorum_is_not_met_by_fewer_shares() {
+        let shares = to_shares_with_quorum(b"secret".to_vec(), 3, 5, 5).unwrap();
+        assert_eq!(
+            shamir_error(from_shares_with_quorum(shares[..4].to_vec())),
+            Some(ShamirError::QuorumNotMet {
+                given: 4,
+                required: 5
+            })
+        );
+    }
+
+    #[test]
+    fn quorum_counts_copies_of_a_share_once() {
+        let shares = to_shares_with_quorum(b"secret".to_vec(), 3, 5, 5).unwrap();
+
+        let mut given = shares[..4].to_vec();
+        given.push(shares[0].clone());
+        assert_eq!(
+            shamir_error(from_shares_with_quorum(given)),
+            Some(ShamirError::QuorumNotMet {
+                given: 4,
+                required: 5
+            })
+        );
+
+        let copies = vec![shares[0].clone(); 5];
+        assert!(from_shares_with_quorum(copies).is_err());
+    }
+
+    #[test]
+    fn exact_copies_are_recovered_once() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let given = vec![shares[0].clone(), shares[0].clone(), shares[1].clone()];
+        assert_eq!(from_shares(given.clone()).unwrap(), b"secret");
+
+        let (_, proof) = from_shares_with_proof(given).unwrap();
+        assert_eq!(proof.indices, vec![1, 2]);
+    }
+
+    #[test]
+    fn different_shares_with_one_index_are_reported() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let other = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let given = vec![shares[0].clone(), shares[1].clone(), other[0].clone()];
+
+        let error = from_shares(given).unwrap_err();
+        assert_eq!(
+            error.downcast_ref::<ShamirError>(),
+            Some(&ShamirError::InconsistentShares)
+        );
+        assert!(error
+            .to_string()
+            .contains("Shares 1 and 3 both have index 1"));
+    }
+
+    /// Shares a byte or a field away from a valid one, the kind of input fuzzing `from_shares` runs
+    /// into, which used to panic or fail with a bincode message
+    fn near_miss_shares() -> Vec<(&'static str, Vec<u8>, ShamirError)> {
+        let share = to_shares(b"secret".to_vec(), 2, 3).unwrap().remove(0);
+        let info_length = u64::from_le_bytes(share[..8].try_into().unwrap()) as usize;
+
+        let mut empty_info = share.clone();
+        empty_info[..8].copy_from_slice(&0u64.to_le_bytes());
+        let mut huge_info = share.clone();
+        huge_info[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut huge_data = share.clone();
+        huge_data[8 + info_length..16 + info_length].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut unknown_scheme = share.clone();
+        let end = unknown_scheme.len();
+        unknown_scheme[end - 4..].copy_from_slice(&[0xff; 4]);
+        let mut trailing = share.clone();
+        trailing.push(0xff);
+        let mut cut_expiry = share.clone();
+        cut_expiry.extend_from_slice(&EXPIRY_MARKER[..2]);
+        let mut index_zero = share.clone();
+        index_zero[8] = 0;
+
+        vec![
+            ("empty", Vec::new(), ShamirError::TruncatedHeader),
+            (
+                "cut in the info length",
+                share[..7].to_vec(),
+                ShamirError::TruncatedHeader,
+            ),
+            ("empty info", empty_info, ShamirError::LengthOutOfBounds),
+            (
+                "huge info length",
+                huge_info,
+                ShamirError::LengthOutOfBounds,
+            ),
+            (
+                "huge data length",
+                huge_data,
+                ShamirError::LengthOutOfBounds,
+            ),
+            (
+                "cut in the data",
+                share[..share.len() - 6].to_vec(),
+                ShamirError::LengthOutOfBounds,
+            ),
+            (
+                "cut in the scheme",
+                share[..share.len() - 2].to_vec(),
+                ShamirError::TruncatedHeader,
+            ),
+            (
+                "unknown scheme",
+                unknown_scheme,
+                ShamirError::UnsupportedVersion,
+            ),
+            ("trailing bytes", trailing, ShamirError::UnsupportedVersion),
+            (
+                "cut in the expiry",
+                cut_expiry,
+                ShamirError::TruncatedHeader,
+            ),
+            (
+                "index 0",
+                index_zero,
+                ShamirError::ShareIndexOutOfRange {
+                    index: 0,
+                    max: MAX_SHARES,
+                },
+            ),
+        ]
+    }
+
+    #[test]
+    fn near_miss_shares_fail_with_typed_errors() {
+        for (name, share, expected) in near_miss_shares() {
+            assert_eq!(
+                shamir_error(validate_share(&share)),
+                Some(expected),
+                "{}",
+                name
+            );
+            assert_eq!(
+                shamir_error(from_shares(vec![share])),
+                Some(expected),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn framed_shares_recover() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let framed = frame_share(&shares[0]);
+        assert!(framed.starts_with(&FRAME_MAGIC));
+        assert_eq!(unframe_share(&framed).unwrap(), &shares[0][..]);
+        validate_share(&framed).unwrap();
+
+        let given = vec![framed, shares[1].clone()];
+        assert_eq!(from_shares(given).unwrap(), b"secret");
+    }
+
+    #[test]
+    fn damaged_frames_fail_with_typed_errors() {
+        let share = to_shares(b"secret".to_vec(), 2, 3).unwrap().remove(0);
+        let framed = frame_share(&share);
+
+        let mut version = framed.clone();
+        version[4] = FRAME_VERSION + 1;
+        let mut huge_length = framed.clone();
+        huge_length[5..13].copy_from_slice(&u64::MAX.to_le_bytes());
+        let mut flipped = framed.clone();
+        flipped[20] ^= 1;
+
+        let cases = [
+            ("not framed", share.clone(), ShamirError::BadMagic),
+            ("empty", Vec::new(), ShamirError::BadMagic),
+            (
+                "cut in the magic",
+                framed[..3].to_vec(),
+                ShamirError::TruncatedHeader,
+            ),
+            (
+                "cut in the length",
+                framed[..8].to_vec(),
+                ShamirError::TruncatedHeader,
+            ),
+            ("other version", version, ShamirError::UnsupportedVersion),
+            ("huge length", huge_length, ShamirError::LengthOutOfBounds),
+            (
+                "cut in the checksum",
+                framed[..framed.len() - 1].to_vec(),
+                ShamirError::LengthOutOfBounds,
+            ),
+            ("damaged share", flipped, ShamirError::ChecksumMismatch),
+        ];
+        for (name, framed, expected) in cases {
+            assert_eq!(
+                shamir_error(unframe_share(&framed)),
+                Some(expected),
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn shares_with_different_data_are_rejected() {
+        let mut shares = to_shares(vec![7; 1000], 3, 3).unwrap();
+        let middle = shares[2].len() / 2;
+        shares[2][middle] ^= 1;
+
+        let mut rejected: Vec<(Option<u8>, &str)> = Vec::new();
+        let result = recover(shares, DEFAULT_MAX_SECRET_BYTES, false, &mut |event| {
+            if let RecoveryEvent::ShareRejected { index, reason } = event {
+                rejected.push((index, reason));
+            }
+        });
+        assert_eq!(result.unwrap_err().to_string(), "Shares do not match");
+        assert_eq!(rejected, vec![(Some(3), "different encrypted data")]);
+    }
+
+    #[test]
+    fn too_few_shares_are_inconsistent() {
+        // The threshold isn't recorded, so too few shares can't be told apart from damaged ones
+        let shares = to_shares(b"secret".to_vec(), 3, 5).unwrap();
+        for given in 1..3 {
+            assert_eq!(
+                shamir_error(from_shares(shares[..given].to_vec())),
+                Some(ShamirError::InconsistentShares)
+            );
+        }
+    }
+
+    /// The share with the last `bytes` of its encrypted data cut, and its length with it
+    fn cut_data(share: &[u8], bytes: u64) -> Vec<u8> {
+        let info_len = u64::from_le_bytes(share[..8].try_into().unwrap()) as usize;
+        let at = 8 + info_len;
+        let data_len = u64::from_le_bytes(share[at..at + 8].try_into().unwrap());
+        let mut cut = share[..at].to_vec();
+        cut.extend_from_slice(&(data_len - bytes).to_le_bytes());
+        cut.extend_from_slice(&share[at + 8..share.len() - 4 - bytes as usize]);
+        cut.extend_from_slice(&share[share.len() - 4..]);
+        cut
+    }
+
+    #[test]
+    fn data_cut_short_in_every_share_is_reported() {
+        let shares = to_shares(vec![7; 1000], 2, 3).unwrap();
+        let cut = shares
+            .iter()
+            .map(|share| cut_data(share, 10))
+            .collect::<Vec<Vec<u8>>>();
+        assert_eq!(
+            shamir_error(from_shares(cut)),
+            Some(ShamirError::TruncatedCiphertext {
+                expected: 1016,
+                got: 1006
+            })
+        );
+    }
+
+    #[test]
+    fn data_cut_short_in_one_share_is_reported() {
+        // Like a download that broke off, the other share has all of the encrypted data
+        let shares = to_shares(vec![7; 1000], 2, 3).unwrap();
+        let cut = vec![cut_data(&shares[0], 300), shares[1].clone()];
+        assert_eq!(
+            shamir_error(from_shares(cut)),
+            Some(ShamirError::TruncatedCiphertext {
+                expected: 1016,
+                got: 716
+            })
+        );
+    }
+
+    #[test]
+    fn encrypted_data_swapped_between_splits_is_detected() {
+        let first = to_shares(b"first secret".to_vec(), 2, 3).unwrap();
+        let second = to_shares(b"other secret".to_vec(), 2, 3).unwrap();
+
+        // Both splits have data of the same length, which sits between the info and the scheme
+        let swapped = first
+            .iter()
+            .zip(second.iter())
+            .map(|(share, other)| {
+                let info_len = u64::from_le_bytes(share[..8].try_into().unwrap()) as usize;
+                let data = 16 + info_len..share.len() - 4;
+                let mut swapped = share.clone();
+                swapped[data.clone()].copy_from_slice(&other[data]);
+                swapped
+            })
+            .collect::<Vec<Vec<u8>>>();
+        validate_share(&swapped[0]).unwrap();
+
+        let error = from_shares(swapped[..2].to_vec()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Encrypted data does not belong to these shares"
+        );
+    }
+
+    #[test]
+    fn share_factory_mints_shares_later() {
+        let factory = to_share_factory(b"secret".to_vec(), 3).unwrap();
+        let early = (1..=3)
+            .map(|i| factory.share(i).unwrap())
+            .collect::<Vec<Vec<u8>>>();
+        let late = factory.share(99).unwrap();
+
+        assert_eq!(from_shares(early.clone()).unwrap(), b"secret");
+        let mixed = vec![early[0].clone(), late, early[2].clone()];
+        assert_eq!(from_shares(mixed).unwrap(), b"secret");
+        assert!(factory.share(0).is_err());
+    }
+
+    #[test]
+    fn info_fragments_of_different_lengths_are_inconsistent() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+
+        // The second share loses the last byte of its info fragment, its data is left alone
+        let info_len = u64::from_le_bytes(shares[1][..8].try_into().unwrap()) as usize;
+        let mut shorter = ((info_len - 1) as u64).to_le_bytes().to_vec();
+        shorter.extend_from_slice(&shares[1][8..7 + info_len]);
+        shorter.extend_from_slice(&shares[1][8 + info_len..]);
+
+        let mixed = vec![shares[0].clone(), shorter];
+        assert_eq!(
+            shamir_error(from_shares(mixed)),
+            Some(ShamirError::InconsistentShares)
+        );
+    }
+
+    #[test]
+    fn shares_of_three_secrets_are_audited_apart() {
+        let first = to_shares(b"first".to_vec(), 2, 3).unwrap();
+        let second = to_shares(b"second".to_vec(), 3, 3).unwrap();
+        let third = to_shares(b"third".to_vec(), 2, 5).unwrap();
+        let held = vec![
+            first[0].clone(),
+            second[1].clone(),
+            third[4].clone(),
+            first[2].clone(),
+        ];
+
+        let audits = audit_shares(&held);
+        assert_eq!(audits.len(), 3);
+        assert_eq!(audits[0].indices, vec![1, 3]);
+        assert_eq!(audits[0].scheme, Some(Scheme::Shamir));
+        assert_eq!(audits[0].threshold, None);
+        assert_eq!(audits[1].indices, vec![2]);
+        assert_eq!(audits[1].scheme, Some(Scheme::Additive));
+        assert_eq!(audits[1].count, Some(3));
+        assert_eq!(audits[1].threshold, Some(3));
+        assert_eq!(audits[2].indices, vec![5]);
+        assert!(audits.iter().all(|audit| audit.valid));
+    }
+
+    #[test]
+    fn secrets_at_aead_boundary_sizes_round_trip() {
+        for size in [0, 1, 15, 16, 17, 31, 32, 33, 4096] {
+            let input = vec![0xa5; size];
+            let shares = to_shares(input.clone(), 2, 3).unwrap();
+            assert_eq!(
+                from_shares(shares[1..].to_vec()).unwrap(),
+                input,
+                "{size} bytes"
+            );
+
+            let padded = to_shares_padded(input.clone(), 2, 3, 64).unwrap();
+            assert_eq!(from_shares(padded).unwrap(), input, "{size} bytes padded");
+        }
+    }
+
+    #[test]
+    fn interleaved_blobs_round_trip() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let blob = to_interleaved(&shares).unwrap();
+        assert_eq!(blob[0], 3);
+        assert_eq!(from_interleaved(&blob).unwrap(), b"secret");
+
+        let subset = to_interleaved(&shares[1..]).unwrap();
+        assert_eq!(from_interleaved(&subset).unwrap(), b"secret");
+
+        let mut relabeled = blob.clone();
+        relabeled[1] = 9;
+        assert_eq!(
+            shamir_error(from_interleaved(&relabeled)),
+            Some(ShamirError::InconsistentShares)
+        );
+        assert!(from_interleaved(&blob[..blob.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn relabeled_indexed_shares_are_tampered() {
+        let shares =
+            to_shares_with_scheme(b"secret".to_vec(), 2, 3, Scheme::IndexedShamir).unwrap();
+        assert_eq!(from_shares(shares[..2].to_vec()).unwrap(), b"secret");
+
+        // The index is the first byte of the info fragment, after its u64 length
+        let mut relabeled = shares[1].clone();
+        assert_eq!(relabeled[8], 2);
+        relabeled[8] = 7;
+        assert_eq!(
+            shamir_error(from_shares(vec![shares[0].clone(), relabeled])),
+            Some(ShamirError::ShareTampered)
+        );
+    }
+
+    #[test]
+    fn padded_shares_of_small_secrets_are_the_same_size() {
+        let short = to_shares_padded(b"pin".to_vec(), 2, 3, 256).unwrap();
+        let long = to_shares_padded(b"a somewhat longer secret".to_vec(), 2, 3, 256).unwrap();
+        assert!(short
+            .iter()
+            .chain(long.iter())
+            .all(|share| share.len() == short[0].len()));
+
+        assert_eq!(from_shares(short).unwrap(), b"pin");
+        assert_eq!(from_shares(long).unwrap(), b"a somewhat longer secret");
+    }
+
+    #[test]
+    fn best_effort_recovers_everything_from_good_shares() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let outcome = try_recover_best_effort(&shares);
+
+        assert_eq!(outcome.plaintext.as_deref(), Some(&b"secret"[..]));
+        let metadata = outcome.metadata.unwrap();
+        assert_eq!((metadata.length, metadata.count), (6, 3));
+        assert_eq!(metadata.scheme, Scheme::Shamir);
+        assert!(outcome.warnings.is_empty());
+    }
+
+    #[test]
+    fn best_effort_recovers_metadata_of_damaged_data() {
+        let mut shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        for share in shares.iter_mut() {
+            let info_length = u64::from_le_bytes(share[..8].try_into().unwrap()) as usize;
+            share[16 + info_length] ^= 1;
+        }
+        let outcome = try_recover_best_effort(&shares);
+
+        assert_eq!(outcome.plaintext, None);
+        assert_eq!(outcome.metadata.unwrap().length, 6);
+        assert_eq!(outcome.warnings.len(), 1);
+    }
+
+    #[test]
+    fn best_effort_recovers_nothing_from_broken_shares() {
+        let outcome = try_recover_best_effort(&[b"not a share".to_vec(), Vec::new()]);
+
+        assert_eq!(outcome.plaintext, None);
+        assert_eq!(outcome.metadata, None);
+        assert_eq!(outcome.warnings, vec!["None of the shares can be read"]);
+    }
+
+    #[test]
+    fn percentage_thresholds_resolve_against_the_count() {
+        let sixty: Threshold = "60%".parse().unwrap();
+        assert_eq!(sixty, Threshold::Percent(60));
+        assert_eq!(sixty.resolve(5).unwrap(), 3);
+        assert_eq!(sixty.resolve(7).unwrap(), 5);
+        assert_eq!(Threshold::Percent(10).resolve(5).unwrap(), 2);
+        assert_eq!(Threshold::Percent(100).resolve(4).unwrap(), 4);
+        assert_eq!("3".parse::<Threshold>().unwrap().resolve(5).unwrap(), 3);
+
+        for (threshold, count) in [(Threshold::Percent(50), 1), (Threshold::Percent(0), 5)] {
+            assert_eq!(
+                shamir_error(threshold.resolve(count)),
+                Some(ShamirError::UnsupportedParameters)
+            );
+        }
+        assert!("60 percent".parse::<Threshold>().is_err());
+    }
+
+    #[test]
+    fn no_shares_differ_from_shares_of_an_empty_secret() {
+        assert_eq!(
+            shamir_error(from_shares(vec![])),
+            Some(ShamirError::NotEnoughShares {
+                have: 0,
+                need: None
+            })
+        );
+
+        let shares = to_shares(vec![], 2, 3).unwrap();
+        assert_eq!(from_shares(shares[..2].to_vec()).unwrap(), b"");
+    }
+
+    /// Shares 1 and 3 of a 2 of 3 split of "secret", written by version 0
+    const V0_SHARES: [&str; 2] = [
+        "56000000000000000185ac0ade7dfcebedf054fd8cd58babde2f7d53364654c43882dd226b2502381e1ce438\
+         7f4f206f93915af3181e7a70a1f2a4c54e2909ccf35c747555a266824fd39df9f8f4737ccf684c869c090485\
+         85ab62eda3ad1600000000000000cdc84d43ef34981ab2caf48897ce6a6ce601ec114959",
+        "56000000000000000398ef1e79871f262c0d0d4dcf9093b400849578b8ccdebd74e29f6932de5fa52dad0e3d\
+         ed064d7bfcf585e062853e2041452d566bee84600ae2953c4c164306dc180292804e7edca54c3601dea2f62c\
+         55c1fa45463f1600000000000000cdc84d43ef34981ab2caf48897ce6a6ce601ec114959",
+    ];
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn version_0_shares_are_recovered() {
+        let shares = V0_SHARES.map(from_hex).to_vec();
+        assert!(shares.iter().all(|share| is_share(share)));
+        assert_eq!(from_shares(shares.clone()).unwrap(), b"secret");
+
+        let current = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let mixed = vec![shares[0].clone(), current[1].clone()];
+        assert_eq!(
+            shamir_error(from_shares(mixed)),
+            Some(ShamirError::InconsistentShares)
+        );
+    }
+
+    #[test]
+    fn recovery_proofs_are_verified_independently() {
+        let shares = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        let used = vec![shares[2].clone(), shares[0].clone()];
+        let (secret, proof) = from_shares_with_proof(used.clone()).unwrap();
+        assert_eq!(secret, b"secret");
+
+        // The auditor only gets the proof as published, and recomputes every hash on their own
+        let published = serde_json::to_string(&proof).unwrap();
+        let proof: RecoveryProof = serde_json::from_str(&published).unwrap();
+        assert_eq!(proof.indices, vec![3, 1]);
+        let share_hashes = used
+            .iter()
+            .map(|share| Sha3_256::digest(share).into())
+            .collect::<Vec<[u8; 32]>>();
+        assert_eq!(proof.share_hashes, share_hashes);
+        assert_eq!(
+            proof.secret_hash,
+            <[u8; 32]>::from(Sha3_256::digest(b"secret"))
+        );
+        assert_eq!(Some(proof.data_hash), share_set_id(&used[0]));
+        proof.verify(used).unwrap();
+
+        assert!(proof.verify(shares[..2].to_vec()).is_err());
+        let others = to_shares(b"secret".to_vec(), 2, 3).unwrap();
+        assert!(proof
+            .verify(vec![others[2].clone(), others[0].clone()])
+            .is_err());
+    }
+
+    #[test]
+    fn shares_are_returned_in_index_order() {
+        let schemes = [Scheme::Shamir, Scheme::Additive, Scheme::IndexedShamir];
+        for scheme in schemes {
+            let shares = to_shares_with_scheme(b"secret".to_vec(), 5, 5, scheme).unwrap();
+            for (i, share) in shares.iter().enumerate() {
+                assert_eq!(share_index(share), Some(i as u8 + 1), "{:?}", scheme);
+            }
+        }
+
+        let shares = to_shares(b"secret".to_vec(), 3, 255).unwrap();
+        for (i, share) in shares.iter().enumerate() {
+            assert_eq!(share_index(share), Some(i as u8 + 1));
+        }
+    }
 }
@@ -0,0 +1,323 @@
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit},
+    Aes256Gcm, Key,
+};
+use anyhow::Result;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use zeroize::Zeroize;
+
+use crate::error::ShamirError;
+use crate::shamir::{self, secure_rng};
+
+/// The name of a zone shares are kept in, like a region or a custodian
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ZoneTag(pub String);
+
+impl From<&str> for ZoneTag {
+    fn from(tag: &str) -> Self {
+        ZoneTag(tag.to_string())
+    }
+}
+
+impl fmt::Display for ZoneTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ZonedInfo {
+    length: usize,
+    hash: [u8; 32],
+    data_hash: [u8; 32],
+    key: [u8; 32],
+    nonce: [u8; 12],
+}
+
+/// The zones of a split and their thresholds, which every share carries in the clear
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct ZoneLayout {
+    global_threshold: u8,
+    zones: Vec<(ZoneTag, u8)>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ZonedShare {
+    layout: ZoneLayout,
+    zone: ZoneTag,
+    /// A share of the pad of the zone, recovered by the threshold of the zone
+    zone_fragment: Vec<u8>,
+    /// A share of the info XOR-ed with the pads of all zones, recovered by the global threshold
+    global_fragment: Vec<u8>,
+    data: Vec<u8>,
+}
+
+/// Splits the input into shares tagged with a zone, recovering it takes the threshold of every zone
+/// as well as `global_threshold` shares in all
+///
+/// Every zone is given as its tag, threshold and number of shares, the shares are returned zone by
+/// zone in the same order. The rules are enforced by the sharing itself: the share info is masked
+/// with a random pad for every zone, which only the threshold of the zone recovers, and the masked
+/// info is split between all shares with the global threshold.
+pub fn to_zoned_shares(
+    input: &[u8],
+    global_threshold: u8,
+    zones: &[(ZoneTag, u8, u8)],
+) -> Result<Vec<Vec<u8>>> {
+    if zones.is_empty() {
+        return Err(anyhow::anyhow!("At least one zone is needed"));
+    }
+
+    let mut total: usize = 0;
+    for (i, (tag, threshold, count)) in zones.iter().enumerate() {
+        if *threshold == 0 || threshold > count {
+            return Err(anyhow::anyhow!(
+                "Threshold of zone \"{}\" must be between 1 and its number of shares",
+                tag
+            ));
+        }
+        if zones[..i].iter().any(|(other, _, _)| other == tag) {
+            return Err(anyhow::anyhow!("Zone \"{}\" is given more than once", tag));
+        }
+        total += *count as usize;
+    }
+
+    let total = shamir::checked_share_count(total)
+        .map_err(|e| e.context("The zones have too many shares together"))?;
+    if global_threshold == 0 || global_threshold > total {
+        return Err(anyhow::anyhow!(
+            "Global threshold must be between 1 and the number of shares of all zones"
+        ));
+    }
+
+    // Encrypt input
+    let mut rng = secure_rng()?;
+    let key = Aes256Gcm::generate_key(&mut rng);
+    let nonce = Aes256Gcm::generate_nonce(&mut rng);
+    let ciphertext = Aes256Gcm::new(&key)
+        .encrypt(&nonce, input)
+        .map_err(|_| anyhow::anyhow!("Encryption failed"))?;
+
+    let info = ZonedInfo {
+        length: input.len(),
+        hash: Sha3_256::digest(input).into(),
+        data_hash: Sha3_256::digest(&ciphertext).into(),
+        key: key.into(),
+        nonce: nonce.into(),
+    };
+    let mut masked = bincode::serialize(&info)?;
+
+    // Every zone gets a pad the info is masked with, split between the shares of that zone
+    let mut zone_fragments: Vec<Vec<Vec<u8>>> = Vec::new();
+    for (_, threshold, count) in zones.iter() {
+        let mut pad = vec![0u8; masked.len()];
+        rng.fill_bytes(&mut pad);
+        for (m, p) in masked.iter_mut().zip(pad.iter()) {
+            *m ^= p;
+        }
+        zone_fragments.push(shamir::split_secret(&pad, *threshold, *count)?);
+        pad.zeroize();
+    }
+
+    let mut global_fragments = shamir::split_secret(&masked, global_threshold, total)?.into_iter();
+    masked.zeroize();
+
+    let layout = ZoneLayout {
+        global_threshold,
+        zones: zones.iter().map(|(tag, t, _)| (tag.clone(), *t)).collect(),
+    };
+
+    let mut shares: Vec<Vec<u8>> = Vec::new();
+    for ((tag, _, _), fragments) in zones.iter().zip(zone_fragments) {
+        for zone_fragment in fragments {
+            let share = ZonedShare {
+                layout: layout.clone(),
+                zone: tag.clone(),
+                zone_fragment,
+                global_fragment: global_fragments.next().unwrap(),
+                data: ciphertext.clone(),
+            };
+            shares.push(bincode::serialize(&share)?);
+        }
+    }
+
+    Ok(shares)
+}
+
+/// Recovers the input from zoned shares, failing with `ShamirError::NotEnoughShares` when a zone or
+/// the split as a whole has fewer shares than its threshold
+pub fn from_zoned_shares(input: Vec<Vec<u8>>) -> Result<Vec<u8>> {
+    let mut shares: Vec<ZonedShare> = Vec::new();
+    for share in input.iter() {
+        shares.push(bincode::deserialize(share)?);
+    }
+
+    if shares.is_empty() {
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: 0,
+            need: None,
+        })
+        .context("No shares were given"));
+    }
+
+    // Check if all shares are of the same split
+    let layout = &shares[0].layout;
+    let data = &shares[0].data;
+    for share in shares.iter() {
+        if &share.layout != layout || &share.data != data {
+            return Err(anyhow::anyhow!("Shares do not match"));
+        }
+        if !layout.zones.iter().any(|(tag, _)| *tag == share.zone) {
+            return Err(
+                anyhow::Error::new(ShamirError::InconsistentShares).context(format!(
+                    "Zone \"{}\" is not a zone of the split",
+                    share.zone
+                )),
+            );
+        }
+    }
+
+    // Exact copies of a share add nothing
+    let mut unique: Vec<&ZonedShare> = Vec::new();
+    for share in shares.iter() {
+        if !unique
+            .iter()
+            .any(|u| u.global_fragment == share.global_fragment)
+        {
+            unique.push(share);
+        }
+    }
+
+    if unique.len() < layout.global_threshold as usize {
+        return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+            have: unique.len(),
+            need: Some(layout.global_threshold as usize),
+        })
+        .context(format!(
+            "The split has {} of the {} shares it needs",
+            unique.len(),
+            layout.global_threshold
+        )));
+    }
+
+    let mut info_bytes = shamir::recover_secret(
+        unique
+            .iter()
+            .map(|s| s.global_fragment.clone())
+            .collect::<Vec<Vec<u8>>>(),
+    )?;
+
+    for (tag, threshold) in layout.zones.iter() {
+        let fragments = unique
+            .iter()
+            .filter(|s| s.zone == *tag)
+            .map(|s| s.zone_fragment.clone())
+            .collect::<Vec<Vec<u8>>>();
+        if fragments.len() < *threshold as usize {
+            info_bytes.zeroize();
+            return Err(anyhow::Error::new(ShamirError::NotEnoughShares {
+                have: fragments.len(),
+                need: Some(*threshold as usize),
+            })
+            .context(format!(
+                "Zone \"{}\" has {} of the {} shares it needs",
+                tag,
+                fragments.len(),
+                threshold
+            )));
+        }
+
+        let mut pad = shamir::recover_secret(fragments)?;
+        if pad.len() != info_bytes.len() {
+            info_bytes.zeroize();
+            return Err(ShamirError::InconsistentShares.into());
+        }
+        for (i, p) in info_bytes.iter_mut().zip(pad.iter()) {
+            *i ^= p;
+        }
+        pad.zeroize();
+    }
+
+    let info: Result<ZonedInfo, _> = bincode::deserialize(&info_bytes);
+    info_bytes.zeroize();
+    let info = info.map_err(|_| anyhow::anyhow!("Shares do not recover the share info"))?;
+
+    // Check if the encrypted data belongs to these shares
+    let data_hash: [u8; 32] = Sha3_256::digest(data).into();
+    if info.data_hash != data_hash {
+        return Err(anyhow::anyhow!(
+            "Encrypted data does not belong to these shares"
+        ));
+    }
+
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&info.key));
+    let mut plaintext = cipher
+        .decrypt(&info.nonce.into(), data.as_ref())
+        .map_err(|_| anyhow::anyhow!("Decryption failed"))?;
+
+    let hash: [u8; 32] = Sha3_256::digest(&plaintext).into();
+    if info.hash != hash || plaintext.len() != info.length {
+        plaintext.zeroize();
+        return Err(anyhow::anyhow!("Hashes do not match"));
+    }
+
+    Ok(plaintext)
+}
+
+/// The zone a zoned share belongs to, to tell which custodian is to keep it
+pub fn share_zone(share: &[u8]) -> Option<ZoneTag> {
+    bincode::deserialize::<ZonedShare>(share)
+        .ok()
+        .map(|share| share.zone)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zoned_error(shares: Vec<Vec<u8>>) -> Option<ShamirError> {
+        from_zoned_shares(shares)
+            .err()?
+            .downcast_ref::<ShamirError>()
+            .copied()
+    }
+
+    #[test]
+    fn every_zone_and_the_split_need_their_threshold() {
+        let zones = [("us-east".into(), 2, 3), ("eu-west".into(), 1, 2)];
+        let shares = to_zoned_shares(b"secret", 3, &zones).unwrap();
+        let (us, eu) = shares.split_at(3);
+        assert_eq!(share_zone(&eu[0]), Some("eu-west".into()));
+
+        let enough = vec![us[0].clone(), us[2].clone(), eu[1].clone()];
+        assert_eq!(from_zoned_shares(enough).unwrap(), b"secret");
+
+        let without_eu = us.to_vec();
+        assert_eq!(
+            zoned_error(without_eu),
+            Some(ShamirError::NotEnoughShares {
+                have: 0,
+                need: Some(1)
+            })
+        );
+        let one_us = vec![us[1].clone(), eu[0].clone(), eu[1].clone()];
+        assert_eq!(
+            zoned_error(one_us),
+            Some(ShamirError::NotEnoughShares {
+                have: 1,
+                need: Some(2)
+            })
+        );
+        let too_few = vec![us[0].clone(), us[1].clone()];
+        assert_eq!(
+            zoned_error(too_few),
+            Some(ShamirError::NotEnoughShares {
+                have: 2,
+                need: Some(3)
+            })
+        );
+    }
+}
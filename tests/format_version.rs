@@ -0,0 +1,23 @@
+use std::process::Command;
+
+use distributed_encrypt::wrapper::{FORMAT_VERSION, MIN_FORMAT_VERSION};
+
+#[test]
+fn format_version_lists_the_share_format_versions() {
+    let output = Command::new(env!("CARGO_BIN_EXE_distributed_encrypt"))
+        .arg("format-version")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let report = String::from_utf8(output.stdout).unwrap();
+    assert!(report.starts_with(&format!(
+        "distributed_encrypt {}\n",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert!(report.contains(&format!("Writes share format version {}\n", FORMAT_VERSION)));
+    assert!(report.contains(&format!(
+        "Reads share format versions {} to {}\n",
+        MIN_FORMAT_VERSION, FORMAT_VERSION
+    )));
+}
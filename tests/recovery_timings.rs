@@ -0,0 +1,14 @@
+use distributed_encrypt::wrapper::{from_shares_timed, to_shares};
+
+#[test]
+fn every_phase_of_a_large_recovery_is_timed() {
+    let secret: Vec<u8> = (0..256 * 1024).map(|i| (i % 251) as u8).collect();
+    let shares = to_shares(secret.clone(), 10, 12).unwrap();
+
+    let (recovered, timings) = from_shares_timed(shares[..10].to_vec()).unwrap();
+    assert_eq!(recovered, secret);
+    assert!(timings.deserialization_us > 0);
+    assert!(timings.interpolation_us > 0);
+    assert!(timings.aes_decryption_us > 0);
+    assert!(timings.hash_verification_us > 0);
+}
@@ -0,0 +1,36 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use distributed_encrypt::shamir::{recover_secret, SecretData, MAX_SHARES};
+
+#[test]
+fn iterating_yields_every_share_up_to_the_count() {
+    let secret_data = SecretData::with_secret_and_count(b"secret".to_vec(), 3, 5).unwrap();
+    let shares = secret_data
+        .into_iter()
+        .collect::<Result<HashMap<u8, Vec<u8>>>>()
+        .unwrap();
+    assert_eq!(shares.len(), 5);
+    assert!((1..=5).all(|i| shares[&i][0] == i));
+
+    let chosen = [2, 4, 5].iter().map(|i| shares[i].clone()).collect();
+    assert_eq!(recover_secret(chosen).unwrap(), b"secret");
+}
+
+#[test]
+fn iterating_without_a_count_yields_every_share_of_the_field() {
+    let secret_data = SecretData::with_secret(b"secret".to_vec(), 2).unwrap();
+    let shares = secret_data.into_iter();
+    assert_eq!(shares.size_hint(), (255, Some(255)));
+
+    let indices = shares.map(|share| share.unwrap().0).collect::<Vec<u8>>();
+    assert_eq!(indices, (1..=MAX_SHARES).collect::<Vec<u8>>());
+
+    let shares = SecretData::with_secret(b"secret".to_vec(), 2)
+        .unwrap()
+        .into_iter()
+        .take(2)
+        .map(|share| share.unwrap().1)
+        .collect();
+    assert_eq!(recover_secret(shares).unwrap(), b"secret");
+}